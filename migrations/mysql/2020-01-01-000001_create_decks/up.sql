@@ -0,0 +1,10 @@
+CREATE TABLE decks (
+  id BIGINT UNSIGNED NOT NULL PRIMARY KEY,
+  name VARCHAR(255) NOT NULL,
+  owner_id BIGINT UNSIGNED NOT NULL,
+  last_updated INT UNSIGNED NOT NULL,
+  scheduler TINYINT UNSIGNED NOT NULL DEFAULT 0,
+  visibility TINYINT UNSIGNED NOT NULL DEFAULT 0,
+  INDEX decks_owner (owner_id),
+  INDEX decks_visibility_name (visibility, name)
+);
@@ -0,0 +1,3 @@
+DROP VIEW card_pos_desc;
+DROP VIEW card_pos_asc;
+DROP TABLE cards;
@@ -0,0 +1,20 @@
+-- position 0 is reserved as scratch space while moving a card,
+-- so (deck_id, deck_pos) stays unique for the whole move
+CREATE TABLE cards (
+  id BIGINT UNSIGNED NOT NULL PRIMARY KEY,
+  deck_id BIGINT UNSIGNED NOT NULL,
+  deck_pos SMALLINT UNSIGNED NOT NULL,
+  question VARCHAR(1024) NOT NULL,
+  answer VARCHAR(1024) NOT NULL,
+  last_updated INT UNSIGNED NOT NULL,
+  UNIQUE KEY cards_deck_pos (deck_id, deck_pos),
+  CONSTRAINT cards_deck FOREIGN KEY (deck_id) REFERENCES decks (id) ON DELETE CASCADE
+);
+
+-- updates through these views run in position order, so shifting a range of cards
+-- never collides with the unique (deck_id, deck_pos) key
+CREATE ALGORITHM = MERGE VIEW card_pos_asc AS
+  SELECT id, deck_id, deck_pos FROM cards ORDER BY deck_pos ASC;
+
+CREATE ALGORITHM = MERGE VIEW card_pos_desc AS
+  SELECT id, deck_id, deck_pos FROM cards ORDER BY deck_pos DESC;
@@ -0,0 +1,15 @@
+CREATE TABLE cardscores (
+  account_id BIGINT UNSIGNED NOT NULL,
+  card_id BIGINT UNSIGNED NOT NULL,
+  score TINYINT UNSIGNED NOT NULL,
+  ease_factor FLOAT NOT NULL,
+  `interval` INT UNSIGNED NOT NULL,
+  repetitions SMALLINT UNSIGNED NOT NULL,
+  due INT UNSIGNED NOT NULL,
+  last_reviewed INT UNSIGNED NOT NULL,
+  stability FLOAT NOT NULL,
+  difficulty FLOAT NOT NULL,
+  PRIMARY KEY (account_id, card_id),
+  INDEX cardscores_due (account_id, due),
+  CONSTRAINT cardscores_card FOREIGN KEY (card_id) REFERENCES cards (id) ON DELETE CASCADE
+);
@@ -0,0 +1,13 @@
+CREATE TABLE reviews (
+  id BIGINT UNSIGNED NOT NULL PRIMARY KEY,
+  account_id BIGINT UNSIGNED NOT NULL,
+  card_id BIGINT UNSIGNED NOT NULL,
+  grade TINYINT UNSIGNED NOT NULL,
+  answered_at INT UNSIGNED NOT NULL,
+  response_ms INT UNSIGNED NULL,
+  scheduler TINYINT UNSIGNED NOT NULL,
+  state_before TEXT NOT NULL,
+  state_after TEXT NOT NULL,
+  INDEX reviews_account_card (account_id, card_id, answered_at),
+  CONSTRAINT reviews_card FOREIGN KEY (card_id) REFERENCES cards (id) ON DELETE CASCADE
+);
@@ -0,0 +1,11 @@
+CREATE TABLE study_sessions (
+  id BIGINT UNSIGNED NOT NULL PRIMARY KEY,
+  deck_id BIGINT UNSIGNED NOT NULL,
+  account_id BIGINT UNSIGNED NOT NULL,
+  started INT UNSIGNED NOT NULL,
+  ended INT UNSIGNED NULL,
+  cards_seen SMALLINT UNSIGNED NOT NULL DEFAULT 0,
+  correct_count SMALLINT UNSIGNED NOT NULL DEFAULT 0,
+  INDEX study_sessions_account_deck (account_id, deck_id),
+  CONSTRAINT study_sessions_deck FOREIGN KEY (deck_id) REFERENCES decks (id) ON DELETE CASCADE
+);
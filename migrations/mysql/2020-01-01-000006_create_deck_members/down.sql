@@ -0,0 +1 @@
+DROP TABLE deck_members;
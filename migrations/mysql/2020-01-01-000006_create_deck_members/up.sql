@@ -0,0 +1,8 @@
+CREATE TABLE deck_members (
+  deck_id BIGINT UNSIGNED NOT NULL,
+  account_id BIGINT UNSIGNED NOT NULL,
+  role TINYINT UNSIGNED NOT NULL,
+  PRIMARY KEY (deck_id, account_id),
+  INDEX deck_members_account (account_id),
+  CONSTRAINT deck_members_deck FOREIGN KEY (deck_id) REFERENCES decks (id) ON DELETE CASCADE
+);
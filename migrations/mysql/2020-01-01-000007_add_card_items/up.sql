@@ -0,0 +1,10 @@
+-- a card is studied as one or more items, e.g. one per cloze deletion, each scored on its own.
+-- existing cards are basic cards, whose only item is 0
+ALTER TABLE cards ADD COLUMN kind TINYINT UNSIGNED NOT NULL DEFAULT 0;
+
+ALTER TABLE cardscores
+  ADD COLUMN item SMALLINT UNSIGNED NOT NULL DEFAULT 0 AFTER card_id,
+  DROP PRIMARY KEY,
+  ADD PRIMARY KEY (account_id, card_id, item);
+
+ALTER TABLE reviews ADD COLUMN item SMALLINT UNSIGNED NOT NULL DEFAULT 0 AFTER card_id;
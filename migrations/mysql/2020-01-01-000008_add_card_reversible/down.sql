@@ -0,0 +1,4 @@
+DELETE cardscores FROM cardscores
+  JOIN cards ON cards.id = cardscores.card_id
+  WHERE cards.kind = 0 AND cardscores.item <> 0;
+ALTER TABLE cards DROP COLUMN reversible;
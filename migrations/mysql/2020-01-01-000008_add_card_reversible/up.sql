@@ -0,0 +1,2 @@
+-- reversible basic cards are also studied answer -> question, as item 1
+ALTER TABLE cards ADD COLUMN reversible BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,8 @@
+-- the wrong options of multiple choice cards, see card::choice
+CREATE TABLE card_distractors (
+  card_id BIGINT UNSIGNED NOT NULL,
+  position SMALLINT UNSIGNED NOT NULL,
+  text VARCHAR(1024) NOT NULL,
+  PRIMARY KEY (card_id, position),
+  CONSTRAINT card_distractors_card FOREIGN KEY (card_id) REFERENCES cards (id) ON DELETE CASCADE
+);
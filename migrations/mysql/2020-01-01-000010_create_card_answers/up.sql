@@ -0,0 +1,8 @@
+-- the other accepted answers of typed-answer cards, see card::typed
+CREATE TABLE card_answers (
+  card_id BIGINT UNSIGNED NOT NULL,
+  position SMALLINT UNSIGNED NOT NULL,
+  text VARCHAR(1024) NOT NULL,
+  PRIMARY KEY (card_id, position),
+  CONSTRAINT card_answers_card FOREIGN KEY (card_id) REFERENCES cards (id) ON DELETE CASCADE
+);
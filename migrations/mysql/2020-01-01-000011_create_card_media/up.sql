@@ -0,0 +1,10 @@
+-- images and audio attached to cards, see media. the bytes are kept in a media::MediaStore
+CREATE TABLE card_media (
+  id BIGINT UNSIGNED NOT NULL PRIMARY KEY,
+  card_id BIGINT UNSIGNED NOT NULL,
+  side TINYINT UNSIGNED NOT NULL,
+  content_type VARCHAR(64) NOT NULL,
+  byte_size INT UNSIGNED NOT NULL,
+  uploaded INT UNSIGNED NOT NULL,
+  CONSTRAINT card_media_card FOREIGN KEY (card_id) REFERENCES cards (id) ON DELETE CASCADE
+);
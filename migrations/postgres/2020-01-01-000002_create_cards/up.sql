@@ -0,0 +1,11 @@
+-- deferrable so a range of cards can be shifted, or the constraint deferred
+-- to the end of the transaction while a card is moved
+CREATE TABLE cards (
+  id BIGINT NOT NULL PRIMARY KEY,
+  deck_id BIGINT NOT NULL REFERENCES decks (id) ON DELETE CASCADE,
+  deck_pos INTEGER NOT NULL,
+  question TEXT NOT NULL,
+  answer TEXT NOT NULL,
+  last_updated BIGINT NOT NULL,
+  CONSTRAINT cards_deck_pos UNIQUE (deck_id, deck_pos) DEFERRABLE INITIALLY IMMEDIATE
+);
@@ -0,0 +1,13 @@
+CREATE TABLE reviews (
+  id BIGINT NOT NULL PRIMARY KEY,
+  account_id BIGINT NOT NULL,
+  card_id BIGINT NOT NULL REFERENCES cards (id) ON DELETE CASCADE,
+  grade SMALLINT NOT NULL,
+  answered_at BIGINT NOT NULL,
+  response_ms BIGINT,
+  scheduler SMALLINT NOT NULL,
+  state_before TEXT NOT NULL,
+  state_after TEXT NOT NULL
+);
+CREATE INDEX reviews_account_card ON reviews (account_id, card_id, answered_at);
+CREATE INDEX reviews_card ON reviews (card_id);
@@ -0,0 +1,11 @@
+CREATE TABLE study_sessions (
+  id BIGINT NOT NULL PRIMARY KEY,
+  deck_id BIGINT NOT NULL REFERENCES decks (id) ON DELETE CASCADE,
+  account_id BIGINT NOT NULL,
+  started BIGINT NOT NULL,
+  ended BIGINT,
+  cards_seen INTEGER NOT NULL DEFAULT 0,
+  correct_count INTEGER NOT NULL DEFAULT 0
+);
+CREATE INDEX study_sessions_account_deck ON study_sessions (account_id, deck_id);
+CREATE INDEX study_sessions_deck ON study_sessions (deck_id);
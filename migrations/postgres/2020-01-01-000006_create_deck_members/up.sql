@@ -0,0 +1,7 @@
+CREATE TABLE deck_members (
+  deck_id BIGINT NOT NULL REFERENCES decks (id) ON DELETE CASCADE,
+  account_id BIGINT NOT NULL,
+  role SMALLINT NOT NULL,
+  PRIMARY KEY (deck_id, account_id)
+);
+CREATE INDEX deck_members_account ON deck_members (account_id);
@@ -0,0 +1,7 @@
+ALTER TABLE reviews DROP COLUMN item;
+DELETE FROM cardscores WHERE item <> 0;
+ALTER TABLE cardscores
+  DROP CONSTRAINT cardscores_pkey,
+  DROP COLUMN item,
+  ADD PRIMARY KEY (account_id, card_id);
+ALTER TABLE cards DROP COLUMN kind;
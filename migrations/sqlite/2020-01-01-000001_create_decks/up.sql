@@ -0,0 +1,10 @@
+CREATE TABLE decks (
+  id BIGINT NOT NULL PRIMARY KEY,
+  name TEXT NOT NULL,
+  owner_id BIGINT NOT NULL,
+  last_updated BIGINT NOT NULL,
+  scheduler SMALLINT NOT NULL DEFAULT 0,
+  visibility SMALLINT NOT NULL DEFAULT 0
+);
+CREATE INDEX decks_owner ON decks (owner_id);
+CREATE INDEX decks_visibility_name ON decks (visibility, name);
@@ -0,0 +1,11 @@
+-- position 0 is reserved as scratch space while moving a card,
+-- and negative positions are used while shifting a range of cards
+CREATE TABLE cards (
+  id BIGINT NOT NULL PRIMARY KEY,
+  deck_id BIGINT NOT NULL REFERENCES decks (id) ON DELETE CASCADE,
+  deck_pos INTEGER NOT NULL,
+  question TEXT NOT NULL,
+  answer TEXT NOT NULL,
+  last_updated BIGINT NOT NULL,
+  UNIQUE (deck_id, deck_pos)
+);
@@ -0,0 +1,15 @@
+CREATE TABLE cardscores (
+  account_id BIGINT NOT NULL,
+  card_id BIGINT NOT NULL REFERENCES cards (id) ON DELETE CASCADE,
+  score SMALLINT NOT NULL,
+  ease_factor FLOAT NOT NULL,
+  interval BIGINT NOT NULL,
+  repetitions INTEGER NOT NULL,
+  due BIGINT NOT NULL,
+  last_reviewed BIGINT NOT NULL,
+  stability FLOAT NOT NULL,
+  difficulty FLOAT NOT NULL,
+  PRIMARY KEY (account_id, card_id)
+);
+CREATE INDEX cardscores_due ON cardscores (account_id, due);
+CREATE INDEX cardscores_card ON cardscores (card_id);
@@ -0,0 +1,25 @@
+CREATE TABLE cardscores_old (
+  account_id BIGINT NOT NULL,
+  card_id BIGINT NOT NULL REFERENCES cards (id) ON DELETE CASCADE,
+  score SMALLINT NOT NULL,
+  ease_factor FLOAT NOT NULL,
+  interval BIGINT NOT NULL,
+  repetitions INTEGER NOT NULL,
+  due BIGINT NOT NULL,
+  last_reviewed BIGINT NOT NULL,
+  stability FLOAT NOT NULL,
+  difficulty FLOAT NOT NULL,
+  PRIMARY KEY (account_id, card_id)
+);
+INSERT INTO cardscores_old (account_id, card_id, score, ease_factor, interval, repetitions,
+    due, last_reviewed, stability, difficulty)
+  SELECT account_id, card_id, score, ease_factor, interval, repetitions,
+    due, last_reviewed, stability, difficulty
+  FROM cardscores WHERE item = 0;
+DROP TABLE cardscores;
+ALTER TABLE cardscores_old RENAME TO cardscores;
+CREATE INDEX cardscores_due ON cardscores (account_id, due);
+CREATE INDEX cardscores_card ON cardscores (card_id);
+
+ALTER TABLE reviews DROP COLUMN item;
+ALTER TABLE cards DROP COLUMN kind;
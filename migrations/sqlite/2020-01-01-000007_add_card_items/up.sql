@@ -0,0 +1,29 @@
+-- a card is studied as one or more items, e.g. one per cloze deletion, each scored on its own.
+-- existing cards are basic cards, whose only item is 0
+ALTER TABLE cards ADD COLUMN kind SMALLINT NOT NULL DEFAULT 0;
+ALTER TABLE reviews ADD COLUMN item INTEGER NOT NULL DEFAULT 0;
+
+-- SQLite can't change a primary key, so cardscores is rebuilt
+CREATE TABLE cardscores_new (
+  account_id BIGINT NOT NULL,
+  card_id BIGINT NOT NULL REFERENCES cards (id) ON DELETE CASCADE,
+  item INTEGER NOT NULL DEFAULT 0,
+  score SMALLINT NOT NULL,
+  ease_factor FLOAT NOT NULL,
+  interval BIGINT NOT NULL,
+  repetitions INTEGER NOT NULL,
+  due BIGINT NOT NULL,
+  last_reviewed BIGINT NOT NULL,
+  stability FLOAT NOT NULL,
+  difficulty FLOAT NOT NULL,
+  PRIMARY KEY (account_id, card_id, item)
+);
+INSERT INTO cardscores_new (account_id, card_id, score, ease_factor, interval, repetitions,
+    due, last_reviewed, stability, difficulty)
+  SELECT account_id, card_id, score, ease_factor, interval, repetitions,
+    due, last_reviewed, stability, difficulty
+  FROM cardscores;
+DROP TABLE cardscores;
+ALTER TABLE cardscores_new RENAME TO cardscores;
+CREATE INDEX cardscores_due ON cardscores (account_id, due);
+CREATE INDEX cardscores_card ON cardscores (card_id);
@@ -0,0 +1,3 @@
+DELETE FROM cardscores
+  WHERE item <> 0 AND card_id IN (SELECT id FROM cards WHERE kind = 0);
+ALTER TABLE cards DROP COLUMN reversible;
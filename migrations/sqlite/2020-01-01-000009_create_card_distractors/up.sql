@@ -0,0 +1,7 @@
+-- the wrong options of multiple choice cards, see card::choice
+CREATE TABLE card_distractors (
+  card_id BIGINT NOT NULL REFERENCES cards (id) ON DELETE CASCADE,
+  position INTEGER NOT NULL,
+  text TEXT NOT NULL,
+  PRIMARY KEY (card_id, position)
+);
@@ -0,0 +1,7 @@
+-- the other accepted answers of typed-answer cards, see card::typed
+CREATE TABLE card_answers (
+  card_id BIGINT NOT NULL REFERENCES cards (id) ON DELETE CASCADE,
+  position INTEGER NOT NULL,
+  text TEXT NOT NULL,
+  PRIMARY KEY (card_id, position)
+);
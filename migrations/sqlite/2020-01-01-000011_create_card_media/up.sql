@@ -0,0 +1,10 @@
+-- images and audio attached to cards, see media. the bytes are kept in a media::MediaStore
+CREATE TABLE card_media (
+  id BIGINT NOT NULL PRIMARY KEY,
+  card_id BIGINT NOT NULL REFERENCES cards (id) ON DELETE CASCADE,
+  side SMALLINT NOT NULL,
+  content_type TEXT NOT NULL,
+  byte_size BIGINT NOT NULL,
+  uploaded BIGINT NOT NULL
+);
+CREATE INDEX card_media_card ON card_media (card_id);
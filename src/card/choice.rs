@@ -0,0 +1,96 @@
+// Multiple choice: a basic card with distractors is studied by picking its answer out of
+// the answer mixed in with the distractors, the wrong options kept in 'card_distractors'.
+// The server grades the picked option, see FlashManager::answer_choice.
+// Only question -> answer is multiple choice, the reverse of a reversible card is answered freely.
+
+use crate::game::CardScore;
+use crate::schema::card_distractors;
+
+use diesel::prelude::*;
+use serde::Serialize;
+
+// most distractors a card can have
+pub const MAX_DISTRACTORS: usize = 9;
+// grades recorded for a picked option, see game::scheduler::PASSING_GRADE
+pub const CORRECT_GRADE: u8 = 4;
+pub const INCORRECT_GRADE: u8 = 1;
+
+#[derive(Serialize, Insertable, Queryable, Clone, PartialEq, Debug)]
+#[table_name = "card_distractors"]
+pub struct Distractor {
+    #[serde(serialize_with = "webe_auth::utility::serialize_as_string")]
+    pub card_id: u64,
+    pub position: u16, // the order the editor listed them in, from 0
+    pub text: String,
+}
+
+impl Distractor {
+    pub fn new(card_id: u64, position: u16, text: String) -> Distractor {
+        Distractor {
+            card_id: card_id,
+            position: position,
+            text: text,
+        }
+    }
+}
+
+// the card's distractors from the editor's list, trimmed and in order.
+// None if any of them is empty, listed twice or the same as the answer, or there are too many
+pub fn from_texts(card_id: u64, answer: &str, texts: Vec<String>) -> Option<Vec<Distractor>> {
+    let texts = super::clean_texts(answer, texts, MAX_DISTRACTORS)?;
+    let distractors = texts
+        .into_iter()
+        .enumerate()
+        .map(|(position, text)| Distractor::new(card_id, position as u16, text))
+        .collect();
+    return Some(distractors);
+}
+
+// the answer and the distractors in an order picked by 'seed'
+pub fn options<'a, I>(answer: &str, distractors: I, seed: u64) -> Vec<String>
+where
+    I: IntoIterator<Item = &'a Distractor>,
+{
+    let mut options = vec![answer.to_owned()];
+    options.extend(
+        distractors
+            .into_iter()
+            .map(|distractor| distractor.text.clone()),
+    );
+    // Fisher-Yates, drawing from a splitmix64 sequence. it only has to keep the answer
+    // from always being in the same place, so there is no need for a secure generator
+    let mut state = seed;
+    for index in (1..options.len()).rev() {
+        state = state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut bits = state;
+        bits = (bits ^ (bits >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        bits = (bits ^ (bits >> 27)).wrapping_mul(0x94D049BB133111EB);
+        bits ^= bits >> 31;
+        options.swap(index, (bits % (index as u64 + 1)) as usize);
+    }
+    return options;
+}
+
+// Some(true) if 'choice' is the answer, Some(false) for a distractor,
+// and None if it isn't one of the card's options at all
+pub fn grade(answer: &str, distractors: &[Distractor], choice: &str) -> Option<bool> {
+    let choice = choice.trim();
+    if choice == answer.trim() {
+        return Some(true);
+    }
+    if distractors
+        .iter()
+        .any(|distractor| distractor.text == choice)
+    {
+        return Some(false);
+    }
+    return None;
+}
+
+// what FlashManager::answer_choice recorded for the picked option
+#[derive(Serialize, Debug)]
+pub struct ChoiceResult {
+    pub correct: bool,
+    pub answer: String, // the right option, to show when the pick was wrong
+    pub score: CardScore,
+}
@@ -0,0 +1,126 @@
+// Cloze deletions, e.g. "The capital of {{c1::France}} is {{c2::Paris}}"
+// Every deletion number is one reviewable item: c1 asks for 'France', c2 asks for 'Paris'.
+// Deletions that share a number are hidden together, and an optional hint is shown
+// in place of the hidden text: "{{c1::France::country}}" asks "The capital of [country] is Paris".
+// Deletions can't be nested. Anything that isn't a well formed deletion is kept as plain text.
+
+// shown in place of a hidden deletion without a hint
+pub const HIDDEN: &str = "[...]";
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Segment<'a> {
+    Text(&'a str),
+    Deletion {
+        number: u16, // 1 or more
+        text: &'a str,
+        hint: Option<&'a str>,
+    },
+}
+
+// split the note text into plain text and deletions, in order
+pub fn parse(text: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut text_start = 0; // start of plain text not added yet
+    let mut search_from = 0;
+    while let Some(found) = text[search_from..].find("{{c") {
+        let start = search_from + found;
+        match parse_deletion(&text[start..]) {
+            Some((deletion, len)) => {
+                if start > text_start {
+                    segments.push(Segment::Text(&text[text_start..start]));
+                }
+                segments.push(deletion);
+                text_start = start + len;
+                search_from = text_start;
+            }
+            // not a deletion, keep the braces as plain text
+            None => search_from = start + 2,
+        }
+    }
+    if text_start < text.len() {
+        segments.push(Segment::Text(&text[text_start..]));
+    }
+    return segments;
+}
+
+// a deletion at the start of 'text', and how many bytes it takes up
+fn parse_deletion(text: &str) -> Option<(Segment<'_>, usize)> {
+    let after_c = text.strip_prefix("{{c")?;
+    let digits = after_c.find(|c: char| !c.is_ascii_digit())?;
+    let number: u16 = after_c[..digits].parse().ok()?;
+    if number == 0 {
+        return None;
+    }
+    let body = after_c[digits..].strip_prefix("::")?;
+    let end = body.find("}}")?;
+    let (hidden, hint) = match body[..end].find("::") {
+        Some(split) => (&body[..split], Some(&body[split + 2..end])),
+        None => (&body[..end], None),
+    };
+    if hidden.is_empty() {
+        return None;
+    }
+    let deletion = Segment::Deletion {
+        number: number,
+        text: hidden,
+        hint: hint,
+    };
+    let len = "{{c".len() + digits + "::".len() + end + "}}".len();
+    return Some((deletion, len));
+}
+
+// the distinct deletion numbers in the note text, lowest first
+pub fn numbers(text: &str) -> Vec<u16> {
+    let mut numbers: Vec<u16> = parse(text)
+        .into_iter()
+        .filter_map(|segment| match segment {
+            Segment::Deletion { number, .. } => Some(number),
+            Segment::Text(_text) => None,
+        })
+        .collect();
+    numbers.sort();
+    numbers.dedup();
+    return numbers;
+}
+
+// the question and answer for one deletion number, None if the text doesn't have it.
+// the question hides every deletion with that number and shows all the others,
+// the answer shows the whole text
+pub fn render(text: &str, number: u16) -> Option<(String, String)> {
+    let segments = parse(text);
+    let mut question = String::with_capacity(text.len());
+    let mut answer = String::with_capacity(text.len());
+    let mut found = false;
+    for segment in segments {
+        match segment {
+            Segment::Text(text) => {
+                question.push_str(text);
+                answer.push_str(text);
+            }
+            Segment::Deletion {
+                number: deletion_number,
+                text,
+                hint,
+            } => {
+                if deletion_number == number {
+                    found = true;
+                    match hint {
+                        Some(hint) => {
+                            question.push('[');
+                            question.push_str(hint);
+                            question.push(']');
+                        }
+                        None => question.push_str(HIDDEN),
+                    }
+                } else {
+                    question.push_str(text);
+                }
+                answer.push_str(text);
+            }
+        }
+    }
+    if !found {
+        return None;
+    }
+    return Some((question, answer));
+}
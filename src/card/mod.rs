@@ -0,0 +1,166 @@
+use crate::schema::cards;
+
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize, Serializer};
+
+use std::time::{SystemTime, SystemTimeError, UNIX_EPOCH};
+
+pub mod choice;
+pub mod cloze;
+pub mod typed;
+
+#[derive(Serialize, AsChangeset, Identifiable, Insertable, Queryable, Clone, Debug)]
+#[table_name = "cards"]
+pub struct Card {
+    #[serde(serialize_with = "webe_auth::utility::serialize_as_string")]
+    pub id: u64,
+    #[serde(serialize_with = "webe_auth::utility::serialize_as_string")]
+    pub deck_id: u64,
+    pub deck_pos: u16,
+    pub(crate) question: String,
+    pub(crate) answer: String,
+    pub(crate) last_updated: u32,
+    #[serde(serialize_with = "serialize_kind_id")]
+    pub(crate) kind: u8,
+    pub(crate) reversible: bool, // basic cards only, also study answer -> question
+}
+
+// How a card is studied. Scores and reviews are kept per reviewable item of a card, see Card::items
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum CardKind {
+    #[default]
+    Basic = 0, // 'question' asks for 'answer', and also the other way around if reversible
+    Cloze = 1, // one item per deletion number in 'question', 'answer' holds optional extra notes
+}
+
+impl CardKind {
+    pub fn from_id(id: u8) -> Option<CardKind> {
+        match id {
+            0 => Some(CardKind::Basic),
+            1 => Some(CardKind::Cloze),
+            _ => None,
+        }
+    }
+
+    pub fn id(&self) -> u8 {
+        *self as u8
+    }
+}
+
+fn serialize_kind_id<S>(id: &u8, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match CardKind::from_id(*id) {
+        Some(kind) => kind.serialize(serializer),
+        None => serializer.serialize_u8(*id),
+    }
+}
+
+// the extra texts an editor listed for a basic card, e.g. distractors, trimmed and in order.
+// None if any of them is empty, listed twice or the same as the answer, or there are over 'max'
+pub(crate) fn clean_texts(answer: &str, texts: Vec<String>, max: usize) -> Option<Vec<String>> {
+    if texts.len() > max {
+        return None;
+    }
+    let mut cleaned: Vec<String> = Vec::with_capacity(texts.len());
+    for text in texts {
+        let text = text.trim();
+        if text.is_empty() || text == answer.trim() || cleaned.iter().any(|other| other == text) {
+            return None;
+        }
+        cleaned.push(text.to_owned());
+    }
+    return Some(cleaned);
+}
+
+// the only item of a basic card, question -> answer
+pub const BASIC_ITEM: u16 = 0;
+// the second item of a reversible basic card, answer -> question
+pub const REVERSE_ITEM: u16 = 1;
+
+impl Card {
+    pub fn new(
+        id: u64,
+        deck_id: u64,
+        deck_pos: u16,
+        question: String,
+        answer: String,
+    ) -> Result<Card, SystemTimeError> {
+        let now: u32 = match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(n) => n.as_secs() as u32,
+            Err(err) => return Err(err),
+        };
+        return Ok(Card {
+            id: id,
+            deck_id: deck_id,
+            deck_pos: deck_pos,
+            question: question,
+            answer: answer,
+            last_updated: now,
+            kind: CardKind::Basic.id(),
+            reversible: false,
+        });
+    }
+
+    pub fn question(&self) -> &str {
+        &self.question
+    }
+    pub fn answer(&self) -> &str {
+        &self.answer
+    }
+
+    // cards with an unrecognized kind id are studied as basic cards
+    pub fn kind(&self) -> CardKind {
+        CardKind::from_id(self.kind).unwrap_or(CardKind::Basic)
+    }
+
+    pub fn set_kind(&mut self, kind: CardKind) {
+        self.kind = kind.id();
+    }
+
+    pub fn is_reversible(&self) -> bool {
+        self.reversible
+    }
+
+    // study a basic card in both directions, each scored on its own.
+    // both directions share the same question and answer, so edits always apply to both
+    pub fn set_reversible(&mut self, reversible: bool) {
+        self.reversible = reversible;
+    }
+
+    // the reviewable items of this card, each scored on its own.
+    // a cloze card without any deletions has nothing to review
+    pub fn items(&self) -> Vec<u16> {
+        match self.kind() {
+            CardKind::Basic if self.reversible => vec![BASIC_ITEM, REVERSE_ITEM],
+            CardKind::Basic => vec![BASIC_ITEM],
+            CardKind::Cloze => cloze::numbers(&self.question),
+        }
+    }
+
+    // the question and answer shown when studying one of the card's items
+    pub fn item_text(&self, item: u16) -> Option<(String, String)> {
+        match self.kind() {
+            CardKind::Basic if item == BASIC_ITEM => {
+                Some((self.question.clone(), self.answer.clone()))
+            }
+            CardKind::Basic if item == REVERSE_ITEM && self.reversible => {
+                Some((self.answer.clone(), self.question.clone()))
+            }
+            CardKind::Basic => None,
+            CardKind::Cloze => cloze::render(&self.question, item),
+        }
+    }
+
+    pub fn update_position(&mut self, new_pos: u16) {
+        self.deck_pos = new_pos;
+    }
+    pub fn update_question(&mut self, new_question: String) {
+        self.question = new_question.to_owned();
+    }
+    pub fn update_answer(&mut self, new_answer: String) {
+        self.answer = new_answer.to_owned();
+    }
+}
@@ -0,0 +1,290 @@
+// Typed answers: the account types the answer of a basic card and it's checked here,
+// see FlashManager::answer_typed. The card's answer is always accepted, and alternatives
+// can be kept in 'card_answers', e.g. "colour" along with "color".
+// Texts are folded as set in config::TypingConfig before comparing, and the typed text is
+// graded by its Levenshtein distance to the closest accepted answer:
+//   5  the same, apart from whitespace
+//   4  the same once folded, e.g. different case or accents
+//   3  within the allowed typos, the lowest passing grade
+//   1  anything else, 0 if nothing was typed
+
+use crate::config::TypingConfig;
+use crate::game::CardScore;
+use crate::game::scheduler::{MAX_GRADE, PASSING_GRADE};
+use crate::schema::card_answers;
+
+use diesel::prelude::*;
+use serde::Serialize;
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::{decompose_canonical, is_combining_mark};
+
+// most alternative answers a card can have
+pub const MAX_ACCEPTED_ANSWERS: usize = 9;
+// longest typed answer that will be checked, the same as the longest card answer
+pub const MAX_TYPED_CHARS: usize = 1024;
+
+pub const FOLDED_GRADE: u8 = 4;
+pub const TYPO_GRADE: u8 = PASSING_GRADE;
+pub const WRONG_GRADE: u8 = 1;
+pub const BLANK_GRADE: u8 = 0;
+
+#[derive(Serialize, Insertable, Queryable, Clone, PartialEq, Debug)]
+#[table_name = "card_answers"]
+pub struct AcceptedAnswer {
+    #[serde(serialize_with = "webe_auth::utility::serialize_as_string")]
+    pub card_id: u64,
+    pub position: u16, // the order the editor listed them in, from 0
+    pub text: String,
+}
+
+impl AcceptedAnswer {
+    pub fn new(card_id: u64, position: u16, text: String) -> AcceptedAnswer {
+        AcceptedAnswer {
+            card_id: card_id,
+            position: position,
+            text: text,
+        }
+    }
+}
+
+// the card's alternative answers from the editor's list, trimmed and in order.
+// None if any of them is empty, listed twice or the same as the answer, or there are too many
+pub fn from_texts(card_id: u64, answer: &str, texts: Vec<String>) -> Option<Vec<AcceptedAnswer>> {
+    let texts = super::clean_texts(answer, texts, MAX_ACCEPTED_ANSWERS)?;
+    let answers = texts
+        .into_iter()
+        .enumerate()
+        .map(|(position, text)| AcceptedAnswer::new(card_id, position as u16, text))
+        .collect();
+    return Some(answers);
+}
+
+// how the typed text differs from the closest accepted answer, in order
+#[derive(Serialize, Clone, PartialEq, Debug)]
+#[serde(tag = "op", content = "text", rename_all = "lowercase")]
+pub enum DiffSegment {
+    Same(String),    // typed right, as written in the accepted answer
+    Extra(String),   // typed, but not in the accepted answer
+    Missing(String), // in the accepted answer, but not typed
+}
+
+#[derive(Serialize, Debug)]
+pub struct TypedCheck {
+    pub correct: bool, // graded at least PASSING_GRADE
+    pub grade: u8,
+    pub typos: usize,   // Levenshtein distance to 'answer', once folded
+    pub answer: String, // the closest accepted answer
+    pub diff: Vec<DiffSegment>,
+}
+
+// what FlashManager::answer_typed recorded for the typed answer
+#[derive(Serialize, Debug)]
+pub struct TypedResult {
+    #[serde(flatten)]
+    pub check: TypedCheck,
+    pub score: CardScore,
+}
+
+// a text folded for comparing, along with the char of the text each folded char came from
+struct Folded {
+    chars: Vec<char>,
+    originals: Vec<char>,
+}
+
+fn fold(text: &str, config: &TypingConfig) -> Folded {
+    let mut folded = Folded {
+        chars: Vec::with_capacity(text.len()),
+        originals: Vec::with_capacity(text.len()),
+    };
+    for original in text.chars() {
+        let mut folded_char = original;
+        if original.is_whitespace() {
+            // runs of whitespace count as one space, and there are none at either end
+            if folded.chars.last().is_some_and(|last| *last != ' ') {
+                folded.chars.push(' ');
+                folded.originals.push(' ');
+            }
+            continue;
+        }
+        if config.ignore_punctuation && !original.is_alphanumeric() {
+            continue;
+        }
+        if config.ignore_diacritics {
+            if is_combining_mark(original) {
+                continue;
+            }
+            folded_char = strip_marks(original);
+        }
+        if config.ignore_case {
+            folded_char = folded_char.to_lowercase().next().unwrap_or(folded_char);
+        }
+        folded.chars.push(folded_char);
+        folded.originals.push(original);
+    }
+    if folded.chars.last() == Some(&' ') {
+        folded.chars.pop();
+        folded.originals.pop();
+    }
+    return folded;
+}
+
+// the char without the marks it decomposes into, e.g. 'é' is 'e' and an acute accent,
+// and 'が' is 'か' and a voiced sound mark. every other part is kept, so a Hangul syllable,
+// which decomposes into letters (jamo) and no marks, is put back together as it was
+fn strip_marks(original: char) -> char {
+    let mut bases = String::new();
+    decompose_canonical(original, |part| {
+        if !is_combining_mark(part) {
+            bases.push(part);
+        }
+    });
+    let mut composed = bases.nfc();
+    match (composed.next(), composed.next()) {
+        (Some(base), None) => return base,
+        _ => return original,
+    }
+}
+
+// the edit distances between every prefix of 'expected' (rows) and of 'typed' (columns)
+fn distances(expected: &[char], typed: &[char]) -> Vec<Vec<usize>> {
+    let mut table = vec![vec![0; typed.len() + 1]; expected.len() + 1];
+    for row in 0..=expected.len() {
+        table[row][0] = row;
+    }
+    for column in 0..=typed.len() {
+        table[0][column] = column;
+    }
+    for row in 1..=expected.len() {
+        for column in 1..=typed.len() {
+            let substitution = if expected[row - 1] == typed[column - 1] {
+                0
+            } else {
+                1
+            };
+            table[row][column] = (table[row - 1][column - 1] + substitution)
+                .min(table[row - 1][column] + 1)
+                .min(table[row][column - 1] + 1);
+        }
+    }
+    return table;
+}
+
+enum Edit {
+    Same(char),
+    Extra(char),
+    Missing(char),
+}
+
+// walk back through the distances to find the edits, and merge them into segments.
+// the typos in a run of changes are listed before what should have been typed instead
+fn diff(expected: &Folded, typed: &Folded, table: &[Vec<usize>]) -> Vec<DiffSegment> {
+    let mut edits = Vec::new();
+    let (mut row, mut column) = (expected.chars.len(), typed.chars.len());
+    while row > 0 || column > 0 {
+        let current = table[row][column];
+        if row > 0
+            && column > 0
+            && expected.chars[row - 1] == typed.chars[column - 1]
+            && current == table[row - 1][column - 1]
+        {
+            edits.push(Edit::Same(expected.originals[row - 1]));
+            row -= 1;
+            column -= 1;
+        } else if row > 0 && column > 0 && current == table[row - 1][column - 1] + 1 {
+            edits.push(Edit::Missing(expected.originals[row - 1]));
+            edits.push(Edit::Extra(typed.originals[column - 1]));
+            row -= 1;
+            column -= 1;
+        } else if row > 0 && current == table[row - 1][column] + 1 {
+            edits.push(Edit::Missing(expected.originals[row - 1]));
+            row -= 1;
+        } else {
+            edits.push(Edit::Extra(typed.originals[column - 1]));
+            column -= 1;
+        }
+    }
+    edits.reverse();
+
+    let mut segments = Vec::new();
+    let (mut same, mut extra, mut missing) = (String::new(), String::new(), String::new());
+    for edit in edits {
+        match edit {
+            Edit::Same(original) => {
+                push_changes(&mut segments, &mut extra, &mut missing);
+                same.push(original);
+            }
+            Edit::Extra(original) => {
+                if !same.is_empty() {
+                    segments.push(DiffSegment::Same(std::mem::take(&mut same)));
+                }
+                extra.push(original);
+            }
+            Edit::Missing(original) => {
+                if !same.is_empty() {
+                    segments.push(DiffSegment::Same(std::mem::take(&mut same)));
+                }
+                missing.push(original);
+            }
+        }
+    }
+    push_changes(&mut segments, &mut extra, &mut missing);
+    if !same.is_empty() {
+        segments.push(DiffSegment::Same(same));
+    }
+    return segments;
+}
+
+fn push_changes(segments: &mut Vec<DiffSegment>, extra: &mut String, missing: &mut String) {
+    if !extra.is_empty() {
+        segments.push(DiffSegment::Extra(std::mem::take(extra)));
+    }
+    if !missing.is_empty() {
+        segments.push(DiffSegment::Missing(std::mem::take(missing)));
+    }
+}
+
+// check the typed text against the accepted answers, the card's own answer first.
+// ties go to the earlier answer
+pub fn check(typed: &str, accepted: &[&str], config: &TypingConfig) -> TypedCheck {
+    let typed_folded = fold(typed, config);
+    let mut closest: Option<(usize, &str, Folded, Vec<Vec<usize>>)> = None;
+    for answer in accepted {
+        let answer_folded = fold(answer, config);
+        let table = distances(&answer_folded.chars, &typed_folded.chars);
+        let typos = table[answer_folded.chars.len()][typed_folded.chars.len()];
+        if closest.as_ref().map_or(true, |(best, ..)| typos < *best) {
+            closest = Some((typos, answer, answer_folded, table));
+        }
+    }
+    let (typos, answer, answer_folded, table) = match closest {
+        Some(closest) => closest,
+        // nothing to accept, the same as a wrong answer
+        None => {
+            return TypedCheck {
+                correct: false,
+                grade: WRONG_GRADE,
+                typos: typed_folded.chars.len(),
+                answer: String::new(),
+                diff: Vec::new(),
+            };
+        }
+    };
+    let grade = if typed.trim().is_empty() {
+        BLANK_GRADE
+    } else if typed.split_whitespace().eq(answer.split_whitespace()) {
+        MAX_GRADE
+    } else if typos == 0 {
+        FOLDED_GRADE
+    } else if typos <= config.allowed_typos(answer_folded.chars.len()) {
+        TYPO_GRADE
+    } else {
+        WRONG_GRADE
+    };
+    return TypedCheck {
+        correct: grade >= PASSING_GRADE,
+        grade: grade,
+        typos: typos,
+        answer: answer.to_owned(),
+        diff: diff(&answer_folded, &typed_folded, &table),
+    };
+}
@@ -0,0 +1,418 @@
+// Deployment settings for a FlashManager, and a builder that wires up the connection pool
+// and ID generator from them.
+//
+// Settings are read from environment variables (FlashConfig::from_env) or a TOML file
+// (FlashConfig::from_toml_file). Everything except the database url has a default.
+//
+// TOML:                             environment:
+//   database_url = "mysql://..."      FLASH_DATABASE_URL
+//   new_cards_per_queue = 10          FLASH_NEW_CARDS_PER_QUEUE
+//   [pool]
+//   max_size = 10                     FLASH_POOL_MAX_SIZE
+//   min_idle = 2                      FLASH_POOL_MIN_IDLE (default: max_size)
+//   connection_timeout_secs = 30      FLASH_POOL_CONNECTION_TIMEOUT_SECS (must be above 0)
+//   test_on_check_out = true          FLASH_POOL_TEST_ON_CHECK_OUT
+//   [id]
+//   node_id = 0                       FLASH_ID_NODE_ID
+//   epoch_ms = 1546300800000          FLASH_ID_EPOCH_MS
+//   [typing]                          how typed answers are checked, see card::typed
+//   ignore_case = true                FLASH_TYPING_IGNORE_CASE
+//   ignore_diacritics = true          FLASH_TYPING_IGNORE_DIACRITICS
+//   ignore_punctuation = true         FLASH_TYPING_IGNORE_PUNCTUATION
+//   chars_per_typo = 6                FLASH_TYPING_CHARS_PER_TYPO
+//   max_typos = 2                     FLASH_TYPING_MAX_TYPOS
+//   [media]                           where card attachments are kept, see media
+//   dir = "media"                     FLASH_MEDIA_DIR
+//   max_bytes = 5242880               FLASH_MEDIA_MAX_BYTES
+
+use serde::Deserialize;
+
+use crate::FlashManager;
+use crate::db::{self, DBApiError};
+use crate::game::queue::DEFAULT_NEW_CARDS_PER_QUEUE;
+use crate::id::{IdError, IdGenerator};
+use crate::media::local::LocalMediaStore;
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const DEFAULT_POOL_MAX_SIZE: u32 = 10;
+pub const DEFAULT_CONNECTION_TIMEOUT_SECS: u64 = 30;
+pub const DEFAULT_ID_EPOCH_MS: u64 = 1546300800000; // 01-01-2019 12:00:00 AM GMT
+pub const DEFAULT_CHARS_PER_TYPO: u16 = 6;
+pub const DEFAULT_MAX_TYPOS: u16 = 2;
+pub const DEFAULT_MEDIA_DIR: &str = "media";
+pub const DEFAULT_MAX_MEDIA_BYTES: u32 = 5 * 1024 * 1024; // 5 MiB
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Missing(&'static str), // a required setting wasn't given
+    Invalid(&'static str), // a setting couldn't be parsed
+    IOError(std::io::Error),
+    TomlError(toml::de::Error),
+    DBError(DBApiError), // the connection pool couldn't be created
+    IdError(IdError),
+}
+
+impl From<std::io::Error> for ConfigError {
+    fn from(err: std::io::Error) -> ConfigError {
+        ConfigError::IOError(err)
+    }
+}
+
+impl From<toml::de::Error> for ConfigError {
+    fn from(err: toml::de::Error) -> ConfigError {
+        ConfigError::TomlError(err)
+    }
+}
+
+impl From<DBApiError> for ConfigError {
+    fn from(err: DBApiError) -> ConfigError {
+        ConfigError::DBError(err)
+    }
+}
+
+impl From<IdError> for ConfigError {
+    fn from(err: IdError) -> ConfigError {
+        ConfigError::IdError(err)
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct PoolConfig {
+    pub max_size: u32,
+    pub min_idle: Option<u32>, // idle connections kept open, None keeps max_size
+    pub connection_timeout_secs: u64, // how long to wait for a free connection
+    pub test_on_check_out: bool, // check connections are still alive before handing them out
+}
+
+impl Default for PoolConfig {
+    fn default() -> PoolConfig {
+        PoolConfig {
+            max_size: DEFAULT_POOL_MAX_SIZE,
+            min_idle: None,
+            connection_timeout_secs: DEFAULT_CONNECTION_TIMEOUT_SECS,
+            test_on_check_out: true,
+        }
+    }
+}
+
+impl PoolConfig {
+    // r2d2 panics on a pool that could never hand out a connection, so catch those first
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.max_size == 0 {
+            return Err(ConfigError::Invalid("pool.max_size"));
+        }
+        if let Some(min_idle) = self.min_idle {
+            if min_idle > self.max_size {
+                return Err(ConfigError::Invalid("pool.min_idle"));
+            }
+        }
+        if self.connection_timeout_secs == 0 {
+            return Err(ConfigError::Invalid("pool.connection_timeout_secs"));
+        }
+        return Ok(());
+    }
+
+    // an r2d2 pool builder with these settings, shared by every backend
+    pub(crate) fn builder<M: r2d2::ManageConnection>(&self) -> r2d2::Builder<M> {
+        r2d2::Pool::builder()
+            .max_size(self.max_size)
+            .min_idle(self.min_idle)
+            .connection_timeout(Duration::from_secs(self.connection_timeout_secs))
+            .test_on_check_out(self.test_on_check_out)
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct IdConfig {
+    pub node_id: u8,   // must be different on every server sharing a database
+    pub epoch_ms: u64, // unix time in milliseconds, never change it once ids have been created
+}
+
+impl Default for IdConfig {
+    fn default() -> IdConfig {
+        IdConfig {
+            node_id: 0,
+            epoch_ms: DEFAULT_ID_EPOCH_MS,
+        }
+    }
+}
+
+// Typed answers are compared after folding case, diacritics and punctuation, depending on
+// these settings, and runs of whitespace always count as a single space.
+// A typed answer within 1 typo (Levenshtein distance) for every 'chars_per_typo' characters
+// of the accepted answer, and at most 'max_typos', still passes
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct TypingConfig {
+    pub ignore_case: bool,
+    pub ignore_diacritics: bool, // e.g. 'é' matches 'e'
+    pub ignore_punctuation: bool,
+    pub chars_per_typo: u16, // 0 only passes answers that match once folded
+    pub max_typos: u16,
+}
+
+impl Default for TypingConfig {
+    fn default() -> TypingConfig {
+        TypingConfig {
+            ignore_case: true,
+            ignore_diacritics: true,
+            ignore_punctuation: true,
+            chars_per_typo: DEFAULT_CHARS_PER_TYPO,
+            max_typos: DEFAULT_MAX_TYPOS,
+        }
+    }
+}
+
+impl TypingConfig {
+    // typos allowed when typing an accepted answer 'chars' characters long
+    pub fn allowed_typos(&self, chars: usize) -> usize {
+        if self.chars_per_typo == 0 {
+            return 0;
+        }
+        return (chars / self.chars_per_typo as usize).min(self.max_typos as usize);
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct MediaConfig {
+    pub dir: PathBuf, // created on the first upload, relative paths are from the working dir
+    pub max_bytes: u32, // largest attachment accepted
+}
+
+impl Default for MediaConfig {
+    fn default() -> MediaConfig {
+        MediaConfig {
+            dir: PathBuf::from(DEFAULT_MEDIA_DIR),
+            max_bytes: DEFAULT_MAX_MEDIA_BYTES,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct FlashConfig {
+    pub database_url: String,
+    #[serde(default = "default_new_cards_per_queue")]
+    pub new_cards_per_queue: u16,
+    #[serde(default)]
+    pub pool: PoolConfig,
+    #[serde(default)]
+    pub id: IdConfig,
+    #[serde(default)]
+    pub typing: TypingConfig,
+    #[serde(default)]
+    pub media: MediaConfig,
+}
+
+fn default_new_cards_per_queue() -> u16 {
+    DEFAULT_NEW_CARDS_PER_QUEUE
+}
+
+// parse the environment variable if it's set
+fn env_var<T: FromStr>(name: &'static str) -> Result<Option<T>, ConfigError> {
+    match env::var(name) {
+        Ok(value) => match value.trim().parse::<T>() {
+            Ok(parsed) => return Ok(Some(parsed)),
+            Err(_err) => return Err(ConfigError::Invalid(name)),
+        },
+        Err(env::VarError::NotPresent) => return Ok(None),
+        Err(env::VarError::NotUnicode(_value)) => return Err(ConfigError::Invalid(name)),
+    }
+}
+
+impl FlashConfig {
+    pub fn new(database_url: String) -> FlashConfig {
+        FlashConfig {
+            database_url: database_url,
+            new_cards_per_queue: DEFAULT_NEW_CARDS_PER_QUEUE,
+            pool: PoolConfig::default(),
+            id: IdConfig::default(),
+            typing: TypingConfig::default(),
+            media: MediaConfig::default(),
+        }
+    }
+
+    pub fn from_env() -> Result<FlashConfig, ConfigError> {
+        let database_url = match env_var::<String>("FLASH_DATABASE_URL")? {
+            Some(database_url) => database_url,
+            None => return Err(ConfigError::Missing("FLASH_DATABASE_URL")),
+        };
+        let mut config = FlashConfig::new(database_url);
+        if let Some(new_cards) = env_var("FLASH_NEW_CARDS_PER_QUEUE")? {
+            config.new_cards_per_queue = new_cards;
+        }
+        if let Some(max_size) = env_var("FLASH_POOL_MAX_SIZE")? {
+            config.pool.max_size = max_size;
+        }
+        if let Some(min_idle) = env_var("FLASH_POOL_MIN_IDLE")? {
+            config.pool.min_idle = Some(min_idle);
+        }
+        if let Some(timeout) = env_var("FLASH_POOL_CONNECTION_TIMEOUT_SECS")? {
+            config.pool.connection_timeout_secs = timeout;
+        }
+        if let Some(test_on_check_out) = env_var("FLASH_POOL_TEST_ON_CHECK_OUT")? {
+            config.pool.test_on_check_out = test_on_check_out;
+        }
+        if let Some(node_id) = env_var("FLASH_ID_NODE_ID")? {
+            config.id.node_id = node_id;
+        }
+        if let Some(epoch_ms) = env_var("FLASH_ID_EPOCH_MS")? {
+            config.id.epoch_ms = epoch_ms;
+        }
+        if let Some(ignore_case) = env_var("FLASH_TYPING_IGNORE_CASE")? {
+            config.typing.ignore_case = ignore_case;
+        }
+        if let Some(ignore_diacritics) = env_var("FLASH_TYPING_IGNORE_DIACRITICS")? {
+            config.typing.ignore_diacritics = ignore_diacritics;
+        }
+        if let Some(ignore_punctuation) = env_var("FLASH_TYPING_IGNORE_PUNCTUATION")? {
+            config.typing.ignore_punctuation = ignore_punctuation;
+        }
+        if let Some(chars_per_typo) = env_var("FLASH_TYPING_CHARS_PER_TYPO")? {
+            config.typing.chars_per_typo = chars_per_typo;
+        }
+        if let Some(max_typos) = env_var("FLASH_TYPING_MAX_TYPOS")? {
+            config.typing.max_typos = max_typos;
+        }
+        if let Some(dir) = env_var("FLASH_MEDIA_DIR")? {
+            config.media.dir = dir;
+        }
+        if let Some(max_bytes) = env_var("FLASH_MEDIA_MAX_BYTES")? {
+            config.media.max_bytes = max_bytes;
+        }
+        config.pool.validate()?;
+        return Ok(config);
+    }
+
+    pub fn from_toml_str(text: &str) -> Result<FlashConfig, ConfigError> {
+        let config: FlashConfig = toml::from_str(text)?;
+        config.pool.validate()?;
+        return Ok(config);
+    }
+
+    pub fn from_toml_file<P: AsRef<Path>>(path: P) -> Result<FlashConfig, ConfigError> {
+        let text = fs::read_to_string(path)?;
+        return FlashConfig::from_toml_str(text.as_str());
+    }
+}
+
+// Creates a FlashManager on MySQL with build(), or with build_sqlite()/build_postgres()
+// when the 'sqlite'/'postgres' cargo features are enabled.
+// Start from FlashManager::builder(url), or from a loaded FlashConfig, then override settings:
+//   let flash_manager = FlashManagerBuilder::from_config(FlashConfig::from_env()?)
+//       .max_pool_size(20)
+//       .build()?;
+// NOTE: build doesn't run migrations, see db::run_migrations
+pub struct FlashManagerBuilder {
+    config: FlashConfig,
+}
+
+impl FlashManagerBuilder {
+    pub fn new(database_url: String) -> FlashManagerBuilder {
+        FlashManagerBuilder::from_config(FlashConfig::new(database_url))
+    }
+
+    pub fn from_config(config: FlashConfig) -> FlashManagerBuilder {
+        FlashManagerBuilder { config: config }
+    }
+
+    pub fn config(&self) -> &FlashConfig {
+        &self.config
+    }
+
+    pub fn max_pool_size(mut self, max_size: u32) -> FlashManagerBuilder {
+        self.config.pool.max_size = max_size;
+        self
+    }
+
+    pub fn min_idle(mut self, min_idle: Option<u32>) -> FlashManagerBuilder {
+        self.config.pool.min_idle = min_idle;
+        self
+    }
+
+    // rounded up to whole seconds, so a sub-second timeout doesn't become 0
+    pub fn connection_timeout(mut self, timeout: Duration) -> FlashManagerBuilder {
+        let part_second = u64::from(timeout.subsec_nanos() > 0);
+        self.config.pool.connection_timeout_secs = timeout.as_secs() + part_second;
+        self
+    }
+
+    pub fn test_on_check_out(mut self, test_on_check_out: bool) -> FlashManagerBuilder {
+        self.config.pool.test_on_check_out = test_on_check_out;
+        self
+    }
+
+    pub fn node_id(mut self, node_id: u8) -> FlashManagerBuilder {
+        self.config.id.node_id = node_id;
+        self
+    }
+
+    pub fn epoch(mut self, epoch: SystemTime) -> FlashManagerBuilder {
+        self.config.id.epoch_ms = match epoch.duration_since(UNIX_EPOCH) {
+            Ok(since_unix) => since_unix.as_millis() as u64,
+            Err(_err) => 0,
+        };
+        self
+    }
+
+    pub fn new_cards_per_queue(mut self, new_cards: u16) -> FlashManagerBuilder {
+        self.config.new_cards_per_queue = new_cards;
+        self
+    }
+
+    pub fn typing(mut self, typing: TypingConfig) -> FlashManagerBuilder {
+        self.config.typing = typing;
+        self
+    }
+
+    pub fn media_dir<P: Into<PathBuf>>(mut self, dir: P) -> FlashManagerBuilder {
+        self.config.media.dir = dir.into();
+        self
+    }
+
+    pub fn max_media_bytes(mut self, max_bytes: u32) -> FlashManagerBuilder {
+        self.config.media.max_bytes = max_bytes;
+        self
+    }
+
+    // on MySQL, the default storage
+    pub fn build(self) -> Result<FlashManager, ConfigError> {
+        return self.build_with(db::new_manager_with_config);
+    }
+
+    #[cfg(feature = "sqlite")]
+    pub fn build_sqlite(self) -> Result<FlashManager<db::sqlite::SqliteManager>, ConfigError> {
+        return self.build_with(db::sqlite::new_manager_with_config);
+    }
+
+    #[cfg(feature = "postgres")]
+    pub fn build_postgres(self) -> Result<FlashManager<db::postgres::PgManager>, ConfigError> {
+        return self.build_with(db::postgres::new_manager_with_config);
+    }
+
+    // 'new_manager' creates the connection pool of the backend
+    fn build_with<S, F>(self, new_manager: F) -> Result<FlashManager<S>, ConfigError>
+    where
+        F: FnOnce(String, &PoolConfig) -> Result<S, DBApiError>,
+    {
+        self.config.pool.validate()?;
+        let epoch = UNIX_EPOCH + Duration::from_millis(self.config.id.epoch_ms);
+        let id_generator = IdGenerator::new(epoch, self.config.id.node_id)?;
+        let db_manager = new_manager(self.config.database_url, &self.config.pool)?;
+        return Ok(FlashManager {
+            db_manager: db_manager,
+            id_generator: id_generator,
+            new_cards_per_queue: self.config.new_cards_per_queue,
+            typing: self.config.typing,
+            media_store: Box::new(LocalMediaStore::new(self.config.media.dir)),
+            max_media_bytes: self.config.media.max_bytes,
+        });
+    }
+}
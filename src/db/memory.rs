@@ -0,0 +1,975 @@
+// In-memory implementation of the db traits, for tests and prototyping.
+// Mirrors the SQL backends: the same NotFound/NotAllowed errors, unique and foreign key
+// violations reported as diesel database errors, position 0 reserved while moving cards,
+// and deletes cascading from decks to cards to scores, reviews, distractors,
+// accepted answers and media records.
+
+use diesel::result::DatabaseErrorKind as DBErrorKind;
+use diesel::result::Error as DieselError;
+
+use super::{
+    AnswerApi, CardApi, DBApiError, DeckApi, DeckRecords, DistractorApi, GameApi, MediaApi,
+    MemberApi, StudyApi,
+};
+use crate::card::Card;
+use crate::card::choice::Distractor;
+use crate::card::typed::AcceptedAnswer;
+use crate::deck::{Deck, DeckMember, DeckRole, DeckVisibility};
+use crate::game::{CardScore, Review};
+use crate::media::CardMedia;
+use crate::study::StudySession;
+
+use std::collections::BTreeMap;
+use std::sync::{Mutex, MutexGuard};
+
+#[derive(Clone, Default)]
+struct MemoryTables {
+    decks: BTreeMap<u64, Deck>,
+    cards: BTreeMap<u64, Card>,
+    card_answers: BTreeMap<(u64, u16), AcceptedAnswer>, // (card_id, position)
+    card_distractors: BTreeMap<(u64, u16), Distractor>, // (card_id, position)
+    card_media: BTreeMap<u64, CardMedia>,
+    cardscores: BTreeMap<(u64, u64, u16), CardScore>, // (account_id, card_id, item)
+    reviews: BTreeMap<u64, Review>,
+    study_sessions: BTreeMap<u64, StudySession>,
+    deck_members: BTreeMap<(u64, u64), DeckMember>, // (deck_id, account_id)
+}
+
+#[derive(Default)]
+pub struct MemoryStore {
+    tables: Mutex<MemoryTables>,
+}
+
+impl MemoryStore {
+    pub fn new() -> MemoryStore {
+        MemoryStore::default()
+    }
+
+    fn tables(&self) -> MutexGuard<'_, MemoryTables> {
+        // every operation checks its constraints before changing anything,
+        // so the tables are still consistent if another thread panicked
+        match self.tables.lock() {
+            Ok(tables) => tables,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+fn violation(kind: DBErrorKind, message: &str) -> DBApiError {
+    DBApiError::OtherError(DieselError::DatabaseError(
+        kind,
+        Box::new(message.to_owned()),
+    ))
+}
+
+fn unique_violation(message: &str) -> DBApiError {
+    violation(DBErrorKind::UniqueViolation, message)
+}
+
+fn foreign_key_violation(message: &str) -> DBApiError {
+    violation(DBErrorKind::ForeignKeyViolation, message)
+}
+
+impl MemoryTables {
+    // is (deck_id, deck_pos) used by any card other than 'except'
+    fn position_taken(&self, deck_id: u64, deck_pos: u16, except: u64) -> bool {
+        self.cards
+            .values()
+            .any(|card| card.id != except && card.deck_id == deck_id && card.deck_pos == deck_pos)
+    }
+
+    fn check_new_card(&self, card: &Card) -> Result<(), DBApiError> {
+        if self.cards.contains_key(&card.id) {
+            return Err(unique_violation("duplicate card id"));
+        }
+        if self.position_taken(card.deck_id, card.deck_pos, card.id) {
+            return Err(unique_violation("duplicate card position"));
+        }
+        return Ok(());
+    }
+
+    fn check_score(&self, score: &CardScore, review: &Review) -> Result<(), DBApiError> {
+        if !self.cards.contains_key(&score.card_id) || !self.cards.contains_key(&review.card_id) {
+            return Err(foreign_key_violation("score references a missing card"));
+        }
+        if self.reviews.contains_key(&review.id) {
+            return Err(unique_violation("duplicate review id"));
+        }
+        return Ok(());
+    }
+
+    // see check_score
+    fn write_score(&mut self, score: &CardScore, review: &Review) {
+        self.cardscores
+            .insert((score.account_id, score.card_id, score.item), score.clone());
+        self.reviews.insert(review.id, review.clone());
+    }
+
+    // cards in the deck, in position order
+    fn deck_cards(&self, deck_id: u64) -> Vec<&Card> {
+        let mut deck_cards: Vec<&Card> = self
+            .cards
+            .values()
+            .filter(|card| card.deck_id == deck_id)
+            .collect();
+        deck_cards.sort_by_key(|card| card.deck_pos);
+        return deck_cards;
+    }
+
+    fn card_in_deck(&self, card_id: u64, deck_id: u64) -> bool {
+        match self.cards.get(&card_id) {
+            Some(card) => card.deck_id == deck_id,
+            None => false,
+        }
+    }
+
+    fn insert_deck(&mut self, deck: &Deck, cards: &[Card]) -> Result<(), DBApiError> {
+        if self.decks.contains_key(&deck.id) {
+            return Err(unique_violation("duplicate deck id"));
+        }
+        // check every card before inserting anything, like the transaction would
+        for (i, card) in cards.iter().enumerate() {
+            if card.deck_id != deck.id && !self.decks.contains_key(&card.deck_id) {
+                return Err(foreign_key_violation("card references a missing deck"));
+            }
+            self.check_new_card(card)?;
+            let earlier = &cards[..i];
+            if earlier.iter().any(|other| other.id == card.id) {
+                return Err(unique_violation("duplicate card id"));
+            }
+            if earlier
+                .iter()
+                .any(|other| other.deck_id == card.deck_id && other.deck_pos == card.deck_pos)
+            {
+                return Err(unique_violation("duplicate card position"));
+            }
+        }
+        self.decks.insert(deck.id, deck.clone());
+        for card in cards {
+            self.cards.insert(card.id, card.clone());
+        }
+        return Ok(());
+    }
+
+    fn remove_card(&mut self, card_id: u64) {
+        self.cards.remove(&card_id);
+        self.cardscores
+            .retain(|(_account_id, score_card_id, _item), _score| *score_card_id != card_id);
+        self.reviews.retain(|_id, review| review.card_id != card_id);
+        self.card_distractors
+            .retain(|(distractor_card_id, _position), _distractor| *distractor_card_id != card_id);
+        self.card_answers
+            .retain(|(answer_card_id, _position), _answer| *answer_card_id != card_id);
+        self.card_media.retain(|_id, media| media.card_id != card_id);
+    }
+
+    // distractors can only be added to existing cards, once per position.
+    // the distractors of 'replacing' are about to be removed, so their positions are free
+    fn check_new_distractors(
+        &self,
+        distractors: &[Distractor],
+        replacing: Option<u64>,
+    ) -> Result<(), DBApiError> {
+        for (index, distractor) in distractors.iter().enumerate() {
+            if !self.cards.contains_key(&distractor.card_id) {
+                return Err(foreign_key_violation("distractor references a missing card"));
+            }
+            let key = (distractor.card_id, distractor.position);
+            let repeated = distractors[..index]
+                .iter()
+                .any(|other| (other.card_id, other.position) == key);
+            let taken = Some(distractor.card_id) != replacing
+                && self.card_distractors.contains_key(&key);
+            if repeated || taken {
+                return Err(unique_violation("duplicate distractor position"));
+            }
+        }
+        return Ok(());
+    }
+
+    // accepted answers can only be added to existing cards, once per position.
+    // the answers of 'replacing' are about to be removed, so their positions are free
+    fn check_new_answers(
+        &self,
+        answers: &[AcceptedAnswer],
+        replacing: Option<u64>,
+    ) -> Result<(), DBApiError> {
+        for (index, answer) in answers.iter().enumerate() {
+            if !self.cards.contains_key(&answer.card_id) {
+                return Err(foreign_key_violation("accepted answer references a missing card"));
+            }
+            let key = (answer.card_id, answer.position);
+            let repeated = answers[..index]
+                .iter()
+                .any(|other| (other.card_id, other.position) == key);
+            let taken = Some(answer.card_id) != replacing && self.card_answers.contains_key(&key);
+            if repeated || taken {
+                return Err(unique_violation("duplicate accepted answer position"));
+            }
+        }
+        return Ok(());
+    }
+
+    fn insert_history(
+        &mut self,
+        scores: &[CardScore],
+        reviews: &[Review],
+    ) -> Result<(), DBApiError> {
+        // check everything before inserting anything, like the transaction would
+        for (i, score) in scores.iter().enumerate() {
+            if !self.cards.contains_key(&score.card_id) {
+                return Err(foreign_key_violation("score references a missing card"));
+            }
+            let key = (score.account_id, score.card_id, score.item);
+            if self.cardscores.contains_key(&key)
+                || scores[..i]
+                    .iter()
+                    .any(|other| (other.account_id, other.card_id, other.item) == key)
+            {
+                return Err(unique_violation("duplicate card score"));
+            }
+        }
+        for (i, review) in reviews.iter().enumerate() {
+            if !self.cards.contains_key(&review.card_id) {
+                return Err(foreign_key_violation("review references a missing card"));
+            }
+            if self.reviews.contains_key(&review.id)
+                || reviews[..i].iter().any(|other| other.id == review.id)
+            {
+                return Err(unique_violation("duplicate review id"));
+            }
+        }
+        for score in scores {
+            self.cardscores
+                .insert((score.account_id, score.card_id, score.item), score.clone());
+        }
+        for review in reviews {
+            self.reviews.insert(review.id, review.clone());
+        }
+        return Ok(());
+    }
+
+    fn insert_media(&mut self, media: &CardMedia) -> Result<(), DBApiError> {
+        if !self.cards.contains_key(&media.card_id) {
+            return Err(foreign_key_violation("media references a missing card"));
+        }
+        if self.card_media.contains_key(&media.id) {
+            return Err(unique_violation("duplicate media id"));
+        }
+        self.card_media.insert(media.id, media.clone());
+        return Ok(());
+    }
+
+    // the account's role on the deck, if the deck exists
+    fn role_on(&self, deck: &Deck, account_id: u64) -> Option<DeckRole> {
+        let member_role = self
+            .deck_members
+            .get(&(deck.id, account_id))
+            .and_then(|member| member.role());
+        return deck.role_for(account_id, member_role);
+    }
+
+    // does the card exist in a deck the account owns, or was shared with as an editor
+    fn can_edit_card(&self, card_id: u64, account_id: u64) -> bool {
+        let deck = match self.cards.get(&card_id) {
+            Some(card) => self.decks.get(&card.deck_id),
+            None => None,
+        };
+        match deck {
+            Some(deck) => match self.role_on(deck, account_id) {
+                Some(role) => role >= DeckRole::Editor,
+                None => false,
+            },
+            None => false,
+        }
+    }
+
+    fn delete_deck(&mut self, deck_id: u64) {
+        self.decks.remove(&deck_id);
+        let card_ids: Vec<u64> = self
+            .cards
+            .values()
+            .filter(|card| card.deck_id == deck_id)
+            .map(|card| card.id)
+            .collect();
+        for card_id in card_ids {
+            self.remove_card(card_id);
+        }
+        self.study_sessions
+            .retain(|_id, study_session| study_session.deck_id != deck_id);
+        self.deck_members
+            .retain(|(member_deck_id, _account_id), _member| *member_deck_id != deck_id);
+    }
+
+    fn delete_card(&mut self, card_id: u64) -> Result<(), DBApiError> {
+        let (deck_id, deck_pos) = match self.cards.get(&card_id) {
+            Some(card) => (card.deck_id, card.deck_pos),
+            None => return Err(DBApiError::NotFound),
+        };
+        self.remove_card(card_id);
+        // shift all of the following cards down 1 position
+        for card in self.cards.values_mut() {
+            if card.deck_id == deck_id && card.deck_pos > deck_pos {
+                card.deck_pos -= 1;
+            }
+        }
+        return Ok(());
+    }
+}
+
+impl DeckApi for MemoryStore {
+    fn insert(&self, deck_info: &Deck) -> Result<(), DBApiError> {
+        let mut tables = self.tables();
+        if tables.decks.contains_key(&deck_info.id) {
+            return Err(unique_violation("duplicate deck id"));
+        }
+        tables.decks.insert(deck_info.id, deck_info.clone());
+        return Ok(());
+    }
+
+    fn insert_with_cards(&self, deck: &Deck, cards: &[Card]) -> Result<(), DBApiError> {
+        // DO NOT ALLOW USER TO MOVE CARD TO RESERVED POSITION 0
+        if cards.iter().any(|card| card.deck_pos == 0) {
+            return Err(DBApiError::NotAllowed);
+        }
+        return self.tables().insert_deck(deck, cards);
+    }
+
+    fn insert_records(&self, records: &[DeckRecords]) -> Result<(), DBApiError> {
+        // DO NOT ALLOW USER TO MOVE CARD TO RESERVED POSITION 0
+        let cards = records.iter().flat_map(|deck_records| deck_records.cards.iter());
+        if cards.clone().any(|card| card.deck_pos == 0) {
+            return Err(DBApiError::NotAllowed);
+        }
+        let mut tables = self.tables();
+        // insert into a copy and keep it only if every deck made it, like the transaction would
+        let mut staged = tables.clone();
+        for deck_records in records {
+            staged.insert_deck(&deck_records.deck, &deck_records.cards)?;
+            staged.check_new_distractors(&deck_records.distractors, None)?;
+            for distractor in &deck_records.distractors {
+                staged
+                    .card_distractors
+                    .insert((distractor.card_id, distractor.position), distractor.clone());
+            }
+            staged.check_new_answers(&deck_records.accepted_answers, None)?;
+            for answer in &deck_records.accepted_answers {
+                staged
+                    .card_answers
+                    .insert((answer.card_id, answer.position), answer.clone());
+            }
+            staged.insert_history(&deck_records.scores, &deck_records.reviews)?;
+            for media in &deck_records.media {
+                staged.insert_media(media)?;
+            }
+        }
+        *tables = staged;
+        return Ok(());
+    }
+
+    fn find(&self, deck_info_id: &u64) -> Result<Deck, DBApiError> {
+        match self.tables().decks.get(deck_info_id) {
+            Some(deck) => return Ok(deck.clone()),
+            None => return Err(DBApiError::NotFound),
+        }
+    }
+
+    fn find_for_account(
+        &self,
+        deck_id: u64,
+        account_id: u64,
+    ) -> Result<(Deck, Option<DeckRole>), DBApiError> {
+        let tables = self.tables();
+        match tables.decks.get(&deck_id) {
+            Some(deck) => return Ok((deck.clone(), tables.role_on(deck, account_id))),
+            None => return Err(DBApiError::NotFound),
+        }
+    }
+
+    fn find_decks_for_owner(&self, owner: &u64) -> Result<Vec<Deck>, DBApiError> {
+        let owner_decks = self
+            .tables()
+            .decks
+            .values()
+            .filter(|deck| deck.owner_id == *owner)
+            .cloned()
+            .collect();
+        return Ok(owner_decks);
+    }
+
+    fn find_decks_for_member(&self, account_id: &u64) -> Result<Vec<Deck>, DBApiError> {
+        let tables = self.tables();
+        let shared_decks = tables
+            .deck_members
+            .values()
+            .filter(|member| member.account_id == *account_id)
+            .filter_map(|member| tables.decks.get(&member.deck_id))
+            .cloned()
+            .collect();
+        return Ok(shared_decks);
+    }
+
+    fn find_public_decks(
+        &self,
+        search: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<Deck>, DBApiError> {
+        // case insensitive, like the default MySQL collation
+        let search = search.map(|text| text.to_lowercase());
+        let tables = self.tables();
+        let mut public_decks: Vec<&Deck> = tables
+            .decks
+            .values()
+            .filter(|deck| deck.visibility() == DeckVisibility::Public)
+            .filter(|deck| match &search {
+                Some(text) => deck.name().to_lowercase().contains(text.as_str()),
+                None => true,
+            })
+            .collect();
+        public_decks.sort_by(|a, b| {
+            (a.name().to_lowercase(), a.id).cmp(&(b.name().to_lowercase(), b.id))
+        });
+        let page = public_decks
+            .into_iter()
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect();
+        return Ok(page);
+    }
+
+    fn update(&self, deck_info: &Deck) -> Result<(), DBApiError> {
+        let mut tables = self.tables();
+        // like an UPDATE, a missing deck is not an error
+        if let Some(existing) = tables.decks.get_mut(&deck_info.id) {
+            *existing = deck_info.clone();
+        }
+        return Ok(());
+    }
+
+    fn delete(&self, deck_info_id: &u64) -> Result<(), DBApiError> {
+        let mut tables = self.tables();
+        if !tables.decks.contains_key(deck_info_id) {
+            return Err(DBApiError::NotFound);
+        }
+        tables.delete_deck(*deck_info_id);
+        return Ok(());
+    }
+
+    fn delete_for_owner(&self, deck_id: u64, account_id: u64) -> Result<(), DBApiError> {
+        let mut tables = self.tables();
+        let role = match tables.decks.get(&deck_id) {
+            Some(deck) => tables.role_on(deck, account_id),
+            None => None,
+        };
+        match role {
+            Some(role) if role >= DeckRole::Owner => {}
+            _ => return Err(DBApiError::NotFound),
+        }
+        tables.delete_deck(deck_id);
+        return Ok(());
+    }
+}
+
+impl CardApi for MemoryStore {
+    fn insert(&self, card: &Card) -> Result<(), DBApiError> {
+        // DO NOT ALLOW USER TO MOVE CARD TO RESERVED POSITION 0
+        if card.deck_pos == 0 {
+            return Err(DBApiError::NotAllowed);
+        }
+        let mut tables = self.tables();
+        if !tables.decks.contains_key(&card.deck_id) {
+            return Err(foreign_key_violation("card references a missing deck"));
+        }
+        tables.check_new_card(card)?;
+        tables.cards.insert(card.id, card.clone());
+        return Ok(());
+    }
+
+    fn find(&self, card_id: &u64) -> Result<Card, DBApiError> {
+        match self.tables().cards.get(card_id) {
+            Some(card) => return Ok(card.clone()),
+            None => return Err(DBApiError::NotFound),
+        }
+    }
+
+    fn find_for_account(
+        &self,
+        card_id: u64,
+        account_id: u64,
+    ) -> Result<(Card, Deck, Option<DeckRole>), DBApiError> {
+        let tables = self.tables();
+        let card = match tables.cards.get(&card_id) {
+            Some(card) => card,
+            None => return Err(DBApiError::NotFound),
+        };
+        match tables.decks.get(&card.deck_id) {
+            Some(deck) => {
+                let role = tables.role_on(deck, account_id);
+                return Ok((card.clone(), deck.clone(), role));
+            }
+            None => return Err(DBApiError::NotFound),
+        }
+    }
+
+    fn find_cards_for_deck(&self, card_deck_id: &u64) -> Result<Vec<Card>, DBApiError> {
+        let deck_cards = self
+            .tables()
+            .deck_cards(*card_deck_id)
+            .into_iter()
+            .cloned()
+            .collect();
+        return Ok(deck_cards);
+    }
+
+    fn update(&self, card: &Card) -> Result<(), DBApiError> {
+        let mut tables = self.tables();
+        if !tables.cards.contains_key(&card.id) {
+            return Ok(());
+        }
+        if !tables.decks.contains_key(&card.deck_id) {
+            return Err(foreign_key_violation("card references a missing deck"));
+        }
+        if tables.position_taken(card.deck_id, card.deck_pos, card.id) {
+            return Err(unique_violation("duplicate card position"));
+        }
+        tables.cards.insert(card.id, card.clone());
+        return Ok(());
+    }
+
+    fn update_position(
+        &self,
+        card_id: u64,
+        deck_id: u64,
+        orig_pos: u16,
+        new_pos: u16,
+    ) -> Result<(), DBApiError> {
+        // DO NOT ALLOW USER TO MOVE CARD TO RESERVED POSITION 0
+        if new_pos == 0 {
+            return Err(DBApiError::NotAllowed);
+        }
+        // ignore without change
+        if new_pos == orig_pos {
+            return Ok(());
+        }
+        let mut tables = self.tables();
+        // the card must be at the original position the user expects
+        match tables.cards.get(&card_id) {
+            Some(card) if card.deck_id == deck_id && card.deck_pos == orig_pos => {}
+            _ => return Err(DBApiError::NotFound),
+        }
+        // shift all cards between new and orig into the gap left by the card
+        for card in tables.cards.values_mut() {
+            if card.deck_id != deck_id || card.id == card_id {
+                continue;
+            }
+            if new_pos < orig_pos && card.deck_pos >= new_pos && card.deck_pos <= orig_pos {
+                card.deck_pos += 1;
+            } else if new_pos > orig_pos && card.deck_pos >= orig_pos && card.deck_pos <= new_pos {
+                card.deck_pos -= 1;
+            }
+        }
+        if let Some(card) = tables.cards.get_mut(&card_id) {
+            card.deck_pos = new_pos;
+        }
+        return Ok(());
+    }
+
+    fn delete(&self, card_id: &u64) -> Result<(), DBApiError> {
+        return self.tables().delete_card(*card_id);
+    }
+
+    fn update_for_editor(
+        &self,
+        card_id: u64,
+        account_id: u64,
+        question: Option<&str>,
+        answer: Option<&str>,
+        reversible: Option<bool>,
+    ) -> Result<(), DBApiError> {
+        let mut tables = self.tables();
+        if !tables.can_edit_card(card_id, account_id) {
+            return Err(DBApiError::NotFound);
+        }
+        if let Some(card) = tables.cards.get_mut(&card_id) {
+            if let Some(question) = question {
+                card.question = question.to_owned();
+            }
+            if let Some(answer) = answer {
+                card.answer = answer.to_owned();
+            }
+            if let Some(reversible) = reversible {
+                card.reversible = reversible;
+            }
+        }
+        return Ok(());
+    }
+
+    fn delete_for_editor(&self, card_id: u64, account_id: u64) -> Result<(), DBApiError> {
+        let mut tables = self.tables();
+        if !tables.can_edit_card(card_id, account_id) {
+            return Err(DBApiError::NotFound);
+        }
+        return tables.delete_card(card_id);
+    }
+}
+
+impl DistractorApi for MemoryStore {
+    fn find_for_card(&self, card_id: u64) -> Result<Vec<Distractor>, DBApiError> {
+        let distractors = self
+            .tables()
+            .card_distractors
+            .range((card_id, 0)..=(card_id, u16::MAX))
+            .map(|(_key, distractor)| distractor.clone())
+            .collect();
+        return Ok(distractors);
+    }
+
+    fn find_for_deck(&self, deck_id: u64) -> Result<Vec<Distractor>, DBApiError> {
+        let tables = self.tables();
+        let distractors = tables
+            .card_distractors
+            .values()
+            .filter(|distractor| tables.card_in_deck(distractor.card_id, deck_id))
+            .cloned()
+            .collect();
+        return Ok(distractors);
+    }
+
+    fn insert(&self, distractors: &[Distractor]) -> Result<(), DBApiError> {
+        let mut tables = self.tables();
+        tables.check_new_distractors(distractors, None)?;
+        for distractor in distractors {
+            tables
+                .card_distractors
+                .insert((distractor.card_id, distractor.position), distractor.clone());
+        }
+        return Ok(());
+    }
+
+    fn replace_for_card(&self, card_id: u64, distractors: &[Distractor]) -> Result<(), DBApiError> {
+        let mut tables = self.tables();
+        tables.check_new_distractors(distractors, Some(card_id))?;
+        tables
+            .card_distractors
+            .retain(|(distractor_card_id, _position), _distractor| *distractor_card_id != card_id);
+        for distractor in distractors {
+            tables
+                .card_distractors
+                .insert((distractor.card_id, distractor.position), distractor.clone());
+        }
+        return Ok(());
+    }
+}
+
+impl AnswerApi for MemoryStore {
+    fn find_for_card(&self, card_id: u64) -> Result<Vec<AcceptedAnswer>, DBApiError> {
+        let answers = self
+            .tables()
+            .card_answers
+            .range((card_id, 0)..=(card_id, u16::MAX))
+            .map(|(_key, answer)| answer.clone())
+            .collect();
+        return Ok(answers);
+    }
+
+    fn find_for_deck(&self, deck_id: u64) -> Result<Vec<AcceptedAnswer>, DBApiError> {
+        let tables = self.tables();
+        let answers = tables
+            .card_answers
+            .values()
+            .filter(|answer| tables.card_in_deck(answer.card_id, deck_id))
+            .cloned()
+            .collect();
+        return Ok(answers);
+    }
+
+    fn insert(&self, answers: &[AcceptedAnswer]) -> Result<(), DBApiError> {
+        let mut tables = self.tables();
+        tables.check_new_answers(answers, None)?;
+        for answer in answers {
+            tables
+                .card_answers
+                .insert((answer.card_id, answer.position), answer.clone());
+        }
+        return Ok(());
+    }
+
+    fn replace_for_card(&self, card_id: u64, answers: &[AcceptedAnswer]) -> Result<(), DBApiError> {
+        let mut tables = self.tables();
+        tables.check_new_answers(answers, Some(card_id))?;
+        tables
+            .card_answers
+            .retain(|(answer_card_id, _position), _answer| *answer_card_id != card_id);
+        for answer in answers {
+            tables
+                .card_answers
+                .insert((answer.card_id, answer.position), answer.clone());
+        }
+        return Ok(());
+    }
+}
+
+impl MediaApi for MemoryStore {
+    fn insert(&self, media: &CardMedia) -> Result<(), DBApiError> {
+        return self.tables().insert_media(media);
+    }
+
+    fn insert_limited(&self, media: &CardMedia, max: usize) -> Result<(), DBApiError> {
+        let mut tables = self.tables();
+        let attached = tables
+            .card_media
+            .values()
+            .filter(|other| other.card_id == media.card_id)
+            .count();
+        if attached >= max {
+            return Err(DBApiError::NotAllowed);
+        }
+        return tables.insert_media(media);
+    }
+
+    fn find(&self, media_id: u64) -> Result<CardMedia, DBApiError> {
+        match self.tables().card_media.get(&media_id) {
+            Some(media) => return Ok(media.clone()),
+            None => return Err(DBApiError::NotFound),
+        }
+    }
+
+    fn find_for_card(&self, card_id: u64) -> Result<Vec<CardMedia>, DBApiError> {
+        let media = self
+            .tables()
+            .card_media
+            .values()
+            .filter(|media| media.card_id == card_id)
+            .cloned()
+            .collect();
+        return Ok(media);
+    }
+
+    fn find_for_deck(&self, deck_id: u64) -> Result<Vec<CardMedia>, DBApiError> {
+        let tables = self.tables();
+        let mut media: Vec<CardMedia> = tables
+            .card_media
+            .values()
+            .filter(|media| tables.card_in_deck(media.card_id, deck_id))
+            .cloned()
+            .collect();
+        media.sort_by_key(|media| (media.card_id, media.id));
+        return Ok(media);
+    }
+
+    fn find_existing(&self, media_ids: &[u64]) -> Result<Vec<u64>, DBApiError> {
+        let tables = self.tables();
+        let existing = media_ids
+            .iter()
+            .filter(|media_id| tables.card_media.contains_key(media_id))
+            .cloned()
+            .collect();
+        return Ok(existing);
+    }
+
+    fn delete(&self, media_id: u64) -> Result<(), DBApiError> {
+        match self.tables().card_media.remove(&media_id) {
+            Some(_media) => return Ok(()),
+            None => return Err(DBApiError::NotFound),
+        }
+    }
+}
+
+impl GameApi for MemoryStore {
+    fn find_score(
+        &self,
+        account_id: u64,
+        card_id: u64,
+        item: u16,
+    ) -> Result<CardScore, DBApiError> {
+        match self.tables().cardscores.get(&(account_id, card_id, item)) {
+            Some(score) => return Ok(score.clone()),
+            None => return Err(DBApiError::NotFound),
+        }
+    }
+
+    fn update_score(&self, score: &CardScore, review: &Review) -> Result<(), DBApiError> {
+        let mut tables = self.tables();
+        tables.check_score(score, review)?;
+        tables.write_score(score, review);
+        return Ok(());
+    }
+
+    fn get_deck_scores(&self, deck_id: u64, account_id: u64) -> Result<Vec<CardScore>, DBApiError> {
+        let tables = self.tables();
+        let deck_scores = tables
+            .cardscores
+            .values()
+            .filter(|score| score.account_id == account_id)
+            .filter(|score| tables.card_in_deck(score.card_id, deck_id))
+            .cloned()
+            .collect();
+        return Ok(deck_scores);
+    }
+
+    fn get_due_scores(
+        &self,
+        deck_id: u64,
+        account_id: u64,
+        now: u32,
+    ) -> Result<Vec<CardScore>, DBApiError> {
+        let tables = self.tables();
+        let mut due_scores: Vec<CardScore> = tables
+            .cardscores
+            .values()
+            .filter(|score| score.account_id == account_id && score.due <= now)
+            .filter(|score| tables.card_in_deck(score.card_id, deck_id))
+            .cloned()
+            .collect();
+        due_scores.sort_by_key(|score| score.due);
+        return Ok(due_scores);
+    }
+
+    fn get_study_candidates(
+        &self,
+        deck_id: u64,
+        account_id: u64,
+        now: u32,
+    ) -> Result<Vec<(Card, u16, Option<CardScore>)>, DBApiError> {
+        let tables = self.tables();
+        let mut candidates = Vec::new();
+        for card in tables.deck_cards(deck_id) {
+            for item in card.items() {
+                match tables.cardscores.get(&(account_id, card.id, item)) {
+                    Some(score) if score.due <= now => {
+                        candidates.push((card.clone(), item, Some(score.clone())))
+                    }
+                    Some(_score) => {}
+                    None => candidates.push((card.clone(), item, None)),
+                }
+            }
+        }
+        return Ok(candidates);
+    }
+
+    fn get_deck_reviews(&self, deck_id: u64, account_id: u64) -> Result<Vec<Review>, DBApiError> {
+        let tables = self.tables();
+        let mut deck_reviews: Vec<Review> = tables
+            .reviews
+            .values()
+            .filter(|review| review.account_id == account_id)
+            .filter(|review| tables.card_in_deck(review.card_id, deck_id))
+            .cloned()
+            .collect();
+        deck_reviews.sort_by_key(|review| (review.answered_at, review.id));
+        return Ok(deck_reviews);
+    }
+
+    fn insert_history(&self, scores: &[CardScore], reviews: &[Review]) -> Result<(), DBApiError> {
+        return self.tables().insert_history(scores, reviews);
+    }
+}
+
+impl StudyApi for MemoryStore {
+    fn insert(&self, study_session: &StudySession) -> Result<(), DBApiError> {
+        let mut tables = self.tables();
+        if !tables.decks.contains_key(&study_session.deck_id) {
+            return Err(foreign_key_violation("study session references a missing deck"));
+        }
+        if tables.study_sessions.contains_key(&study_session.id) {
+            return Err(unique_violation("duplicate study session id"));
+        }
+        tables
+            .study_sessions
+            .insert(study_session.id, study_session.clone());
+        return Ok(());
+    }
+
+    fn find(&self, study_id: &u64) -> Result<StudySession, DBApiError> {
+        match self.tables().study_sessions.get(study_id) {
+            Some(study_session) => return Ok(study_session.clone()),
+            None => return Err(DBApiError::NotFound),
+        }
+    }
+
+    fn find_open(&self, account_id: u64, deck_id: u64) -> Result<StudySession, DBApiError> {
+        let study_session = self
+            .tables()
+            .study_sessions
+            .values()
+            .filter(|study_session| {
+                study_session.account_id == account_id
+                    && study_session.deck_id == deck_id
+                    && study_session.ended.is_none()
+            })
+            .max_by_key(|study_session| study_session.started)
+            .cloned();
+        match study_session {
+            Some(study_session) => return Ok(study_session),
+            None => return Err(DBApiError::NotFound),
+        }
+    }
+
+    fn record_answer(
+        &self,
+        study_id: u64,
+        correct: bool,
+        score: &CardScore,
+        review: &Review,
+    ) -> Result<(), DBApiError> {
+        let mut tables = self.tables();
+        match tables.study_sessions.get(&study_id) {
+            Some(study_session) if study_session.ended.is_none() => {}
+            _ => return Err(DBApiError::NotFound),
+        }
+        tables.check_score(score, review)?;
+        if let Some(study_session) = tables.study_sessions.get_mut(&study_id) {
+            study_session.cards_seen += 1;
+            study_session.correct_count += correct as u16;
+        }
+        tables.write_score(score, review);
+        return Ok(());
+    }
+
+    fn finish(&self, study_id: u64, ended: u32) -> Result<(), DBApiError> {
+        if let Some(study_session) = self.tables().study_sessions.get_mut(&study_id) {
+            study_session.finish(ended);
+        }
+        return Ok(());
+    }
+}
+
+impl MemberApi for MemoryStore {
+    fn upsert(&self, member: &DeckMember) -> Result<(), DBApiError> {
+        let mut tables = self.tables();
+        if !tables.decks.contains_key(&member.deck_id) {
+            return Err(foreign_key_violation("member references a missing deck"));
+        }
+        tables
+            .deck_members
+            .insert((member.deck_id, member.account_id), member.clone());
+        return Ok(());
+    }
+
+    fn find(&self, deck_id: u64, account_id: u64) -> Result<DeckMember, DBApiError> {
+        match self.tables().deck_members.get(&(deck_id, account_id)) {
+            Some(member) => return Ok(member.clone()),
+            None => return Err(DBApiError::NotFound),
+        }
+    }
+
+    fn find_members_for_deck(&self, deck_id: u64) -> Result<Vec<DeckMember>, DBApiError> {
+        let members = self
+            .tables()
+            .deck_members
+            .values()
+            .filter(|member| member.deck_id == deck_id)
+            .cloned()
+            .collect();
+        return Ok(members);
+    }
+
+    fn delete(&self, deck_id: u64, account_id: u64) -> Result<(), DBApiError> {
+        match self.tables().deck_members.remove(&(deck_id, account_id)) {
+            Some(_member) => return Ok(()),
+            None => return Err(DBApiError::NotFound),
+        }
+    }
+}
@@ -0,0 +1,302 @@
+// This module contains database CRUD operations for each of the models.
+// The traits are implemented once per backend, see the 'mysql', 'sqlite' and 'postgres' modules,
+// and 'memory' for a store that needs no database at all.
+// DBManager is the MySQL connection pool, the default storage of a FlashManager.
+// The 'sqlite' and 'postgres' cargo features each add their own backend module.
+
+use diesel::result::Error as DieselError;
+
+use crate::card::Card;
+use crate::card::choice::Distractor;
+use crate::card::typed::AcceptedAnswer;
+use crate::deck::{Deck, DeckMember, DeckRole};
+use crate::game::{CardScore, Review};
+use crate::media::CardMedia;
+use crate::study::StudySession;
+
+pub mod memory;
+pub mod mysql;
+mod store;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+// records for backends without unsigned integer columns
+#[cfg(any(feature = "sqlite", feature = "postgres"))]
+mod signed;
+
+pub use store::FlashStore;
+
+pub use mysql::{MysqlManager as DBManager, new_manager, new_manager_with_config, run_migrations};
+
+#[derive(Debug)]
+pub enum DBApiError {
+    OtherError(DieselError), // errors from interacting with database
+    BadVerifyCode,
+    PoolError(r2d2::Error),
+    NotAllowed, // caller is trying to do something wonky
+    NotFound,
+    MigrationError(Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl From<DBApiError> for crate::FlashError {
+    fn from(err: DBApiError) -> crate::FlashError {
+        crate::FlashError::DBError(err)
+    }
+}
+
+impl From<r2d2::Error> for DBApiError {
+    fn from(err: r2d2::Error) -> DBApiError {
+        DBApiError::PoolError(err)
+    }
+}
+
+impl From<DieselError> for DBApiError {
+    fn from(err: DieselError) -> DBApiError {
+        match err {
+            DieselError::NotFound => DBApiError::NotFound,
+            _ => DBApiError::OtherError(err),
+        }
+    }
+}
+
+// max rows per multi-row insert
+const INSERT_CHUNK_SIZE: usize = 1000;
+
+// 'contains' pattern for LIKE, with wildcards in the search text escaped by '\'
+fn like_pattern(search: &str) -> String {
+    let escaped = search
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    return format!("%{}%", escaped);
+}
+
+// a brand new deck and the records that belong to it, see DeckApi::insert_records
+pub struct DeckRecords {
+    pub deck: Deck,
+    pub cards: Vec<Card>,
+    pub distractors: Vec<Distractor>,
+    pub accepted_answers: Vec<AcceptedAnswer>,
+    pub scores: Vec<CardScore>,
+    pub reviews: Vec<Review>,
+    pub media: Vec<CardMedia>, // only the records, the bytes are stored afterwards
+}
+
+impl DeckRecords {
+    pub fn new(deck: Deck, cards: Vec<Card>) -> DeckRecords {
+        DeckRecords {
+            deck: deck,
+            cards: cards,
+            distractors: Vec::new(),
+            accepted_answers: Vec::new(),
+            scores: Vec::new(),
+            reviews: Vec::new(),
+            media: Vec::new(),
+        }
+    }
+}
+
+pub trait DeckApi {
+    fn insert(&self, deck: &Deck) -> Result<(), DBApiError>;
+
+    // insert a new deck along with all of its cards in a single transaction
+    fn insert_with_cards(&self, deck: &Deck, cards: &[Card]) -> Result<(), DBApiError>;
+
+    // insert several new decks and their records in a single transaction,
+    // nothing is stored if any of them can't be
+    fn insert_records(&self, records: &[DeckRecords]) -> Result<(), DBApiError>;
+
+    fn find(&self, id: &u64) -> Result<Deck, DBApiError>;
+
+    // the deck along with the account's role on it, in one query
+    fn find_for_account(
+        &self,
+        deck_id: u64,
+        account_id: u64,
+    ) -> Result<(Deck, Option<DeckRole>), DBApiError>;
+
+    fn find_decks_for_owner(&self, owner_id: &u64) -> Result<Vec<Deck>, DBApiError>;
+
+    // decks shared with the account through deck_members
+    fn find_decks_for_member(&self, account_id: &u64) -> Result<Vec<Deck>, DBApiError>;
+
+    // public decks ordered by name, optionally only those whose name contains 'search'
+    fn find_public_decks(
+        &self,
+        search: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<Deck>, DBApiError>;
+
+    fn update(&self, deck: &Deck) -> Result<(), DBApiError>;
+
+    fn delete(&self, id: &u64) -> Result<(), DBApiError>;
+
+    // delete the deck only if the account created it or was shared it as an owner,
+    // NotFound otherwise
+    fn delete_for_owner(&self, deck_id: u64, account_id: u64) -> Result<(), DBApiError>;
+}
+
+pub trait CardApi {
+    fn insert(&self, card: &Card) -> Result<(), DBApiError>;
+
+    fn find(&self, card_id: &u64) -> Result<Card, DBApiError>;
+
+    // the card and its deck, along with the account's role on the deck, in one query
+    fn find_for_account(
+        &self,
+        card_id: u64,
+        account_id: u64,
+    ) -> Result<(Card, Deck, Option<DeckRole>), DBApiError>;
+
+    fn find_cards_for_deck(&self, deck_id: &u64) -> Result<Vec<Card>, DBApiError>;
+
+    fn update(&self, card: &Card) -> Result<(), DBApiError>;
+
+    fn update_position(
+        &self,
+        card_id: u64,
+        deck_id: u64,
+        orig_pos: u16,
+        new_pos: u16,
+    ) -> Result<(), DBApiError>;
+
+    fn delete(&self, card_id: &u64) -> Result<(), DBApiError>;
+
+    // the '_for_editor' functions only match cards in decks the account owns
+    // or was shared with as an editor, and are NotFound otherwise
+
+    // change the question, the answer and/or whether the card is reversible.
+    // at least one must be given
+    fn update_for_editor(
+        &self,
+        card_id: u64,
+        account_id: u64,
+        question: Option<&str>,
+        answer: Option<&str>,
+        reversible: Option<bool>,
+    ) -> Result<(), DBApiError>;
+
+    fn delete_for_editor(&self, card_id: u64, account_id: u64) -> Result<(), DBApiError>;
+}
+
+// the wrong options of multiple choice cards, see card::choice
+pub trait DistractorApi {
+    // the card's distractors, in position order
+    fn find_for_card(&self, card_id: u64) -> Result<Vec<Distractor>, DBApiError>;
+
+    // the distractors of every card in the deck, ordered by card and then position
+    fn find_for_deck(&self, deck_id: u64) -> Result<Vec<Distractor>, DBApiError>;
+
+    // bulk insert, e.g. for the cards of a cloned or imported deck
+    fn insert(&self, distractors: &[Distractor]) -> Result<(), DBApiError>;
+
+    // swap all of the card's distractors for 'distractors' in one transaction.
+    // an empty list leaves the card without any
+    fn replace_for_card(&self, card_id: u64, distractors: &[Distractor]) -> Result<(), DBApiError>;
+}
+
+// the other accepted answers of typed-answer cards, see card::typed
+pub trait AnswerApi {
+    // the card's accepted answers, in position order
+    fn find_for_card(&self, card_id: u64) -> Result<Vec<AcceptedAnswer>, DBApiError>;
+
+    // the accepted answers of every card in the deck, ordered by card and then position
+    fn find_for_deck(&self, deck_id: u64) -> Result<Vec<AcceptedAnswer>, DBApiError>;
+
+    // bulk insert, e.g. for the cards of a cloned or imported deck
+    fn insert(&self, answers: &[AcceptedAnswer]) -> Result<(), DBApiError>;
+
+    // swap all of the card's accepted answers for 'answers' in one transaction.
+    // an empty list leaves only the card's own answer
+    fn replace_for_card(&self, card_id: u64, answers: &[AcceptedAnswer]) -> Result<(), DBApiError>;
+}
+
+// the records of card attachments, see media. the bytes themselves are kept in a
+// media::MediaStore, and records cascade away with their card
+pub trait MediaApi {
+    fn insert(&self, media: &CardMedia) -> Result<(), DBApiError>;
+
+    // insert unless the card already has 'max' attachments, counted in the same transaction
+    // so uploads at the same time can't go over it. NotAllowed if the card is full
+    fn insert_limited(&self, media: &CardMedia, max: usize) -> Result<(), DBApiError>;
+
+    fn find(&self, media_id: u64) -> Result<CardMedia, DBApiError>;
+
+    // the card's attachments, oldest first
+    fn find_for_card(&self, card_id: u64) -> Result<Vec<CardMedia>, DBApiError>;
+
+    // the attachments of every card in the deck, ordered by card and then age
+    fn find_for_deck(&self, deck_id: u64) -> Result<Vec<CardMedia>, DBApiError>;
+
+    // which of 'media_ids' still have a record, in no particular order
+    fn find_existing(&self, media_ids: &[u64]) -> Result<Vec<u64>, DBApiError>;
+
+    fn delete(&self, media_id: u64) -> Result<(), DBApiError>;
+}
+
+pub trait GameApi {
+    // the score for one of the card's items, see Card::items
+    fn find_score(
+        &self,
+        account_id: u64,
+        card_id: u64,
+        item: u16,
+    ) -> Result<CardScore, DBApiError>;
+    // store the new score and append the review that produced it
+    fn update_score(&self, score: &CardScore, review: &Review) -> Result<(), DBApiError>;
+    fn get_deck_scores(&self, deck_id: u64, account_id: u64) -> Result<Vec<CardScore>, DBApiError>;
+    fn get_due_scores(
+        &self,
+        deck_id: u64,
+        account_id: u64,
+        now: u32,
+    ) -> Result<Vec<CardScore>, DBApiError>;
+    // card items in the deck that the account has never scored or that are due at 'now',
+    // in deck order. see game::queue::expand_candidates
+    fn get_study_candidates(
+        &self,
+        deck_id: u64,
+        account_id: u64,
+        now: u32,
+    ) -> Result<Vec<(Card, u16, Option<CardScore>)>, DBApiError>;
+    fn get_deck_reviews(&self, deck_id: u64, account_id: u64) -> Result<Vec<Review>, DBApiError>;
+    // bulk insert previously recorded scores and reviews, e.g. from a deck bundle
+    fn insert_history(&self, scores: &[CardScore], reviews: &[Review]) -> Result<(), DBApiError>;
+}
+
+pub trait StudyApi {
+    fn insert(&self, study_session: &StudySession) -> Result<(), DBApiError>;
+
+    fn find(&self, study_id: &u64) -> Result<StudySession, DBApiError>;
+
+    // most recently started session that hasn't been finished
+    fn find_open(&self, account_id: u64, deck_id: u64) -> Result<StudySession, DBApiError>;
+
+    // count one more answer against the session and store its score and review, all in one
+    // transaction. NotFound, and nothing is stored, if the session has already been finished
+    fn record_answer(
+        &self,
+        study_id: u64,
+        correct: bool,
+        score: &CardScore,
+        review: &Review,
+    ) -> Result<(), DBApiError>;
+
+    // close the session at 'ended' without touching its counts.
+    // a session that has already been finished keeps its first end time
+    fn finish(&self, study_id: u64, ended: u32) -> Result<(), DBApiError>;
+}
+
+pub trait MemberApi {
+    // add the member, or change their role if they are already a member
+    fn upsert(&self, member: &DeckMember) -> Result<(), DBApiError>;
+
+    fn find(&self, deck_id: u64, account_id: u64) -> Result<DeckMember, DBApiError>;
+
+    fn find_members_for_deck(&self, deck_id: u64) -> Result<Vec<DeckMember>, DBApiError>;
+
+    fn delete(&self, deck_id: u64, account_id: u64) -> Result<(), DBApiError>;
+}
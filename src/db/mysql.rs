@@ -0,0 +1,938 @@
+// MySQL implementation of the db traits, using the 'card_pos_asc'/'card_pos_desc' views
+// to shift card positions in order.
+
+use diesel::mysql::Mysql;
+use diesel::prelude::*;
+use diesel::r2d2 as diesel_r2d2;
+use diesel::r2d2::ConnectionManager;
+use diesel::result::DatabaseErrorKind as DBErrorKind;
+use diesel::result::Error as DieselError;
+use diesel::sql_types::Bool;
+use diesel_migrations::{EmbeddedMigrations, MigrationHarness, embed_migrations};
+
+use super::{
+    AnswerApi, CardApi, DBApiError, DeckApi, DeckRecords, DistractorApi, GameApi, INSERT_CHUNK_SIZE,
+    MediaApi, MemberApi, StudyApi, like_pattern,
+};
+use crate::card::choice::Distractor;
+use crate::card::typed::AcceptedAnswer;
+use crate::card::{Card, CardKind};
+use crate::config::PoolConfig;
+use crate::deck::{Deck, DeckMember, DeckRole, DeckVisibility};
+use crate::game::queue::expand_candidates;
+use crate::game::{CardScore, Review};
+use crate::media::CardMedia;
+use crate::schema::card_answers;
+use crate::schema::card_answers::dsl as AnswerDSL;
+use crate::schema::card_distractors;
+use crate::schema::card_distractors::dsl as DistractorDSL;
+use crate::schema::card_media;
+use crate::schema::card_media::dsl as MediaDSL;
+use crate::schema::card_pos_asc::dsl as CardPosAscDSL;
+use crate::schema::card_pos_desc::dsl as CardPosDescDSL;
+use crate::schema::cards;
+use crate::schema::cards::dsl as CardDSL;
+use crate::schema::cardscores;
+use crate::schema::cardscores::dsl as ScoreDSL;
+use crate::schema::deck_members::dsl as MemberDSL;
+use crate::schema::decks;
+use crate::schema::decks::dsl as DeckDSL;
+use crate::schema::reviews;
+use crate::schema::reviews::dsl as ReviewDSL;
+use crate::schema::study_sessions::dsl as StudyDSL;
+use crate::study::StudySession;
+
+pub type MysqlManager = diesel_r2d2::Pool<diesel_r2d2::ConnectionManager<MysqlConnection>>;
+
+pub fn new_manager(connect_string: String) -> Result<MysqlManager, DBApiError> {
+    return new_manager_with_config(connect_string, &PoolConfig::default());
+}
+
+pub fn new_manager_with_config(
+    connect_string: String,
+    pool_config: &PoolConfig,
+) -> Result<MysqlManager, DBApiError> {
+    let connection_manager = ConnectionManager::new(connect_string.as_str());
+    // build the database connection pool
+    let pool = pool_config.builder().build(connection_manager)?;
+    return Ok(pool);
+}
+
+// every migration in 'migrations/mysql', compiled into the library
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations/mysql");
+
+// create or upgrade all of the tables and views used by this module
+pub fn run_migrations(db_manager: &MysqlManager) -> Result<(), DBApiError> {
+    let mut conn = db_manager.get()?;
+    conn.run_pending_migrations(MIGRATIONS)
+        .map_err(|err| DBApiError::MigrationError(err))?;
+    return Ok(());
+}
+
+// TODO: since crud operations for all types are basically the same,
+// we could/should move them all into a DBManager impl with generic functions.
+// and just keep the type specific code in their own api impls
+// insert the deck and its cards, inside the caller's transaction
+fn insert_deck(conn: &mut MysqlConnection, deck: &Deck, cards: &[Card]) -> Result<(), DBApiError> {
+    diesel::insert_into(DeckDSL::decks)
+        .values(deck)
+        .execute(conn)?;
+    // keep each statement well under the placeholder limit
+    for chunk in cards.chunks(INSERT_CHUNK_SIZE) {
+        diesel::insert_into(CardDSL::cards)
+            .values(chunk)
+            .execute(conn)?;
+    }
+    return Ok(());
+}
+
+// insert the deck and everything that belongs to it, inside the caller's transaction
+fn insert_deck_records(
+    conn: &mut MysqlConnection,
+    records: &DeckRecords,
+) -> Result<(), DBApiError> {
+    insert_deck(conn, &records.deck, &records.cards)?;
+    for chunk in records.distractors.chunks(INSERT_CHUNK_SIZE) {
+        diesel::insert_into(DistractorDSL::card_distractors)
+            .values(chunk)
+            .execute(conn)?;
+    }
+    for chunk in records.accepted_answers.chunks(INSERT_CHUNK_SIZE) {
+        diesel::insert_into(AnswerDSL::card_answers)
+            .values(chunk)
+            .execute(conn)?;
+    }
+    for chunk in records.scores.chunks(INSERT_CHUNK_SIZE) {
+        diesel::insert_into(ScoreDSL::cardscores)
+            .values(chunk)
+            .execute(conn)?;
+    }
+    for chunk in records.reviews.chunks(INSERT_CHUNK_SIZE) {
+        diesel::insert_into(ReviewDSL::reviews)
+            .values(chunk)
+            .execute(conn)?;
+    }
+    for chunk in records.media.chunks(INSERT_CHUNK_SIZE) {
+        diesel::insert_into(MediaDSL::card_media)
+            .values(chunk)
+            .execute(conn)?;
+    }
+    return Ok(());
+}
+
+impl DeckApi for MysqlManager {
+    fn insert(&self, deck_info: &Deck) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        match diesel::insert_into(DeckDSL::decks)
+            .values(deck_info)
+            .execute(&mut conn)
+        {
+            Ok(_) => return Ok(()),
+            Err(err) => return Err(DBApiError::OtherError(err)),
+        }
+    }
+
+    fn insert_with_cards(&self, deck: &Deck, cards: &[Card]) -> Result<(), DBApiError> {
+        // DO NOT ALLOW USER TO MOVE CARD TO RESERVED POSITION 0
+        if cards.iter().any(|card| card.deck_pos == 0) {
+            return Err(DBApiError::NotAllowed);
+        }
+        let mut conn = self.get()?;
+        conn.transaction::<(), DBApiError, _>(|conn| insert_deck(conn, deck, cards))?;
+        return Ok(());
+    }
+
+    fn insert_records(&self, records: &[DeckRecords]) -> Result<(), DBApiError> {
+        // DO NOT ALLOW USER TO MOVE CARD TO RESERVED POSITION 0
+        let cards = records.iter().flat_map(|deck_records| deck_records.cards.iter());
+        if cards.clone().any(|card| card.deck_pos == 0) {
+            return Err(DBApiError::NotAllowed);
+        }
+        let mut conn = self.get()?;
+        conn.transaction::<(), DBApiError, _>(|conn| {
+            for deck_records in records {
+                insert_deck_records(conn, deck_records)?;
+            }
+            return Ok(());
+        })?;
+        return Ok(());
+    }
+
+    fn find(&self, deck_info_id: &u64) -> Result<Deck, DBApiError> {
+        let mut conn = self.get()?;
+        let deck_info = DeckDSL::decks.find(deck_info_id).first(&mut conn)?;
+        return Ok(deck_info);
+    }
+
+    fn find_for_account(
+        &self,
+        deck_id: u64,
+        account_id: u64,
+    ) -> Result<(Deck, Option<DeckRole>), DBApiError> {
+        let mut conn = self.get()?;
+        let (deck, member_role): (Deck, Option<u8>) = DeckDSL::decks
+            .left_join(
+                MemberDSL::deck_members.on(MemberDSL::deck_id
+                    .eq(DeckDSL::id)
+                    .and(MemberDSL::account_id.eq(account_id))),
+            )
+            .filter(DeckDSL::id.eq(deck_id))
+            .select((decks::all_columns, MemberDSL::role.nullable()))
+            .first(&mut conn)?;
+        let role = deck.role_for(account_id, member_role.and_then(DeckRole::from_id));
+        return Ok((deck, role));
+    }
+
+    fn find_decks_for_owner(&self, owner: &u64) -> Result<Vec<Deck>, DBApiError> {
+        let mut conn = self.get()?;
+        let owner_decks = DeckDSL::decks
+            .filter(DeckDSL::owner_id.eq(owner))
+            .get_results(&mut conn)?;
+        // TODO: should result be sorted in any convenient way?
+        return Ok(owner_decks);
+    }
+
+    fn find_decks_for_member(&self, account_id: &u64) -> Result<Vec<Deck>, DBApiError> {
+        let mut conn = self.get()?;
+        let shared_decks = DeckDSL::decks
+            .inner_join(MemberDSL::deck_members)
+            .filter(MemberDSL::account_id.eq(account_id))
+            .select(decks::all_columns)
+            .get_results(&mut conn)?;
+        return Ok(shared_decks);
+    }
+
+    fn find_public_decks(
+        &self,
+        search: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<Deck>, DBApiError> {
+        let mut conn = self.get()?;
+        let mut query = DeckDSL::decks
+            .filter(DeckDSL::visibility.eq(DeckVisibility::Public.id()))
+            .into_boxed();
+        if let Some(search) = search {
+            query = query.filter(DeckDSL::name.like(like_pattern(search)).escape('\\'));
+        }
+        let public_decks = query
+            .order((DeckDSL::name.asc(), DeckDSL::id.asc()))
+            .offset(offset)
+            .limit(limit)
+            .get_results(&mut conn)?;
+        return Ok(public_decks);
+    }
+
+    fn update(&self, deck_info: &Deck) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        diesel::update(deck_info)
+            .set(deck_info)
+            .execute(&mut conn)?;
+        return Ok(());
+    }
+
+    fn delete(&self, deck_info_id: &u64) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        let result = diesel::delete(DeckDSL::decks.filter(DeckDSL::id.eq(deck_info_id)))
+            .execute(&mut conn)?;
+        if result == 1 {
+            return Ok(());
+        } else {
+            return Err(DBApiError::NotFound);
+        }
+    }
+
+    fn delete_for_owner(&self, deck_id: u64, account_id: u64) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        let result = diesel::delete(
+            DeckDSL::decks.filter(DeckDSL::id.eq(deck_id).and(owned_by(account_id))),
+        )
+        .execute(&mut conn)?;
+        if result == 1 {
+            return Ok(());
+        } else {
+            return Err(DBApiError::NotFound);
+        }
+    }
+}
+
+// decks the account created, or was shared with as an owner
+fn owned_by(account_id: u64) -> Box<dyn BoxableExpression<decks::table, Mysql, SqlType = Bool>> {
+    let shared = MemberDSL::deck_members
+        .filter(
+            MemberDSL::account_id
+                .eq(account_id)
+                .and(MemberDSL::role.ge(DeckRole::Owner.id())),
+        )
+        .select(MemberDSL::deck_id);
+    return Box::new(
+        DeckDSL::owner_id
+            .eq(account_id)
+            .or(DeckDSL::id.eq_any(shared)),
+    );
+}
+
+// cards in decks the account owns, or was shared with as an editor
+fn editable_by(account_id: u64) -> Box<dyn BoxableExpression<cards::table, Mysql, SqlType = Bool>> {
+    let owned = DeckDSL::decks
+        .filter(DeckDSL::owner_id.eq(account_id))
+        .select(DeckDSL::id);
+    let shared = MemberDSL::deck_members
+        .filter(
+            MemberDSL::account_id
+                .eq(account_id)
+                .and(MemberDSL::role.ge(DeckRole::Editor.id())),
+        )
+        .select(MemberDSL::deck_id);
+    return Box::new(
+        CardDSL::deck_id
+            .eq_any(owned)
+            .or(CardDSL::deck_id.eq_any(shared)),
+    );
+}
+
+// delete the card and shift all of the following cards down 1 position, lowest first
+fn delete_card(conn: &mut MysqlConnection, card: &Card) -> Result<(), DBApiError> {
+    diesel::delete(CardDSL::cards.filter(CardDSL::id.eq(card.id))).execute(conn)?;
+    diesel::update(
+        CardPosAscDSL::card_pos_asc.filter(
+            CardPosAscDSL::deck_id
+                .eq(card.deck_id)
+                .and(CardPosAscDSL::deck_pos.ge(card.deck_pos)),
+        ),
+    )
+    .set(CardPosAscDSL::deck_pos.eq(CardPosAscDSL::deck_pos - 1))
+    .execute(conn)?;
+    return Ok(());
+}
+
+impl CardApi for MysqlManager {
+    fn insert(&self, card: &Card) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        // DO NOT ALLOW USER TO MOVE CARD TO RESERVED POSITION 0
+        if card.deck_pos == 0 {
+            return Err(DBApiError::NotAllowed);
+        }
+        match diesel::insert_into(CardDSL::cards)
+            .values(card)
+            .execute(&mut conn)
+        {
+            Ok(_) => return Ok(()),
+            Err(err) => return Err(DBApiError::OtherError(err)),
+        }
+    }
+
+    fn find(&self, card_id: &u64) -> Result<Card, DBApiError> {
+        let mut conn = self.get()?;
+        let card = CardDSL::cards.find(card_id).first(&mut conn)?;
+        return Ok(card);
+    }
+
+    fn find_for_account(
+        &self,
+        card_id: u64,
+        account_id: u64,
+    ) -> Result<(Card, Deck, Option<DeckRole>), DBApiError> {
+        let mut conn = self.get()?;
+        let (card, deck, member_role): (Card, Deck, Option<u8>) = CardDSL::cards
+            .inner_join(DeckDSL::decks)
+            .left_join(
+                MemberDSL::deck_members.on(MemberDSL::deck_id
+                    .eq(CardDSL::deck_id)
+                    .and(MemberDSL::account_id.eq(account_id))),
+            )
+            .filter(CardDSL::id.eq(card_id))
+            .select((
+                cards::all_columns,
+                decks::all_columns,
+                MemberDSL::role.nullable(),
+            ))
+            .first(&mut conn)?;
+        let role = deck.role_for(account_id, member_role.and_then(DeckRole::from_id));
+        return Ok((card, deck, role));
+    }
+
+    fn find_cards_for_deck(&self, card_deck_id: &u64) -> Result<Vec<Card>, DBApiError> {
+        let mut conn = self.get()?;
+        let deck_cards = CardDSL::cards
+            .filter(CardDSL::deck_id.eq(card_deck_id))
+            .order(CardDSL::deck_pos.asc())
+            .get_results(&mut conn)?;
+        // TODO: should result be sorted in any convenient way? position?
+        return Ok(deck_cards);
+    }
+
+    fn update(&self, card: &Card) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        diesel::update(card).set(card).execute(&mut conn)?;
+        return Ok(());
+    }
+
+    fn update_position(
+        &self,
+        card_id: u64,
+        deck_id: u64,
+        orig_pos: u16,
+        new_pos: u16,
+    ) -> Result<(), DBApiError> {
+        // DO NOT ALLOW USER TO MOVE CARD TO RESERVED POSITION 0
+        if new_pos == 0 {
+            return Err(DBApiError::NotAllowed);
+        }
+        // ignore without change
+        if new_pos == orig_pos {
+            return Ok(());
+        }
+
+        let mut conn = self.get()?;
+
+        conn.transaction::<(), DBApiError, _>(|conn| {
+            // try to set the target card to position 0
+            // if not found, then the card doesn't exist at the original position the user expects
+            // - and therefore should be an error
+            let result = diesel::update(
+                CardDSL::cards.filter(
+                    CardDSL::deck_id
+                        .eq(deck_id)
+                        .and(CardDSL::id.eq(card_id))
+                        .and(CardDSL::deck_pos.eq(orig_pos)),
+                ),
+            )
+            .set(CardDSL::deck_pos.eq(0))
+            .execute(conn)?;
+            if result == 0 {
+                // no matching card
+                return Err(DBApiError::NotFound);
+            } // NOTE:  result > 1 should be impossible based on card_id being primary key
+            // shift all cards between new and orig
+            if new_pos < orig_pos {
+                diesel::update(
+                    CardPosDescDSL::card_pos_desc.filter(
+                        CardPosDescDSL::deck_id
+                            .eq(deck_id)
+                            .and(CardPosDescDSL::deck_pos.le(orig_pos))
+                            .and(CardPosDescDSL::deck_pos.ge(new_pos)),
+                    ),
+                )
+                .set(CardPosDescDSL::deck_pos.eq(CardPosDescDSL::deck_pos + 1))
+                .execute(conn)?;
+            } else {
+                diesel::update(
+                    CardPosAscDSL::card_pos_asc.filter(
+                        CardPosAscDSL::deck_id
+                            .eq(deck_id)
+                            .and(CardPosAscDSL::deck_pos.le(new_pos))
+                            .and(CardPosAscDSL::deck_pos.ge(orig_pos)),
+                    ),
+                )
+                .set(CardPosAscDSL::deck_pos.eq(CardPosAscDSL::deck_pos - 1))
+                .execute(conn)?;
+            }
+            // move the card into final position
+            diesel::update(CardDSL::cards.filter(CardDSL::id.eq(card_id)))
+                .set(CardDSL::deck_pos.eq(new_pos))
+                .execute(conn)?;
+            return Ok(());
+        })?;
+
+        return Ok(());
+    }
+
+    fn delete(&self, card_id: &u64) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        conn.transaction::<(), DBApiError, _>(|conn| {
+            // get the card if exists
+            let card: Card = CardDSL::cards.find(card_id).first(conn)?;
+            return delete_card(conn, &card);
+        })?;
+        return Ok(());
+    }
+
+    fn update_for_editor(
+        &self,
+        card_id: u64,
+        account_id: u64,
+        question: Option<&str>,
+        answer: Option<&str>,
+        reversible: Option<bool>,
+    ) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        let result = diesel::update(
+            CardDSL::cards.filter(CardDSL::id.eq(card_id).and(editable_by(account_id))),
+        )
+        .set((
+            question.map(|question| CardDSL::question.eq(question)),
+            answer.map(|answer| CardDSL::answer.eq(answer)),
+            reversible.map(|reversible| CardDSL::reversible.eq(reversible)),
+        ))
+        .execute(&mut conn)?;
+        if result == 1 {
+            return Ok(());
+        } else {
+            return Err(DBApiError::NotFound);
+        }
+    }
+
+    fn delete_for_editor(&self, card_id: u64, account_id: u64) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        conn.transaction::<(), DBApiError, _>(|conn| {
+            // get the card if exists, and the account may edit it
+            let card: Card = CardDSL::cards
+                .filter(CardDSL::id.eq(card_id).and(editable_by(account_id)))
+                .first(conn)?;
+            return delete_card(conn, &card);
+        })?;
+        return Ok(());
+    }
+}
+
+impl DistractorApi for MysqlManager {
+    fn find_for_card(&self, card_id: u64) -> Result<Vec<Distractor>, DBApiError> {
+        let mut conn = self.get()?;
+        let distractors = DistractorDSL::card_distractors
+            .filter(DistractorDSL::card_id.eq(card_id))
+            .order(DistractorDSL::position.asc())
+            .get_results(&mut conn)?;
+        return Ok(distractors);
+    }
+
+    fn find_for_deck(&self, deck_id: u64) -> Result<Vec<Distractor>, DBApiError> {
+        let mut conn = self.get()?;
+        let distractors = DistractorDSL::card_distractors
+            .inner_join(CardDSL::cards)
+            .filter(CardDSL::deck_id.eq(deck_id))
+            .select(card_distractors::all_columns)
+            .order((DistractorDSL::card_id.asc(), DistractorDSL::position.asc()))
+            .get_results(&mut conn)?;
+        return Ok(distractors);
+    }
+
+    fn insert(&self, distractors: &[Distractor]) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        conn.transaction::<(), DBApiError, _>(|conn| {
+            for chunk in distractors.chunks(INSERT_CHUNK_SIZE) {
+                diesel::insert_into(DistractorDSL::card_distractors)
+                    .values(chunk)
+                    .execute(conn)?;
+            }
+            return Ok(());
+        })?;
+        return Ok(());
+    }
+
+    fn replace_for_card(&self, card_id: u64, distractors: &[Distractor]) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        conn.transaction::<(), DBApiError, _>(|conn| {
+            diesel::delete(
+                DistractorDSL::card_distractors.filter(DistractorDSL::card_id.eq(card_id)),
+            )
+            .execute(conn)?;
+            if !distractors.is_empty() {
+                diesel::insert_into(DistractorDSL::card_distractors)
+                    .values(distractors)
+                    .execute(conn)?;
+            }
+            return Ok(());
+        })?;
+        return Ok(());
+    }
+}
+
+impl AnswerApi for MysqlManager {
+    fn find_for_card(&self, card_id: u64) -> Result<Vec<AcceptedAnswer>, DBApiError> {
+        let mut conn = self.get()?;
+        let answers = AnswerDSL::card_answers
+            .filter(AnswerDSL::card_id.eq(card_id))
+            .order(AnswerDSL::position.asc())
+            .get_results(&mut conn)?;
+        return Ok(answers);
+    }
+
+    fn find_for_deck(&self, deck_id: u64) -> Result<Vec<AcceptedAnswer>, DBApiError> {
+        let mut conn = self.get()?;
+        let answers = AnswerDSL::card_answers
+            .inner_join(CardDSL::cards)
+            .filter(CardDSL::deck_id.eq(deck_id))
+            .select(card_answers::all_columns)
+            .order((AnswerDSL::card_id.asc(), AnswerDSL::position.asc()))
+            .get_results(&mut conn)?;
+        return Ok(answers);
+    }
+
+    fn insert(&self, answers: &[AcceptedAnswer]) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        conn.transaction::<(), DBApiError, _>(|conn| {
+            for chunk in answers.chunks(INSERT_CHUNK_SIZE) {
+                diesel::insert_into(AnswerDSL::card_answers)
+                    .values(chunk)
+                    .execute(conn)?;
+            }
+            return Ok(());
+        })?;
+        return Ok(());
+    }
+
+    fn replace_for_card(&self, card_id: u64, answers: &[AcceptedAnswer]) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        conn.transaction::<(), DBApiError, _>(|conn| {
+            diesel::delete(AnswerDSL::card_answers.filter(AnswerDSL::card_id.eq(card_id)))
+                .execute(conn)?;
+            if !answers.is_empty() {
+                diesel::insert_into(AnswerDSL::card_answers)
+                    .values(answers)
+                    .execute(conn)?;
+            }
+            return Ok(());
+        })?;
+        return Ok(());
+    }
+}
+
+impl MediaApi for MysqlManager {
+    fn insert(&self, media: &CardMedia) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        diesel::insert_into(MediaDSL::card_media)
+            .values(media)
+            .execute(&mut conn)?;
+        return Ok(());
+    }
+
+    fn insert_limited(&self, media: &CardMedia, max: usize) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        conn.transaction::<(), DBApiError, _>(|conn| {
+            // lock the card, so other uploads to it wait until this one is counted
+            CardDSL::cards
+                .find(media.card_id)
+                .select(CardDSL::id)
+                .for_update()
+                .first::<u64>(conn)?;
+            let attached: i64 = MediaDSL::card_media
+                .filter(MediaDSL::card_id.eq(media.card_id))
+                .count()
+                .get_result(conn)?;
+            if attached as usize >= max {
+                return Err(DBApiError::NotAllowed);
+            }
+            diesel::insert_into(MediaDSL::card_media)
+                .values(media)
+                .execute(conn)?;
+            return Ok(());
+        })?;
+        return Ok(());
+    }
+
+    fn find(&self, media_id: u64) -> Result<CardMedia, DBApiError> {
+        let mut conn = self.get()?;
+        let media = MediaDSL::card_media.find(media_id).first(&mut conn)?;
+        return Ok(media);
+    }
+
+    fn find_for_card(&self, card_id: u64) -> Result<Vec<CardMedia>, DBApiError> {
+        let mut conn = self.get()?;
+        let media = MediaDSL::card_media
+            .filter(MediaDSL::card_id.eq(card_id))
+            .order(MediaDSL::id.asc())
+            .get_results(&mut conn)?;
+        return Ok(media);
+    }
+
+    fn find_for_deck(&self, deck_id: u64) -> Result<Vec<CardMedia>, DBApiError> {
+        let mut conn = self.get()?;
+        let media = MediaDSL::card_media
+            .inner_join(CardDSL::cards)
+            .filter(CardDSL::deck_id.eq(deck_id))
+            .select(card_media::all_columns)
+            .order((MediaDSL::card_id.asc(), MediaDSL::id.asc()))
+            .get_results(&mut conn)?;
+        return Ok(media);
+    }
+
+    fn find_existing(&self, media_ids: &[u64]) -> Result<Vec<u64>, DBApiError> {
+        let mut conn = self.get()?;
+        let mut existing = Vec::with_capacity(media_ids.len());
+        for chunk in media_ids.chunks(INSERT_CHUNK_SIZE) {
+            let found: Vec<u64> = MediaDSL::card_media
+                .filter(MediaDSL::id.eq_any(chunk))
+                .select(MediaDSL::id)
+                .get_results(&mut conn)?;
+            existing.extend(found);
+        }
+        return Ok(existing);
+    }
+
+    fn delete(&self, media_id: u64) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        let result = diesel::delete(MediaDSL::card_media.find(media_id)).execute(&mut conn)?;
+        if result == 1 {
+            return Ok(());
+        } else {
+            return Err(DBApiError::NotFound);
+        }
+    }
+}
+
+// save the score and append its review, inside the caller's transaction
+fn write_score(
+    conn: &mut MysqlConnection,
+    score: &CardScore,
+    review: &Review,
+) -> Result<(), DBApiError> {
+    // NOTE: Mysql does not support Upsert
+    // so we must try insert first, then update on duplicate
+    match diesel::insert_into(ScoreDSL::cardscores)
+        .values(score)
+        .execute(conn)
+    {
+        Ok(_) => {}
+        Err(error) => match error {
+            DieselError::DatabaseError(db_error, _) => {
+                match db_error {
+                    DBErrorKind::UniqueViolation => {
+                        // already exists, time to try update
+                        diesel::update(score).set(score).execute(conn)?;
+                    }
+                    _ => return Err(DBApiError::from(error)),
+                }
+            }
+            _ => return Err(DBApiError::from(error)),
+        },
+    }
+    diesel::insert_into(ReviewDSL::reviews)
+        .values(review)
+        .execute(conn)?;
+    return Ok(());
+}
+
+impl GameApi for MysqlManager {
+    fn find_score(
+        &self,
+        account_id: u64,
+        card_id: u64,
+        item: u16,
+    ) -> Result<CardScore, DBApiError> {
+        let mut conn = self.get()?;
+        let score = ScoreDSL::cardscores
+            .find((account_id, card_id, item))
+            .first(&mut conn)?;
+        return Ok(score);
+    }
+
+    fn update_score(&self, score: &CardScore, review: &Review) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        conn.transaction::<(), DBApiError, _>(|conn| write_score(conn, score, review))?;
+        return Ok(());
+    }
+
+    fn get_deck_scores(&self, deck_id: u64, account_id: u64) -> Result<Vec<CardScore>, DBApiError> {
+        let mut conn = self.get()?;
+        let deck_scores = ScoreDSL::cardscores
+            .inner_join(CardDSL::cards)
+            .select(cardscores::all_columns)
+            .filter(
+                CardDSL::deck_id
+                    .eq(deck_id)
+                    .and(ScoreDSL::account_id.eq(account_id)),
+            )
+            .load::<CardScore>(&mut conn)?;
+        // TODO: should result be sorted in any convenient way? position?
+        return Ok(deck_scores);
+    }
+
+    fn get_due_scores(
+        &self,
+        deck_id: u64,
+        account_id: u64,
+        now: u32,
+    ) -> Result<Vec<CardScore>, DBApiError> {
+        let mut conn = self.get()?;
+        let due_scores = ScoreDSL::cardscores
+            .inner_join(CardDSL::cards)
+            .select(cardscores::all_columns)
+            .filter(
+                CardDSL::deck_id
+                    .eq(deck_id)
+                    .and(ScoreDSL::account_id.eq(account_id))
+                    .and(ScoreDSL::due.le(now)),
+            )
+            .order(ScoreDSL::due.asc())
+            .load::<CardScore>(&mut conn)?;
+        return Ok(due_scores);
+    }
+
+    fn get_study_candidates(
+        &self,
+        deck_id: u64,
+        account_id: u64,
+        now: u32,
+    ) -> Result<Vec<(Card, u16, Option<CardScore>)>, DBApiError> {
+        let mut conn = self.get()?;
+        // basic cards without a reverse have a single item, so only unscored and due ones
+        // are needed. other cards need all of their scores to find their unscored items
+        let rows = CardDSL::cards
+            .left_join(
+                ScoreDSL::cardscores.on(ScoreDSL::card_id
+                    .eq(CardDSL::id)
+                    .and(ScoreDSL::account_id.eq(account_id))),
+            )
+            .filter(
+                CardDSL::deck_id.eq(deck_id).and(
+                    ScoreDSL::card_id
+                        .nullable()
+                        .is_null()
+                        .or(ScoreDSL::due.nullable().le(now))
+                        .or(CardDSL::kind.ne(CardKind::Basic.id()))
+                        .or(CardDSL::reversible.eq(true)),
+                ),
+            )
+            .order(CardDSL::deck_pos.asc())
+            .select((cards::all_columns, cardscores::all_columns.nullable()))
+            .load::<(Card, Option<CardScore>)>(&mut conn)?;
+        return Ok(expand_candidates(rows, now));
+    }
+
+    fn get_deck_reviews(&self, deck_id: u64, account_id: u64) -> Result<Vec<Review>, DBApiError> {
+        let mut conn = self.get()?;
+        let deck_reviews = ReviewDSL::reviews
+            .inner_join(CardDSL::cards)
+            .select(reviews::all_columns)
+            .filter(
+                CardDSL::deck_id
+                    .eq(deck_id)
+                    .and(ReviewDSL::account_id.eq(account_id)),
+            )
+            .order((ReviewDSL::answered_at.asc(), ReviewDSL::id.asc()))
+            .load::<Review>(&mut conn)?;
+        return Ok(deck_reviews);
+    }
+
+    fn insert_history(&self, scores: &[CardScore], reviews: &[Review]) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        conn.transaction::<(), DBApiError, _>(|conn| {
+            for chunk in scores.chunks(INSERT_CHUNK_SIZE) {
+                diesel::insert_into(ScoreDSL::cardscores)
+                    .values(chunk)
+                    .execute(conn)?;
+            }
+            for chunk in reviews.chunks(INSERT_CHUNK_SIZE) {
+                diesel::insert_into(ReviewDSL::reviews)
+                    .values(chunk)
+                    .execute(conn)?;
+            }
+            return Ok(());
+        })?;
+        return Ok(());
+    }
+}
+
+impl StudyApi for MysqlManager {
+    fn insert(&self, study_session: &StudySession) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        diesel::insert_into(StudyDSL::study_sessions)
+            .values(study_session)
+            .execute(&mut conn)?;
+        return Ok(());
+    }
+
+    fn find(&self, study_id: &u64) -> Result<StudySession, DBApiError> {
+        let mut conn = self.get()?;
+        let study_session = StudyDSL::study_sessions.find(study_id).first(&mut conn)?;
+        return Ok(study_session);
+    }
+
+    fn find_open(&self, account_id: u64, deck_id: u64) -> Result<StudySession, DBApiError> {
+        let mut conn = self.get()?;
+        let study_session = StudyDSL::study_sessions
+            .filter(
+                StudyDSL::account_id
+                    .eq(account_id)
+                    .and(StudyDSL::deck_id.eq(deck_id))
+                    .and(StudyDSL::ended.is_null()),
+            )
+            .order(StudyDSL::started.desc())
+            .first(&mut conn)?;
+        return Ok(study_session);
+    }
+
+    fn record_answer(
+        &self,
+        study_id: u64,
+        correct: bool,
+        score: &CardScore,
+        review: &Review,
+    ) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        conn.transaction::<(), DBApiError, _>(|conn| {
+            let result = diesel::update(
+                StudyDSL::study_sessions
+                    .filter(StudyDSL::id.eq(study_id).and(StudyDSL::ended.is_null())),
+            )
+            .set((
+                StudyDSL::cards_seen.eq(StudyDSL::cards_seen + 1),
+                StudyDSL::correct_count.eq(StudyDSL::correct_count + correct as u16),
+            ))
+            .execute(conn)?;
+            if result != 1 {
+                return Err(DBApiError::NotFound);
+            }
+            return write_score(conn, score, review);
+        })?;
+        return Ok(());
+    }
+
+    fn finish(&self, study_id: u64, ended: u32) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        diesel::update(
+            StudyDSL::study_sessions
+                .filter(StudyDSL::id.eq(study_id).and(StudyDSL::ended.is_null())),
+        )
+        .set(StudyDSL::ended.eq(ended))
+        .execute(&mut conn)?;
+        return Ok(());
+    }
+}
+
+impl MemberApi for MysqlManager {
+    fn upsert(&self, member: &DeckMember) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        // NOTE: Mysql does not support Upsert
+        // so we must try insert first, then update on duplicate
+        match diesel::insert_into(MemberDSL::deck_members)
+            .values(member)
+            .execute(&mut conn)
+        {
+            Ok(_) => return Ok(()),
+            Err(DieselError::DatabaseError(DBErrorKind::UniqueViolation, _)) => {
+                diesel::update(member).set(member).execute(&mut conn)?;
+                return Ok(());
+            }
+            Err(error) => return Err(DBApiError::from(error)),
+        }
+    }
+
+    fn find(&self, deck_id: u64, account_id: u64) -> Result<DeckMember, DBApiError> {
+        let mut conn = self.get()?;
+        let member = MemberDSL::deck_members
+            .find((deck_id, account_id))
+            .first(&mut conn)?;
+        return Ok(member);
+    }
+
+    fn find_members_for_deck(&self, deck_id: u64) -> Result<Vec<DeckMember>, DBApiError> {
+        let mut conn = self.get()?;
+        let members = MemberDSL::deck_members
+            .filter(MemberDSL::deck_id.eq(deck_id))
+            .get_results(&mut conn)?;
+        return Ok(members);
+    }
+
+    fn delete(&self, deck_id: u64, account_id: u64) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        let result = diesel::delete(MemberDSL::deck_members.find((deck_id, account_id)))
+            .execute(&mut conn)?;
+        if result == 1 {
+            return Ok(());
+        } else {
+            return Err(DBApiError::NotFound);
+        }
+    }
+}
@@ -0,0 +1,493 @@
+// Records for backends without unsigned integer columns.
+// ids are stored bit-for-bit in BIGINT columns, and the smaller unsigned fields
+// are widened to the next signed type so every value still fits.
+
+use crate::card::Card;
+use crate::card::choice::Distractor;
+use crate::card::typed::AcceptedAnswer;
+use crate::deck::{Deck, DeckMember};
+use crate::game::{CardScore, Review};
+use crate::media::CardMedia;
+use crate::study::StudySession;
+
+pub mod schema {
+    use diesel::{allow_tables_to_appear_in_same_query, joinable, table};
+
+    table! {
+        cards (id) {
+            id -> BigInt,
+            deck_id -> BigInt,
+            deck_pos -> Integer,
+            question -> Text,
+            answer -> Text,
+            last_updated -> BigInt,
+            kind -> SmallInt,
+            reversible -> Bool,
+        }
+    }
+
+    table! {
+        card_answers (card_id, position) {
+            card_id -> BigInt,
+            position -> Integer,
+            text -> Text,
+        }
+    }
+
+    table! {
+        card_distractors (card_id, position) {
+            card_id -> BigInt,
+            position -> Integer,
+            text -> Text,
+        }
+    }
+
+    table! {
+        card_media (id) {
+            id -> BigInt,
+            card_id -> BigInt,
+            side -> SmallInt,
+            content_type -> Text,
+            byte_size -> BigInt,
+            uploaded -> BigInt,
+        }
+    }
+
+    table! {
+        cardscores (account_id, card_id, item) {
+            account_id -> BigInt,
+            card_id -> BigInt,
+            item -> Integer,
+            score -> SmallInt,
+            ease_factor -> Float,
+            interval -> BigInt,
+            repetitions -> Integer,
+            due -> BigInt,
+            last_reviewed -> BigInt,
+            stability -> Float,
+            difficulty -> Float,
+        }
+    }
+
+    table! {
+        deck_members (deck_id, account_id) {
+            deck_id -> BigInt,
+            account_id -> BigInt,
+            role -> SmallInt,
+        }
+    }
+
+    table! {
+        decks (id) {
+            id -> BigInt,
+            name -> Text,
+            owner_id -> BigInt,
+            last_updated -> BigInt,
+            scheduler -> SmallInt,
+            visibility -> SmallInt,
+        }
+    }
+
+    table! {
+        reviews (id) {
+            id -> BigInt,
+            account_id -> BigInt,
+            card_id -> BigInt,
+            item -> Integer,
+            grade -> SmallInt,
+            answered_at -> BigInt,
+            response_ms -> Nullable<BigInt>,
+            scheduler -> SmallInt,
+            state_before -> Text,
+            state_after -> Text,
+        }
+    }
+
+    table! {
+        study_sessions (id) {
+            id -> BigInt,
+            deck_id -> BigInt,
+            account_id -> BigInt,
+            started -> BigInt,
+            ended -> Nullable<BigInt>,
+            cards_seen -> Integer,
+            correct_count -> Integer,
+        }
+    }
+
+    joinable!(card_answers -> cards (card_id));
+    joinable!(card_distractors -> cards (card_id));
+    joinable!(card_media -> cards (card_id));
+    joinable!(cards -> decks (deck_id));
+    joinable!(cardscores -> cards (card_id));
+    joinable!(deck_members -> decks (deck_id));
+    joinable!(reviews -> cards (card_id));
+    joinable!(study_sessions -> decks (deck_id));
+
+    allow_tables_to_appear_in_same_query!(
+        card_answers,
+        card_distractors,
+        card_media,
+        cards,
+        cardscores,
+        deck_members,
+        decks,
+        reviews,
+        study_sessions,
+    );
+}
+
+use schema::{
+    card_answers, card_distractors, card_media, cards, cardscores, deck_members, decks, reviews,
+    study_sessions,
+};
+
+#[derive(AsChangeset, Identifiable, Insertable, Queryable)]
+#[table_name = "decks"]
+pub struct DeckRow {
+    pub id: i64,
+    pub name: String,
+    pub owner_id: i64,
+    pub last_updated: i64,
+    pub scheduler: i16,
+    pub visibility: i16,
+}
+
+impl From<&Deck> for DeckRow {
+    fn from(deck: &Deck) -> DeckRow {
+        DeckRow {
+            id: deck.id as i64,
+            name: deck.name.clone(),
+            owner_id: deck.owner_id as i64,
+            last_updated: deck.last_updated as i64,
+            scheduler: deck.scheduler as i16,
+            visibility: deck.visibility as i16,
+        }
+    }
+}
+
+impl From<DeckRow> for Deck {
+    fn from(row: DeckRow) -> Deck {
+        Deck {
+            id: row.id as u64,
+            name: row.name,
+            owner_id: row.owner_id as u64,
+            last_updated: row.last_updated as u32,
+            scheduler: row.scheduler as u8,
+            visibility: row.visibility as u8,
+        }
+    }
+}
+
+#[derive(AsChangeset, Identifiable, Insertable, Queryable)]
+#[table_name = "cards"]
+pub struct CardRow {
+    pub id: i64,
+    pub deck_id: i64,
+    pub deck_pos: i32,
+    pub question: String,
+    pub answer: String,
+    pub last_updated: i64,
+    pub kind: i16,
+    pub reversible: bool,
+}
+
+impl From<&Card> for CardRow {
+    fn from(card: &Card) -> CardRow {
+        CardRow {
+            id: card.id as i64,
+            deck_id: card.deck_id as i64,
+            deck_pos: card.deck_pos as i32,
+            question: card.question.clone(),
+            answer: card.answer.clone(),
+            last_updated: card.last_updated as i64,
+            kind: card.kind as i16,
+            reversible: card.reversible,
+        }
+    }
+}
+
+impl From<CardRow> for Card {
+    fn from(row: CardRow) -> Card {
+        Card {
+            id: row.id as u64,
+            deck_id: row.deck_id as u64,
+            deck_pos: row.deck_pos as u16,
+            question: row.question,
+            answer: row.answer,
+            last_updated: row.last_updated as u32,
+            kind: row.kind as u8,
+            reversible: row.reversible,
+        }
+    }
+}
+
+#[derive(AsChangeset, Identifiable, Insertable, Queryable)]
+#[table_name = "cardscores"]
+#[primary_key(account_id, card_id, item)]
+pub struct ScoreRow {
+    pub account_id: i64,
+    pub card_id: i64,
+    pub item: i32,
+    pub score: i16,
+    pub ease_factor: f32,
+    pub interval: i64,
+    pub repetitions: i32,
+    pub due: i64,
+    pub last_reviewed: i64,
+    pub stability: f32,
+    pub difficulty: f32,
+}
+
+impl From<&CardScore> for ScoreRow {
+    fn from(score: &CardScore) -> ScoreRow {
+        ScoreRow {
+            account_id: score.account_id as i64,
+            card_id: score.card_id as i64,
+            item: score.item as i32,
+            score: score.score as i16,
+            ease_factor: score.ease_factor,
+            interval: score.interval as i64,
+            repetitions: score.repetitions as i32,
+            due: score.due as i64,
+            last_reviewed: score.last_reviewed as i64,
+            stability: score.stability,
+            difficulty: score.difficulty,
+        }
+    }
+}
+
+impl From<ScoreRow> for CardScore {
+    fn from(row: ScoreRow) -> CardScore {
+        CardScore {
+            account_id: row.account_id as u64,
+            card_id: row.card_id as u64,
+            item: row.item as u16,
+            score: row.score as u8,
+            ease_factor: row.ease_factor,
+            interval: row.interval as u32,
+            repetitions: row.repetitions as u16,
+            due: row.due as u32,
+            last_reviewed: row.last_reviewed as u32,
+            stability: row.stability,
+            difficulty: row.difficulty,
+        }
+    }
+}
+
+#[derive(Identifiable, Insertable, Queryable)]
+#[table_name = "reviews"]
+pub struct ReviewRow {
+    pub id: i64,
+    pub account_id: i64,
+    pub card_id: i64,
+    pub item: i32,
+    pub grade: i16,
+    pub answered_at: i64,
+    pub response_ms: Option<i64>,
+    pub scheduler: i16,
+    pub state_before: String,
+    pub state_after: String,
+}
+
+impl From<&Review> for ReviewRow {
+    fn from(review: &Review) -> ReviewRow {
+        ReviewRow {
+            id: review.id as i64,
+            account_id: review.account_id as i64,
+            card_id: review.card_id as i64,
+            item: review.item as i32,
+            grade: review.grade as i16,
+            answered_at: review.answered_at as i64,
+            response_ms: review.response_ms.map(|ms| ms as i64),
+            scheduler: review.scheduler as i16,
+            state_before: review.state_before.clone(),
+            state_after: review.state_after.clone(),
+        }
+    }
+}
+
+impl From<ReviewRow> for Review {
+    fn from(row: ReviewRow) -> Review {
+        Review {
+            id: row.id as u64,
+            account_id: row.account_id as u64,
+            card_id: row.card_id as u64,
+            item: row.item as u16,
+            grade: row.grade as u8,
+            answered_at: row.answered_at as u32,
+            response_ms: row.response_ms.map(|ms| ms as u32),
+            scheduler: row.scheduler as u8,
+            state_before: row.state_before,
+            state_after: row.state_after,
+        }
+    }
+}
+
+#[derive(AsChangeset, Identifiable, Insertable, Queryable)]
+#[table_name = "study_sessions"]
+#[changeset_options(treat_none_as_null = "true")]
+pub struct StudyRow {
+    pub id: i64,
+    pub deck_id: i64,
+    pub account_id: i64,
+    pub started: i64,
+    pub ended: Option<i64>,
+    pub cards_seen: i32,
+    pub correct_count: i32,
+}
+
+impl From<&StudySession> for StudyRow {
+    fn from(study_session: &StudySession) -> StudyRow {
+        StudyRow {
+            id: study_session.id as i64,
+            deck_id: study_session.deck_id as i64,
+            account_id: study_session.account_id as i64,
+            started: study_session.started as i64,
+            ended: study_session.ended.map(|ended| ended as i64),
+            cards_seen: study_session.cards_seen as i32,
+            correct_count: study_session.correct_count as i32,
+        }
+    }
+}
+
+impl From<StudyRow> for StudySession {
+    fn from(row: StudyRow) -> StudySession {
+        StudySession {
+            id: row.id as u64,
+            deck_id: row.deck_id as u64,
+            account_id: row.account_id as u64,
+            started: row.started as u32,
+            ended: row.ended.map(|ended| ended as u32),
+            cards_seen: row.cards_seen as u16,
+            correct_count: row.correct_count as u16,
+        }
+    }
+}
+
+#[derive(AsChangeset, Identifiable, Insertable, Queryable)]
+#[table_name = "deck_members"]
+#[primary_key(deck_id, account_id)]
+pub struct MemberRow {
+    pub deck_id: i64,
+    pub account_id: i64,
+    pub role: i16,
+}
+
+impl From<&DeckMember> for MemberRow {
+    fn from(member: &DeckMember) -> MemberRow {
+        MemberRow {
+            deck_id: member.deck_id as i64,
+            account_id: member.account_id as i64,
+            role: member.role as i16,
+        }
+    }
+}
+
+impl From<MemberRow> for DeckMember {
+    fn from(row: MemberRow) -> DeckMember {
+        DeckMember {
+            deck_id: row.deck_id as u64,
+            account_id: row.account_id as u64,
+            role: row.role as u8,
+        }
+    }
+}
+
+#[derive(Insertable, Queryable)]
+#[table_name = "card_distractors"]
+pub struct DistractorRow {
+    pub card_id: i64,
+    pub position: i32,
+    pub text: String,
+}
+
+impl From<&Distractor> for DistractorRow {
+    fn from(distractor: &Distractor) -> DistractorRow {
+        DistractorRow {
+            card_id: distractor.card_id as i64,
+            position: distractor.position as i32,
+            text: distractor.text.clone(),
+        }
+    }
+}
+
+impl From<DistractorRow> for Distractor {
+    fn from(row: DistractorRow) -> Distractor {
+        Distractor {
+            card_id: row.card_id as u64,
+            position: row.position as u16,
+            text: row.text,
+        }
+    }
+}
+
+#[derive(Insertable, Queryable)]
+#[table_name = "card_answers"]
+pub struct AnswerRow {
+    pub card_id: i64,
+    pub position: i32,
+    pub text: String,
+}
+
+impl From<&AcceptedAnswer> for AnswerRow {
+    fn from(answer: &AcceptedAnswer) -> AnswerRow {
+        AnswerRow {
+            card_id: answer.card_id as i64,
+            position: answer.position as i32,
+            text: answer.text.clone(),
+        }
+    }
+}
+
+impl From<AnswerRow> for AcceptedAnswer {
+    fn from(row: AnswerRow) -> AcceptedAnswer {
+        AcceptedAnswer {
+            card_id: row.card_id as u64,
+            position: row.position as u16,
+            text: row.text,
+        }
+    }
+}
+
+#[derive(Insertable, Queryable)]
+#[table_name = "card_media"]
+pub struct MediaRow {
+    pub id: i64,
+    pub card_id: i64,
+    pub side: i16,
+    pub content_type: String,
+    pub byte_size: i64,
+    pub uploaded: i64,
+}
+
+impl From<&CardMedia> for MediaRow {
+    fn from(media: &CardMedia) -> MediaRow {
+        MediaRow {
+            id: media.id as i64,
+            card_id: media.card_id as i64,
+            side: media.side as i16,
+            content_type: media.content_type.clone(),
+            byte_size: media.byte_size as i64,
+            uploaded: media.uploaded as i64,
+        }
+    }
+}
+
+impl From<MediaRow> for CardMedia {
+    fn from(row: MediaRow) -> CardMedia {
+        CardMedia {
+            id: row.id as u64,
+            card_id: row.card_id as u64,
+            side: row.side as u8,
+            content_type: row.content_type,
+            byte_size: row.byte_size as u32,
+            uploaded: row.uploaded as u32,
+        }
+    }
+}
+
+// convert a page of loaded rows into models
+pub fn into_models<R, M: From<R>>(rows: Vec<R>) -> Vec<M> {
+    rows.into_iter().map(M::from).collect()
+}
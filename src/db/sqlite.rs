@@ -0,0 +1,969 @@
+// SQLite implementation of the db traits, enabled with the 'sqlite' cargo feature.
+// SQLite can't order the rows of an UPDATE, so card positions are shifted by moving
+// the affected range through negative positions first. see 'shift_positions'
+
+use diesel::connection::SimpleConnection;
+use diesel::prelude::*;
+use diesel::r2d2 as diesel_r2d2;
+use diesel::r2d2::{ConnectionManager, CustomizeConnection};
+use diesel::sql_types::Bool;
+use diesel::sqlite::Sqlite;
+use diesel_migrations::{EmbeddedMigrations, MigrationHarness, embed_migrations};
+
+use super::signed::schema::card_answers::dsl as AnswerDSL;
+use super::signed::schema::card_distractors::dsl as DistractorDSL;
+use super::signed::schema::card_media::dsl as MediaDSL;
+use super::signed::schema::cards::dsl as CardDSL;
+use super::signed::schema::cardscores::dsl as ScoreDSL;
+use super::signed::schema::deck_members::dsl as MemberDSL;
+use super::signed::schema::decks::dsl as DeckDSL;
+use super::signed::schema::reviews::dsl as ReviewDSL;
+use super::signed::schema::study_sessions::dsl as StudyDSL;
+use super::signed::schema::{
+    card_answers, card_distractors, card_media, cards, cardscores, decks, reviews,
+};
+use super::signed::{
+    AnswerRow, CardRow, DeckRow, DistractorRow, MediaRow, MemberRow, ReviewRow, ScoreRow, StudyRow,
+    into_models,
+};
+use super::{
+    AnswerApi, CardApi, DBApiError, DeckApi, DeckRecords, DistractorApi, GameApi, INSERT_CHUNK_SIZE,
+    MediaApi, MemberApi, StudyApi, like_pattern,
+};
+use crate::card::choice::Distractor;
+use crate::card::typed::AcceptedAnswer;
+use crate::card::{Card, CardKind};
+use crate::config::PoolConfig;
+use crate::deck::{Deck, DeckMember, DeckRole, DeckVisibility};
+use crate::game::queue::expand_candidates;
+use crate::game::{CardScore, Review};
+use crate::media::CardMedia;
+use crate::study::StudySession;
+
+pub type SqliteManager = diesel_r2d2::Pool<diesel_r2d2::ConnectionManager<SqliteConnection>>;
+
+// SQLite settings are per connection, so they are applied to each one the pool opens
+#[derive(Debug)]
+struct ConnectionPragmas;
+
+impl CustomizeConnection<SqliteConnection, diesel_r2d2::Error> for ConnectionPragmas {
+    fn on_acquire(&self, conn: &mut SqliteConnection) -> Result<(), diesel_r2d2::Error> {
+        // foreign keys are off by default, and are needed for cascading deletes.
+        // wait for other connections to finish writing instead of failing immediately
+        conn.batch_execute("PRAGMA foreign_keys = ON; PRAGMA busy_timeout = 5000;")
+            .map_err(diesel_r2d2::Error::QueryError)
+    }
+}
+
+// 'connect_string' is the path to the database file, which is created if missing.
+// NOTE: ':memory:' gives each pooled connection its own empty database
+pub fn new_manager(connect_string: String) -> Result<SqliteManager, DBApiError> {
+    return new_manager_with_config(connect_string, &PoolConfig::default());
+}
+
+pub fn new_manager_with_config(
+    connect_string: String,
+    pool_config: &PoolConfig,
+) -> Result<SqliteManager, DBApiError> {
+    let connection_manager = ConnectionManager::new(connect_string.as_str());
+    // build the database connection pool
+    let pool = pool_config
+        .builder()
+        .connection_customizer(Box::new(ConnectionPragmas))
+        .build(connection_manager)?;
+    return Ok(pool);
+}
+
+// every migration in 'migrations/sqlite', compiled into the library
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations/sqlite");
+
+// create or upgrade all of the tables used by this module
+pub fn run_migrations(db_manager: &SqliteManager) -> Result<(), DBApiError> {
+    let mut conn = db_manager.get()?;
+    conn.run_pending_migrations(MIGRATIONS)
+        .map_err(|err| DBApiError::MigrationError(err))?;
+    return Ok(());
+}
+
+// add 'offset' to the position of every card in the deck between 'from' and 'to' (inclusive).
+// each position is first negated, which can't collide with the unique (deck_id, deck_pos)
+// constraint, and then flipped back once the whole range has moved
+fn shift_positions(
+    conn: &mut SqliteConnection,
+    deck_id: u64,
+    from: u16,
+    to: u16,
+    offset: i32,
+) -> Result<(), DBApiError> {
+    diesel::update(
+        CardDSL::cards.filter(
+            CardDSL::deck_id
+                .eq(deck_id as i64)
+                .and(CardDSL::deck_pos.ge(from as i32))
+                .and(CardDSL::deck_pos.le(to as i32)),
+        ),
+    )
+    .set(CardDSL::deck_pos.eq(CardDSL::deck_pos * -1 - offset))
+    .execute(conn)?;
+    diesel::update(
+        CardDSL::cards.filter(
+            CardDSL::deck_id
+                .eq(deck_id as i64)
+                .and(CardDSL::deck_pos.lt(0)),
+        ),
+    )
+    .set(CardDSL::deck_pos.eq(CardDSL::deck_pos * -1))
+    .execute(conn)?;
+    return Ok(());
+}
+
+// insert the deck and its cards, inside the caller's transaction
+fn insert_deck(conn: &mut SqliteConnection, deck: &Deck, cards: &[Card]) -> Result<(), DBApiError> {
+    let card_rows: Vec<CardRow> = cards.iter().map(CardRow::from).collect();
+    diesel::insert_into(DeckDSL::decks)
+        .values(&DeckRow::from(deck))
+        .execute(conn)?;
+    for chunk in card_rows.chunks(INSERT_CHUNK_SIZE) {
+        diesel::insert_into(CardDSL::cards)
+            .values(chunk)
+            .execute(conn)?;
+    }
+    return Ok(());
+}
+
+// insert the deck and everything that belongs to it, inside the caller's transaction
+fn insert_deck_records(
+    conn: &mut SqliteConnection,
+    records: &DeckRecords,
+) -> Result<(), DBApiError> {
+    insert_deck(conn, &records.deck, &records.cards)?;
+    let distractor_rows: Vec<DistractorRow> =
+        records.distractors.iter().map(DistractorRow::from).collect();
+    for chunk in distractor_rows.chunks(INSERT_CHUNK_SIZE) {
+        diesel::insert_into(DistractorDSL::card_distractors)
+            .values(chunk)
+            .execute(conn)?;
+    }
+    let answer_rows: Vec<AnswerRow> =
+        records.accepted_answers.iter().map(AnswerRow::from).collect();
+    for chunk in answer_rows.chunks(INSERT_CHUNK_SIZE) {
+        diesel::insert_into(AnswerDSL::card_answers)
+            .values(chunk)
+            .execute(conn)?;
+    }
+    let score_rows: Vec<ScoreRow> = records.scores.iter().map(ScoreRow::from).collect();
+    for chunk in score_rows.chunks(INSERT_CHUNK_SIZE) {
+        diesel::insert_into(ScoreDSL::cardscores)
+            .values(chunk)
+            .execute(conn)?;
+    }
+    let review_rows: Vec<ReviewRow> = records.reviews.iter().map(ReviewRow::from).collect();
+    for chunk in review_rows.chunks(INSERT_CHUNK_SIZE) {
+        diesel::insert_into(ReviewDSL::reviews)
+            .values(chunk)
+            .execute(conn)?;
+    }
+    let media_rows: Vec<MediaRow> = records.media.iter().map(MediaRow::from).collect();
+    for chunk in media_rows.chunks(INSERT_CHUNK_SIZE) {
+        diesel::insert_into(MediaDSL::card_media)
+            .values(chunk)
+            .execute(conn)?;
+    }
+    return Ok(());
+}
+
+impl DeckApi for SqliteManager {
+    fn insert(&self, deck_info: &Deck) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        diesel::insert_into(DeckDSL::decks)
+            .values(&DeckRow::from(deck_info))
+            .execute(&mut conn)?;
+        return Ok(());
+    }
+
+    fn insert_with_cards(&self, deck: &Deck, cards: &[Card]) -> Result<(), DBApiError> {
+        // DO NOT ALLOW USER TO MOVE CARD TO RESERVED POSITION 0
+        if cards.iter().any(|card| card.deck_pos == 0) {
+            return Err(DBApiError::NotAllowed);
+        }
+        let mut conn = self.get()?;
+        conn.transaction::<(), DBApiError, _>(|conn| insert_deck(conn, deck, cards))?;
+        return Ok(());
+    }
+
+    fn insert_records(&self, records: &[DeckRecords]) -> Result<(), DBApiError> {
+        // DO NOT ALLOW USER TO MOVE CARD TO RESERVED POSITION 0
+        let cards = records.iter().flat_map(|deck_records| deck_records.cards.iter());
+        if cards.clone().any(|card| card.deck_pos == 0) {
+            return Err(DBApiError::NotAllowed);
+        }
+        let mut conn = self.get()?;
+        conn.transaction::<(), DBApiError, _>(|conn| {
+            for deck_records in records {
+                insert_deck_records(conn, deck_records)?;
+            }
+            return Ok(());
+        })?;
+        return Ok(());
+    }
+
+    fn find(&self, deck_info_id: &u64) -> Result<Deck, DBApiError> {
+        let mut conn = self.get()?;
+        let row: DeckRow = DeckDSL::decks
+            .find(*deck_info_id as i64)
+            .first(&mut conn)?;
+        return Ok(Deck::from(row));
+    }
+
+    fn find_for_account(
+        &self,
+        deck_id: u64,
+        account_id: u64,
+    ) -> Result<(Deck, Option<DeckRole>), DBApiError> {
+        let mut conn = self.get()?;
+        let (row, member_role): (DeckRow, Option<i16>) = DeckDSL::decks
+            .left_join(
+                MemberDSL::deck_members.on(MemberDSL::deck_id
+                    .eq(DeckDSL::id)
+                    .and(MemberDSL::account_id.eq(account_id as i64))),
+            )
+            .filter(DeckDSL::id.eq(deck_id as i64))
+            .select((decks::all_columns, MemberDSL::role.nullable()))
+            .first(&mut conn)?;
+        let deck = Deck::from(row);
+        let role = deck.role_for(
+            account_id,
+            member_role.and_then(|role| DeckRole::from_id(role as u8)),
+        );
+        return Ok((deck, role));
+    }
+
+    fn find_decks_for_owner(&self, owner: &u64) -> Result<Vec<Deck>, DBApiError> {
+        let mut conn = self.get()?;
+        let rows: Vec<DeckRow> = DeckDSL::decks
+            .filter(DeckDSL::owner_id.eq(*owner as i64))
+            .get_results(&mut conn)?;
+        return Ok(into_models(rows));
+    }
+
+    fn find_decks_for_member(&self, account_id: &u64) -> Result<Vec<Deck>, DBApiError> {
+        let mut conn = self.get()?;
+        let rows: Vec<DeckRow> = DeckDSL::decks
+            .inner_join(MemberDSL::deck_members)
+            .filter(MemberDSL::account_id.eq(*account_id as i64))
+            .select(decks::all_columns)
+            .get_results(&mut conn)?;
+        return Ok(into_models(rows));
+    }
+
+    fn find_public_decks(
+        &self,
+        search: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<Deck>, DBApiError> {
+        let mut conn = self.get()?;
+        let mut query = DeckDSL::decks
+            .filter(DeckDSL::visibility.eq(DeckVisibility::Public.id() as i16))
+            .into_boxed();
+        if let Some(search) = search {
+            query = query.filter(DeckDSL::name.like(like_pattern(search)).escape('\\'));
+        }
+        let rows: Vec<DeckRow> = query
+            .order((DeckDSL::name.asc(), DeckDSL::id.asc()))
+            .offset(offset)
+            .limit(limit)
+            .get_results(&mut conn)?;
+        return Ok(into_models(rows));
+    }
+
+    fn update(&self, deck_info: &Deck) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        let row = DeckRow::from(deck_info);
+        diesel::update(&row).set(&row).execute(&mut conn)?;
+        return Ok(());
+    }
+
+    fn delete(&self, deck_info_id: &u64) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        let result = diesel::delete(DeckDSL::decks.filter(DeckDSL::id.eq(*deck_info_id as i64)))
+            .execute(&mut conn)?;
+        if result == 1 {
+            return Ok(());
+        } else {
+            return Err(DBApiError::NotFound);
+        }
+    }
+
+    fn delete_for_owner(&self, deck_id: u64, account_id: u64) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        let result = diesel::delete(
+            DeckDSL::decks.filter(DeckDSL::id.eq(deck_id as i64).and(owned_by(account_id))),
+        )
+        .execute(&mut conn)?;
+        if result == 1 {
+            return Ok(());
+        } else {
+            return Err(DBApiError::NotFound);
+        }
+    }
+}
+
+// decks the account created, or was shared with as an owner
+fn owned_by(account_id: u64) -> Box<dyn BoxableExpression<decks::table, Sqlite, SqlType = Bool>> {
+    let shared = MemberDSL::deck_members
+        .filter(
+            MemberDSL::account_id
+                .eq(account_id as i64)
+                .and(MemberDSL::role.ge(DeckRole::Owner.id() as i16)),
+        )
+        .select(MemberDSL::deck_id);
+    return Box::new(
+        DeckDSL::owner_id
+            .eq(account_id as i64)
+            .or(DeckDSL::id.eq_any(shared)),
+    );
+}
+
+// cards in decks the account owns, or was shared with as an editor
+fn editable_by(account_id: u64) -> Box<dyn BoxableExpression<cards::table, Sqlite, SqlType = Bool>> {
+    let owned = DeckDSL::decks
+        .filter(DeckDSL::owner_id.eq(account_id as i64))
+        .select(DeckDSL::id);
+    let shared = MemberDSL::deck_members
+        .filter(
+            MemberDSL::account_id
+                .eq(account_id as i64)
+                .and(MemberDSL::role.ge(DeckRole::Editor.id() as i16)),
+        )
+        .select(MemberDSL::deck_id);
+    return Box::new(
+        CardDSL::deck_id
+            .eq_any(owned)
+            .or(CardDSL::deck_id.eq_any(shared)),
+    );
+}
+
+// delete the card and shift all of the following cards down 1 position
+fn delete_card(conn: &mut SqliteConnection, row: &CardRow) -> Result<(), DBApiError> {
+    diesel::delete(CardDSL::cards.filter(CardDSL::id.eq(row.id))).execute(conn)?;
+    shift_positions(
+        conn,
+        row.deck_id as u64,
+        row.deck_pos as u16,
+        u16::MAX,
+        -1,
+    )?;
+    return Ok(());
+}
+
+impl CardApi for SqliteManager {
+    fn insert(&self, card: &Card) -> Result<(), DBApiError> {
+        // DO NOT ALLOW USER TO MOVE CARD TO RESERVED POSITION 0
+        if card.deck_pos == 0 {
+            return Err(DBApiError::NotAllowed);
+        }
+        let mut conn = self.get()?;
+        diesel::insert_into(CardDSL::cards)
+            .values(&CardRow::from(card))
+            .execute(&mut conn)?;
+        return Ok(());
+    }
+
+    fn find(&self, card_id: &u64) -> Result<Card, DBApiError> {
+        let mut conn = self.get()?;
+        let row: CardRow = CardDSL::cards.find(*card_id as i64).first(&mut conn)?;
+        return Ok(Card::from(row));
+    }
+
+    fn find_for_account(
+        &self,
+        card_id: u64,
+        account_id: u64,
+    ) -> Result<(Card, Deck, Option<DeckRole>), DBApiError> {
+        let mut conn = self.get()?;
+        let (card_row, deck_row, member_role): (CardRow, DeckRow, Option<i16>) = CardDSL::cards
+            .inner_join(DeckDSL::decks)
+            .left_join(
+                MemberDSL::deck_members.on(MemberDSL::deck_id
+                    .eq(CardDSL::deck_id)
+                    .and(MemberDSL::account_id.eq(account_id as i64))),
+            )
+            .filter(CardDSL::id.eq(card_id as i64))
+            .select((
+                cards::all_columns,
+                decks::all_columns,
+                MemberDSL::role.nullable(),
+            ))
+            .first(&mut conn)?;
+        let deck = Deck::from(deck_row);
+        let role = deck.role_for(
+            account_id,
+            member_role.and_then(|role| DeckRole::from_id(role as u8)),
+        );
+        return Ok((Card::from(card_row), deck, role));
+    }
+
+    fn find_cards_for_deck(&self, card_deck_id: &u64) -> Result<Vec<Card>, DBApiError> {
+        let mut conn = self.get()?;
+        let rows: Vec<CardRow> = CardDSL::cards
+            .filter(CardDSL::deck_id.eq(*card_deck_id as i64))
+            .order(CardDSL::deck_pos.asc())
+            .get_results(&mut conn)?;
+        return Ok(into_models(rows));
+    }
+
+    fn update(&self, card: &Card) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        let row = CardRow::from(card);
+        diesel::update(&row).set(&row).execute(&mut conn)?;
+        return Ok(());
+    }
+
+    fn update_position(
+        &self,
+        card_id: u64,
+        deck_id: u64,
+        orig_pos: u16,
+        new_pos: u16,
+    ) -> Result<(), DBApiError> {
+        // DO NOT ALLOW USER TO MOVE CARD TO RESERVED POSITION 0
+        if new_pos == 0 {
+            return Err(DBApiError::NotAllowed);
+        }
+        // ignore without change
+        if new_pos == orig_pos {
+            return Ok(());
+        }
+
+        let mut conn = self.get()?;
+
+        conn.transaction::<(), DBApiError, _>(|conn| {
+            // park the target card at position 0
+            // if not found, then the card doesn't exist at the original position the user expects
+            let result = diesel::update(
+                CardDSL::cards.filter(
+                    CardDSL::deck_id
+                        .eq(deck_id as i64)
+                        .and(CardDSL::id.eq(card_id as i64))
+                        .and(CardDSL::deck_pos.eq(orig_pos as i32)),
+                ),
+            )
+            .set(CardDSL::deck_pos.eq(0))
+            .execute(conn)?;
+            if result == 0 {
+                return Err(DBApiError::NotFound);
+            }
+            // shift all cards between new and orig into the gap left by the card
+            if new_pos < orig_pos {
+                shift_positions(conn, deck_id, new_pos, orig_pos, 1)?;
+            } else {
+                shift_positions(conn, deck_id, orig_pos, new_pos, -1)?;
+            }
+            // move the card into final position
+            diesel::update(CardDSL::cards.filter(CardDSL::id.eq(card_id as i64)))
+                .set(CardDSL::deck_pos.eq(new_pos as i32))
+                .execute(conn)?;
+            return Ok(());
+        })?;
+
+        return Ok(());
+    }
+
+    fn delete(&self, card_id: &u64) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        conn.transaction::<(), DBApiError, _>(|conn| {
+            // get the card if exists
+            let row: CardRow = CardDSL::cards.find(*card_id as i64).first(conn)?;
+            return delete_card(conn, &row);
+        })?;
+        return Ok(());
+    }
+
+    fn update_for_editor(
+        &self,
+        card_id: u64,
+        account_id: u64,
+        question: Option<&str>,
+        answer: Option<&str>,
+        reversible: Option<bool>,
+    ) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        let result = diesel::update(
+            CardDSL::cards.filter(CardDSL::id.eq(card_id as i64).and(editable_by(account_id))),
+        )
+        .set((
+            question.map(|question| CardDSL::question.eq(question)),
+            answer.map(|answer| CardDSL::answer.eq(answer)),
+            reversible.map(|reversible| CardDSL::reversible.eq(reversible)),
+        ))
+        .execute(&mut conn)?;
+        if result == 1 {
+            return Ok(());
+        } else {
+            return Err(DBApiError::NotFound);
+        }
+    }
+
+    fn delete_for_editor(&self, card_id: u64, account_id: u64) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        conn.transaction::<(), DBApiError, _>(|conn| {
+            // get the card if exists, and the account may edit it
+            let row: CardRow = CardDSL::cards
+                .filter(CardDSL::id.eq(card_id as i64).and(editable_by(account_id)))
+                .first(conn)?;
+            return delete_card(conn, &row);
+        })?;
+        return Ok(());
+    }
+}
+
+impl DistractorApi for SqliteManager {
+    fn find_for_card(&self, card_id: u64) -> Result<Vec<Distractor>, DBApiError> {
+        let mut conn = self.get()?;
+        let rows: Vec<DistractorRow> = DistractorDSL::card_distractors
+            .filter(DistractorDSL::card_id.eq(card_id as i64))
+            .order(DistractorDSL::position.asc())
+            .get_results(&mut conn)?;
+        return Ok(into_models(rows));
+    }
+
+    fn find_for_deck(&self, deck_id: u64) -> Result<Vec<Distractor>, DBApiError> {
+        let mut conn = self.get()?;
+        let rows: Vec<DistractorRow> = DistractorDSL::card_distractors
+            .inner_join(CardDSL::cards)
+            .filter(CardDSL::deck_id.eq(deck_id as i64))
+            .select(card_distractors::all_columns)
+            .order((DistractorDSL::card_id.asc(), DistractorDSL::position.asc()))
+            .get_results(&mut conn)?;
+        return Ok(into_models(rows));
+    }
+
+    fn insert(&self, distractors: &[Distractor]) -> Result<(), DBApiError> {
+        let rows: Vec<DistractorRow> = distractors.iter().map(DistractorRow::from).collect();
+        let mut conn = self.get()?;
+        conn.transaction::<(), DBApiError, _>(|conn| {
+            for chunk in rows.chunks(INSERT_CHUNK_SIZE) {
+                diesel::insert_into(DistractorDSL::card_distractors)
+                    .values(chunk)
+                    .execute(conn)?;
+            }
+            return Ok(());
+        })?;
+        return Ok(());
+    }
+
+    fn replace_for_card(&self, card_id: u64, distractors: &[Distractor]) -> Result<(), DBApiError> {
+        let rows: Vec<DistractorRow> = distractors.iter().map(DistractorRow::from).collect();
+        let mut conn = self.get()?;
+        conn.transaction::<(), DBApiError, _>(|conn| {
+            diesel::delete(
+                DistractorDSL::card_distractors.filter(DistractorDSL::card_id.eq(card_id as i64)),
+            )
+            .execute(conn)?;
+            if !rows.is_empty() {
+                diesel::insert_into(DistractorDSL::card_distractors)
+                    .values(&rows)
+                    .execute(conn)?;
+            }
+            return Ok(());
+        })?;
+        return Ok(());
+    }
+}
+
+impl AnswerApi for SqliteManager {
+    fn find_for_card(&self, card_id: u64) -> Result<Vec<AcceptedAnswer>, DBApiError> {
+        let mut conn = self.get()?;
+        let rows: Vec<AnswerRow> = AnswerDSL::card_answers
+            .filter(AnswerDSL::card_id.eq(card_id as i64))
+            .order(AnswerDSL::position.asc())
+            .get_results(&mut conn)?;
+        return Ok(into_models(rows));
+    }
+
+    fn find_for_deck(&self, deck_id: u64) -> Result<Vec<AcceptedAnswer>, DBApiError> {
+        let mut conn = self.get()?;
+        let rows: Vec<AnswerRow> = AnswerDSL::card_answers
+            .inner_join(CardDSL::cards)
+            .filter(CardDSL::deck_id.eq(deck_id as i64))
+            .select(card_answers::all_columns)
+            .order((AnswerDSL::card_id.asc(), AnswerDSL::position.asc()))
+            .get_results(&mut conn)?;
+        return Ok(into_models(rows));
+    }
+
+    fn insert(&self, answers: &[AcceptedAnswer]) -> Result<(), DBApiError> {
+        let rows: Vec<AnswerRow> = answers.iter().map(AnswerRow::from).collect();
+        let mut conn = self.get()?;
+        conn.transaction::<(), DBApiError, _>(|conn| {
+            for chunk in rows.chunks(INSERT_CHUNK_SIZE) {
+                diesel::insert_into(AnswerDSL::card_answers)
+                    .values(chunk)
+                    .execute(conn)?;
+            }
+            return Ok(());
+        })?;
+        return Ok(());
+    }
+
+    fn replace_for_card(&self, card_id: u64, answers: &[AcceptedAnswer]) -> Result<(), DBApiError> {
+        let rows: Vec<AnswerRow> = answers.iter().map(AnswerRow::from).collect();
+        let mut conn = self.get()?;
+        conn.transaction::<(), DBApiError, _>(|conn| {
+            diesel::delete(AnswerDSL::card_answers.filter(AnswerDSL::card_id.eq(card_id as i64)))
+                .execute(conn)?;
+            if !rows.is_empty() {
+                diesel::insert_into(AnswerDSL::card_answers)
+                    .values(&rows)
+                    .execute(conn)?;
+            }
+            return Ok(());
+        })?;
+        return Ok(());
+    }
+}
+
+impl MediaApi for SqliteManager {
+    fn insert(&self, media: &CardMedia) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        diesel::insert_into(MediaDSL::card_media)
+            .values(MediaRow::from(media))
+            .execute(&mut conn)?;
+        return Ok(());
+    }
+
+    fn insert_limited(&self, media: &CardMedia, max: usize) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        // takes the write lock up front, so other uploads wait until this one is counted
+        conn.immediate_transaction::<(), DBApiError, _>(|conn| {
+            let attached: i64 = MediaDSL::card_media
+                .filter(MediaDSL::card_id.eq(media.card_id as i64))
+                .count()
+                .get_result(conn)?;
+            if attached as usize >= max {
+                return Err(DBApiError::NotAllowed);
+            }
+            diesel::insert_into(MediaDSL::card_media)
+                .values(MediaRow::from(media))
+                .execute(conn)?;
+            return Ok(());
+        })?;
+        return Ok(());
+    }
+
+    fn find(&self, media_id: u64) -> Result<CardMedia, DBApiError> {
+        let mut conn = self.get()?;
+        let row: MediaRow = MediaDSL::card_media
+            .find(media_id as i64)
+            .first(&mut conn)?;
+        return Ok(CardMedia::from(row));
+    }
+
+    fn find_for_card(&self, card_id: u64) -> Result<Vec<CardMedia>, DBApiError> {
+        let mut conn = self.get()?;
+        let rows: Vec<MediaRow> = MediaDSL::card_media
+            .filter(MediaDSL::card_id.eq(card_id as i64))
+            .order(MediaDSL::id.asc())
+            .get_results(&mut conn)?;
+        return Ok(into_models(rows));
+    }
+
+    fn find_for_deck(&self, deck_id: u64) -> Result<Vec<CardMedia>, DBApiError> {
+        let mut conn = self.get()?;
+        let rows: Vec<MediaRow> = MediaDSL::card_media
+            .inner_join(CardDSL::cards)
+            .filter(CardDSL::deck_id.eq(deck_id as i64))
+            .select(card_media::all_columns)
+            .order((MediaDSL::card_id.asc(), MediaDSL::id.asc()))
+            .get_results(&mut conn)?;
+        return Ok(into_models(rows));
+    }
+
+    fn find_existing(&self, media_ids: &[u64]) -> Result<Vec<u64>, DBApiError> {
+        let mut conn = self.get()?;
+        let mut existing = Vec::with_capacity(media_ids.len());
+        for chunk in media_ids.chunks(INSERT_CHUNK_SIZE) {
+            let chunk: Vec<i64> = chunk.iter().map(|media_id| *media_id as i64).collect();
+            let found: Vec<i64> = MediaDSL::card_media
+                .filter(MediaDSL::id.eq_any(chunk))
+                .select(MediaDSL::id)
+                .get_results(&mut conn)?;
+            existing.extend(found.into_iter().map(|media_id| media_id as u64));
+        }
+        return Ok(existing);
+    }
+
+    fn delete(&self, media_id: u64) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        let result =
+            diesel::delete(MediaDSL::card_media.find(media_id as i64)).execute(&mut conn)?;
+        if result == 1 {
+            return Ok(());
+        } else {
+            return Err(DBApiError::NotFound);
+        }
+    }
+}
+
+// save the score and append its review, inside the caller's transaction
+fn write_score(
+    conn: &mut SqliteConnection,
+    score: &CardScore,
+    review: &Review,
+) -> Result<(), DBApiError> {
+    let score_row = ScoreRow::from(score);
+    diesel::insert_into(ScoreDSL::cardscores)
+        .values(&score_row)
+        .on_conflict((ScoreDSL::account_id, ScoreDSL::card_id, ScoreDSL::item))
+        .do_update()
+        .set(&score_row)
+        .execute(conn)?;
+    diesel::insert_into(ReviewDSL::reviews)
+        .values(&ReviewRow::from(review))
+        .execute(conn)?;
+    return Ok(());
+}
+
+impl GameApi for SqliteManager {
+    fn find_score(
+        &self,
+        account_id: u64,
+        card_id: u64,
+        item: u16,
+    ) -> Result<CardScore, DBApiError> {
+        let mut conn = self.get()?;
+        let row: ScoreRow = ScoreDSL::cardscores
+            .find((account_id as i64, card_id as i64, item as i32))
+            .first(&mut conn)?;
+        return Ok(CardScore::from(row));
+    }
+
+    fn update_score(&self, score: &CardScore, review: &Review) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        conn.transaction::<(), DBApiError, _>(|conn| write_score(conn, score, review))?;
+        return Ok(());
+    }
+
+    fn get_deck_scores(&self, deck_id: u64, account_id: u64) -> Result<Vec<CardScore>, DBApiError> {
+        let mut conn = self.get()?;
+        let rows: Vec<ScoreRow> = ScoreDSL::cardscores
+            .inner_join(CardDSL::cards)
+            .select(cardscores::all_columns)
+            .filter(
+                CardDSL::deck_id
+                    .eq(deck_id as i64)
+                    .and(ScoreDSL::account_id.eq(account_id as i64)),
+            )
+            .load(&mut conn)?;
+        return Ok(into_models(rows));
+    }
+
+    fn get_due_scores(
+        &self,
+        deck_id: u64,
+        account_id: u64,
+        now: u32,
+    ) -> Result<Vec<CardScore>, DBApiError> {
+        let mut conn = self.get()?;
+        let rows: Vec<ScoreRow> = ScoreDSL::cardscores
+            .inner_join(CardDSL::cards)
+            .select(cardscores::all_columns)
+            .filter(
+                CardDSL::deck_id
+                    .eq(deck_id as i64)
+                    .and(ScoreDSL::account_id.eq(account_id as i64))
+                    .and(ScoreDSL::due.le(now as i64)),
+            )
+            .order(ScoreDSL::due.asc())
+            .load(&mut conn)?;
+        return Ok(into_models(rows));
+    }
+
+    fn get_study_candidates(
+        &self,
+        deck_id: u64,
+        account_id: u64,
+        now: u32,
+    ) -> Result<Vec<(Card, u16, Option<CardScore>)>, DBApiError> {
+        let mut conn = self.get()?;
+        // basic cards without a reverse have a single item, so only unscored and due ones
+        // are needed. other cards need all of their scores to find their unscored items
+        let rows: Vec<(CardRow, Option<ScoreRow>)> = CardDSL::cards
+            .left_join(
+                ScoreDSL::cardscores.on(ScoreDSL::card_id
+                    .eq(CardDSL::id)
+                    .and(ScoreDSL::account_id.eq(account_id as i64))),
+            )
+            .filter(
+                CardDSL::deck_id.eq(deck_id as i64).and(
+                    ScoreDSL::card_id
+                        .nullable()
+                        .is_null()
+                        .or(ScoreDSL::due.nullable().le(now as i64))
+                        .or(CardDSL::kind.ne(CardKind::Basic.id() as i16))
+                        .or(CardDSL::reversible.eq(true)),
+                ),
+            )
+            .order(CardDSL::deck_pos.asc())
+            .select((cards::all_columns, cardscores::all_columns.nullable()))
+            .load(&mut conn)?;
+        let rows = rows
+            .into_iter()
+            .map(|(card, score)| (Card::from(card), score.map(CardScore::from)))
+            .collect();
+        return Ok(expand_candidates(rows, now));
+    }
+
+    fn get_deck_reviews(&self, deck_id: u64, account_id: u64) -> Result<Vec<Review>, DBApiError> {
+        let mut conn = self.get()?;
+        let rows: Vec<ReviewRow> = ReviewDSL::reviews
+            .inner_join(CardDSL::cards)
+            .select(reviews::all_columns)
+            .filter(
+                CardDSL::deck_id
+                    .eq(deck_id as i64)
+                    .and(ReviewDSL::account_id.eq(account_id as i64)),
+            )
+            .order((ReviewDSL::answered_at.asc(), ReviewDSL::id.asc()))
+            .load(&mut conn)?;
+        return Ok(into_models(rows));
+    }
+
+    fn insert_history(&self, scores: &[CardScore], reviews: &[Review]) -> Result<(), DBApiError> {
+        let score_rows: Vec<ScoreRow> = scores.iter().map(ScoreRow::from).collect();
+        let review_rows: Vec<ReviewRow> = reviews.iter().map(ReviewRow::from).collect();
+        let mut conn = self.get()?;
+        conn.transaction::<(), DBApiError, _>(|conn| {
+            for chunk in score_rows.chunks(INSERT_CHUNK_SIZE) {
+                diesel::insert_into(ScoreDSL::cardscores)
+                    .values(chunk)
+                    .execute(conn)?;
+            }
+            for chunk in review_rows.chunks(INSERT_CHUNK_SIZE) {
+                diesel::insert_into(ReviewDSL::reviews)
+                    .values(chunk)
+                    .execute(conn)?;
+            }
+            return Ok(());
+        })?;
+        return Ok(());
+    }
+}
+
+impl StudyApi for SqliteManager {
+    fn insert(&self, study_session: &StudySession) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        diesel::insert_into(StudyDSL::study_sessions)
+            .values(&StudyRow::from(study_session))
+            .execute(&mut conn)?;
+        return Ok(());
+    }
+
+    fn find(&self, study_id: &u64) -> Result<StudySession, DBApiError> {
+        let mut conn = self.get()?;
+        let row: StudyRow = StudyDSL::study_sessions
+            .find(*study_id as i64)
+            .first(&mut conn)?;
+        return Ok(StudySession::from(row));
+    }
+
+    fn find_open(&self, account_id: u64, deck_id: u64) -> Result<StudySession, DBApiError> {
+        let mut conn = self.get()?;
+        let row: StudyRow = StudyDSL::study_sessions
+            .filter(
+                StudyDSL::account_id
+                    .eq(account_id as i64)
+                    .and(StudyDSL::deck_id.eq(deck_id as i64))
+                    .and(StudyDSL::ended.is_null()),
+            )
+            .order(StudyDSL::started.desc())
+            .first(&mut conn)?;
+        return Ok(StudySession::from(row));
+    }
+
+    fn record_answer(
+        &self,
+        study_id: u64,
+        correct: bool,
+        score: &CardScore,
+        review: &Review,
+    ) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        conn.transaction::<(), DBApiError, _>(|conn| {
+            let result = diesel::update(
+                StudyDSL::study_sessions.filter(
+                    StudyDSL::id
+                        .eq(study_id as i64)
+                        .and(StudyDSL::ended.is_null()),
+                ),
+            )
+            .set((
+                StudyDSL::cards_seen.eq(StudyDSL::cards_seen + 1),
+                StudyDSL::correct_count.eq(StudyDSL::correct_count + correct as i32),
+            ))
+            .execute(conn)?;
+            if result != 1 {
+                return Err(DBApiError::NotFound);
+            }
+            return write_score(conn, score, review);
+        })?;
+        return Ok(());
+    }
+
+    fn finish(&self, study_id: u64, ended: u32) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        diesel::update(
+            StudyDSL::study_sessions.filter(
+                StudyDSL::id
+                    .eq(study_id as i64)
+                    .and(StudyDSL::ended.is_null()),
+            ),
+        )
+        .set(StudyDSL::ended.eq(ended as i64))
+        .execute(&mut conn)?;
+        return Ok(());
+    }
+}
+
+impl MemberApi for SqliteManager {
+    fn upsert(&self, member: &DeckMember) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        let row = MemberRow::from(member);
+        diesel::insert_into(MemberDSL::deck_members)
+            .values(&row)
+            .on_conflict((MemberDSL::deck_id, MemberDSL::account_id))
+            .do_update()
+            .set(MemberDSL::role.eq(row.role))
+            .execute(&mut conn)?;
+        return Ok(());
+    }
+
+    fn find(&self, deck_id: u64, account_id: u64) -> Result<DeckMember, DBApiError> {
+        let mut conn = self.get()?;
+        let row: MemberRow = MemberDSL::deck_members
+            .find((deck_id as i64, account_id as i64))
+            .first(&mut conn)?;
+        return Ok(DeckMember::from(row));
+    }
+
+    fn find_members_for_deck(&self, deck_id: u64) -> Result<Vec<DeckMember>, DBApiError> {
+        let mut conn = self.get()?;
+        let rows: Vec<MemberRow> = MemberDSL::deck_members
+            .filter(MemberDSL::deck_id.eq(deck_id as i64))
+            .get_results(&mut conn)?;
+        return Ok(into_models(rows));
+    }
+
+    fn delete(&self, deck_id: u64, account_id: u64) -> Result<(), DBApiError> {
+        let mut conn = self.get()?;
+        let result =
+            diesel::delete(MemberDSL::deck_members.find((deck_id as i64, account_id as i64)))
+                .execute(&mut conn)?;
+        if result == 1 {
+            return Ok(());
+        } else {
+            return Err(DBApiError::NotFound);
+        }
+    }
+}
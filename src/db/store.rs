@@ -0,0 +1,311 @@
+// FlashStore bundles the db traits into the one bound FlashManager needs from its storage.
+// It is implemented for every type implementing all of the db traits, so a wrapper that
+// adds caching or metrics around another store only has to implement those.
+// Boxed stores forward to their contents, which allows FlashManager<Box<dyn FlashStore>>.
+
+use super::{
+    AnswerApi, CardApi, DBApiError, DeckApi, DeckRecords, DistractorApi, GameApi, MediaApi,
+    MemberApi, StudyApi,
+};
+use crate::card::Card;
+use crate::card::choice::Distractor;
+use crate::card::typed::AcceptedAnswer;
+use crate::deck::{Deck, DeckMember, DeckRole};
+use crate::game::{CardScore, Review};
+use crate::media::CardMedia;
+use crate::study::StudySession;
+
+pub trait FlashStore:
+    DeckApi
+    + CardApi
+    + DistractorApi
+    + AnswerApi
+    + MediaApi
+    + GameApi
+    + StudyApi
+    + MemberApi
+    + Send
+    + Sync
+{
+}
+
+impl<T> FlashStore for T where
+    T: DeckApi
+        + CardApi
+        + DistractorApi
+        + AnswerApi
+        + MediaApi
+        + GameApi
+        + StudyApi
+        + MemberApi
+        + Send
+        + Sync
+{
+}
+
+impl<S: DeckApi + ?Sized> DeckApi for Box<S> {
+    fn insert(&self, deck: &Deck) -> Result<(), DBApiError> {
+        DeckApi::insert(&**self, deck)
+    }
+
+    fn insert_with_cards(&self, deck: &Deck, cards: &[Card]) -> Result<(), DBApiError> {
+        DeckApi::insert_with_cards(&**self, deck, cards)
+    }
+
+    fn insert_records(&self, records: &[DeckRecords]) -> Result<(), DBApiError> {
+        DeckApi::insert_records(&**self, records)
+    }
+
+    fn find(&self, id: &u64) -> Result<Deck, DBApiError> {
+        DeckApi::find(&**self, id)
+    }
+
+    fn find_for_account(
+        &self,
+        deck_id: u64,
+        account_id: u64,
+    ) -> Result<(Deck, Option<DeckRole>), DBApiError> {
+        DeckApi::find_for_account(&**self, deck_id, account_id)
+    }
+
+    fn find_decks_for_owner(&self, owner_id: &u64) -> Result<Vec<Deck>, DBApiError> {
+        DeckApi::find_decks_for_owner(&**self, owner_id)
+    }
+
+    fn find_decks_for_member(&self, account_id: &u64) -> Result<Vec<Deck>, DBApiError> {
+        DeckApi::find_decks_for_member(&**self, account_id)
+    }
+
+    fn find_public_decks(
+        &self,
+        search: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<Deck>, DBApiError> {
+        DeckApi::find_public_decks(&**self, search, offset, limit)
+    }
+
+    fn update(&self, deck: &Deck) -> Result<(), DBApiError> {
+        DeckApi::update(&**self, deck)
+    }
+
+    fn delete(&self, id: &u64) -> Result<(), DBApiError> {
+        DeckApi::delete(&**self, id)
+    }
+
+    fn delete_for_owner(&self, deck_id: u64, account_id: u64) -> Result<(), DBApiError> {
+        DeckApi::delete_for_owner(&**self, deck_id, account_id)
+    }
+}
+
+impl<S: CardApi + ?Sized> CardApi for Box<S> {
+    fn insert(&self, card: &Card) -> Result<(), DBApiError> {
+        CardApi::insert(&**self, card)
+    }
+
+    fn find(&self, card_id: &u64) -> Result<Card, DBApiError> {
+        CardApi::find(&**self, card_id)
+    }
+
+    fn find_for_account(
+        &self,
+        card_id: u64,
+        account_id: u64,
+    ) -> Result<(Card, Deck, Option<DeckRole>), DBApiError> {
+        CardApi::find_for_account(&**self, card_id, account_id)
+    }
+
+    fn find_cards_for_deck(&self, deck_id: &u64) -> Result<Vec<Card>, DBApiError> {
+        CardApi::find_cards_for_deck(&**self, deck_id)
+    }
+
+    fn update(&self, card: &Card) -> Result<(), DBApiError> {
+        CardApi::update(&**self, card)
+    }
+
+    fn update_position(
+        &self,
+        card_id: u64,
+        deck_id: u64,
+        orig_pos: u16,
+        new_pos: u16,
+    ) -> Result<(), DBApiError> {
+        CardApi::update_position(&**self, card_id, deck_id, orig_pos, new_pos)
+    }
+
+    fn delete(&self, card_id: &u64) -> Result<(), DBApiError> {
+        CardApi::delete(&**self, card_id)
+    }
+
+    fn update_for_editor(
+        &self,
+        card_id: u64,
+        account_id: u64,
+        question: Option<&str>,
+        answer: Option<&str>,
+        reversible: Option<bool>,
+    ) -> Result<(), DBApiError> {
+        CardApi::update_for_editor(&**self, card_id, account_id, question, answer, reversible)
+    }
+
+    fn delete_for_editor(&self, card_id: u64, account_id: u64) -> Result<(), DBApiError> {
+        CardApi::delete_for_editor(&**self, card_id, account_id)
+    }
+}
+
+impl<S: DistractorApi + ?Sized> DistractorApi for Box<S> {
+    fn find_for_card(&self, card_id: u64) -> Result<Vec<Distractor>, DBApiError> {
+        DistractorApi::find_for_card(&**self, card_id)
+    }
+
+    fn find_for_deck(&self, deck_id: u64) -> Result<Vec<Distractor>, DBApiError> {
+        DistractorApi::find_for_deck(&**self, deck_id)
+    }
+
+    fn insert(&self, distractors: &[Distractor]) -> Result<(), DBApiError> {
+        DistractorApi::insert(&**self, distractors)
+    }
+
+    fn replace_for_card(&self, card_id: u64, distractors: &[Distractor]) -> Result<(), DBApiError> {
+        DistractorApi::replace_for_card(&**self, card_id, distractors)
+    }
+}
+
+impl<S: AnswerApi + ?Sized> AnswerApi for Box<S> {
+    fn find_for_card(&self, card_id: u64) -> Result<Vec<AcceptedAnswer>, DBApiError> {
+        AnswerApi::find_for_card(&**self, card_id)
+    }
+
+    fn find_for_deck(&self, deck_id: u64) -> Result<Vec<AcceptedAnswer>, DBApiError> {
+        AnswerApi::find_for_deck(&**self, deck_id)
+    }
+
+    fn insert(&self, answers: &[AcceptedAnswer]) -> Result<(), DBApiError> {
+        AnswerApi::insert(&**self, answers)
+    }
+
+    fn replace_for_card(&self, card_id: u64, answers: &[AcceptedAnswer]) -> Result<(), DBApiError> {
+        AnswerApi::replace_for_card(&**self, card_id, answers)
+    }
+}
+
+impl<S: MediaApi + ?Sized> MediaApi for Box<S> {
+    fn insert(&self, media: &CardMedia) -> Result<(), DBApiError> {
+        MediaApi::insert(&**self, media)
+    }
+
+    fn insert_limited(&self, media: &CardMedia, max: usize) -> Result<(), DBApiError> {
+        MediaApi::insert_limited(&**self, media, max)
+    }
+
+    fn find(&self, media_id: u64) -> Result<CardMedia, DBApiError> {
+        MediaApi::find(&**self, media_id)
+    }
+
+    fn find_for_card(&self, card_id: u64) -> Result<Vec<CardMedia>, DBApiError> {
+        MediaApi::find_for_card(&**self, card_id)
+    }
+
+    fn find_for_deck(&self, deck_id: u64) -> Result<Vec<CardMedia>, DBApiError> {
+        MediaApi::find_for_deck(&**self, deck_id)
+    }
+
+    fn find_existing(&self, media_ids: &[u64]) -> Result<Vec<u64>, DBApiError> {
+        MediaApi::find_existing(&**self, media_ids)
+    }
+
+    fn delete(&self, media_id: u64) -> Result<(), DBApiError> {
+        MediaApi::delete(&**self, media_id)
+    }
+}
+
+impl<S: GameApi + ?Sized> GameApi for Box<S> {
+    fn find_score(
+        &self,
+        account_id: u64,
+        card_id: u64,
+        item: u16,
+    ) -> Result<CardScore, DBApiError> {
+        GameApi::find_score(&**self, account_id, card_id, item)
+    }
+
+    fn update_score(&self, score: &CardScore, review: &Review) -> Result<(), DBApiError> {
+        GameApi::update_score(&**self, score, review)
+    }
+
+    fn get_deck_scores(&self, deck_id: u64, account_id: u64) -> Result<Vec<CardScore>, DBApiError> {
+        GameApi::get_deck_scores(&**self, deck_id, account_id)
+    }
+
+    fn get_due_scores(
+        &self,
+        deck_id: u64,
+        account_id: u64,
+        now: u32,
+    ) -> Result<Vec<CardScore>, DBApiError> {
+        GameApi::get_due_scores(&**self, deck_id, account_id, now)
+    }
+
+    fn get_study_candidates(
+        &self,
+        deck_id: u64,
+        account_id: u64,
+        now: u32,
+    ) -> Result<Vec<(Card, u16, Option<CardScore>)>, DBApiError> {
+        GameApi::get_study_candidates(&**self, deck_id, account_id, now)
+    }
+
+    fn get_deck_reviews(&self, deck_id: u64, account_id: u64) -> Result<Vec<Review>, DBApiError> {
+        GameApi::get_deck_reviews(&**self, deck_id, account_id)
+    }
+
+    fn insert_history(&self, scores: &[CardScore], reviews: &[Review]) -> Result<(), DBApiError> {
+        GameApi::insert_history(&**self, scores, reviews)
+    }
+}
+
+impl<S: StudyApi + ?Sized> StudyApi for Box<S> {
+    fn insert(&self, study_session: &StudySession) -> Result<(), DBApiError> {
+        StudyApi::insert(&**self, study_session)
+    }
+
+    fn find(&self, study_id: &u64) -> Result<StudySession, DBApiError> {
+        StudyApi::find(&**self, study_id)
+    }
+
+    fn find_open(&self, account_id: u64, deck_id: u64) -> Result<StudySession, DBApiError> {
+        StudyApi::find_open(&**self, account_id, deck_id)
+    }
+
+    fn record_answer(
+        &self,
+        study_id: u64,
+        correct: bool,
+        score: &CardScore,
+        review: &Review,
+    ) -> Result<(), DBApiError> {
+        StudyApi::record_answer(&**self, study_id, correct, score, review)
+    }
+
+    fn finish(&self, study_id: u64, ended: u32) -> Result<(), DBApiError> {
+        StudyApi::finish(&**self, study_id, ended)
+    }
+}
+
+impl<S: MemberApi + ?Sized> MemberApi for Box<S> {
+    fn upsert(&self, member: &DeckMember) -> Result<(), DBApiError> {
+        MemberApi::upsert(&**self, member)
+    }
+
+    fn find(&self, deck_id: u64, account_id: u64) -> Result<DeckMember, DBApiError> {
+        MemberApi::find(&**self, deck_id, account_id)
+    }
+
+    fn find_members_for_deck(&self, deck_id: u64) -> Result<Vec<DeckMember>, DBApiError> {
+        MemberApi::find_members_for_deck(&**self, deck_id)
+    }
+
+    fn delete(&self, deck_id: u64, account_id: u64) -> Result<(), DBApiError> {
+        MemberApi::delete(&**self, deck_id, account_id)
+    }
+}
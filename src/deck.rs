@@ -1,26 +1,37 @@
 use crate::card::Card;
-use crate::schema::decks;
+use crate::card::choice::Distractor;
+use crate::card::typed::AcceptedAnswer;
+use crate::game::SchedulerKind;
+use crate::media::CardMedia;
+use crate::schema::{deck_members, decks};
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize, Serializer};
 
 use std::time::{SystemTime, SystemTimeError, UNIX_EPOCH};
 
 // used as the database record since we can't exclude fields from derives
 // see https://github.com/diesel-rs/diesel/issues/860
-#[derive(Serialize, AsChangeset, Identifiable, Insertable, Queryable, Debug)]
+#[derive(Serialize, AsChangeset, Identifiable, Insertable, Queryable, Clone, Debug)]
 #[table_name = "decks"]
 pub struct Deck {
   #[serde(serialize_with = "webe_auth::utility::serialize_as_string")]
   pub id: u64,
-  name: String,
+  pub(crate) name: String,
   pub owner_id: u64,
-  last_updated: u32,
+  pub(crate) last_updated: u32,
+  #[serde(serialize_with = "crate::game::scheduler::serialize_scheduler_id")]
+  pub(crate) scheduler: u8,
+  #[serde(serialize_with = "serialize_visibility_id")]
+  pub(crate) visibility: u8,
 }
 
 #[derive(Serialize)]
 pub struct DeckDetails {
   pub info: Deck,
   pub cards: Vec<Card>,
+  pub distractors: Vec<Distractor>, // for the multiple choice cards, ordered by card
+  pub accepted_answers: Vec<AcceptedAnswer>, // for typed answers, ordered by card
+  pub media: Vec<CardMedia>, // attachments of the cards, ordered by card
 }
 
 impl Deck {
@@ -34,10 +45,154 @@ impl Deck {
       name: name,
       owner_id: owner_id,
       last_updated: now,
+      scheduler: SchedulerKind::Sm2.id(),
+      visibility: DeckVisibility::Private.id(),
     });
   }
 
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
   pub fn rename(&mut self, name: &str) {
     self.name = name.to_owned();
   }
+
+  // decks with an unrecognized scheduler id fall back to SM-2
+  pub fn scheduler(&self) -> SchedulerKind {
+    SchedulerKind::from_id(self.scheduler).unwrap_or(SchedulerKind::Sm2)
+  }
+
+  pub fn set_scheduler(&mut self, kind: SchedulerKind) {
+    self.scheduler = kind.id();
+  }
+
+  // decks with an unrecognized visibility id are treated as private
+  pub fn visibility(&self) -> DeckVisibility {
+    DeckVisibility::from_id(self.visibility).unwrap_or(DeckVisibility::Private)
+  }
+
+  pub fn set_visibility(&mut self, visibility: DeckVisibility) {
+    self.visibility = visibility.id();
+  }
+
+  // the account's role on this deck, given the role the deck was shared with it, if any.
+  // unlisted and public decks can be viewed by anyone
+  pub fn role_for(&self, account_id: u64, member_role: Option<DeckRole>) -> Option<DeckRole> {
+    if self.owner_id == account_id {
+      return Some(DeckRole::Owner);
+    }
+    if member_role.is_none() && self.visibility() != DeckVisibility::Private {
+      return Some(DeckRole::Viewer);
+    }
+    return member_role;
+  }
+}
+
+// Who can find and read a deck without being a member
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum DeckVisibility {
+  Private = 0,  // owner and members only
+  Unlisted = 1, // anyone with the deck id can view it
+  Public = 2,   // also listed in the public catalog
+}
+
+impl DeckVisibility {
+  pub fn from_id(id: u8) -> Option<DeckVisibility> {
+    match id {
+      0 => Some(DeckVisibility::Private),
+      1 => Some(DeckVisibility::Unlisted),
+      2 => Some(DeckVisibility::Public),
+      _ => None,
+    }
+  }
+
+  pub fn id(&self) -> u8 {
+    *self as u8
+  }
+}
+
+fn serialize_visibility_id<S>(id: &u8, serializer: S) -> Result<S::Ok, S::Error>
+where
+  S: Serializer,
+{
+  match DeckVisibility::from_id(*id) {
+    Some(visibility) => visibility.serialize(serializer),
+    None => serializer.serialize_u8(*id),
+  }
+}
+
+pub const DEFAULT_DECKS_PER_PAGE: u16 = 20;
+pub const MAX_DECKS_PER_PAGE: u16 = 100;
+
+// One page of the public deck catalog
+#[derive(Serialize)]
+pub struct DeckPage {
+  pub decks: Vec<Deck>,
+  pub page: u32,
+  pub has_more: bool,
+}
+
+// What an account may do with a deck. Each role includes the ones below it.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum DeckRole {
+  Viewer = 1, // study and read cards
+  Editor = 2, // also create, edit, move and delete cards
+  Owner = 3,  // also rename, delete and share the deck
+}
+
+impl DeckRole {
+  pub fn from_id(id: u8) -> Option<DeckRole> {
+    match id {
+      1 => Some(DeckRole::Viewer),
+      2 => Some(DeckRole::Editor),
+      3 => Some(DeckRole::Owner),
+      _ => None,
+    }
+  }
+
+  pub fn id(&self) -> u8 {
+    *self as u8
+  }
+}
+
+// An account the deck has been shared with.
+// The deck's creator (Deck.owner_id) is always an owner and never has a member record.
+#[derive(Serialize, AsChangeset, Identifiable, Insertable, Queryable, Clone, Debug)]
+#[table_name = "deck_members"]
+#[primary_key(deck_id, account_id)]
+pub struct DeckMember {
+  #[serde(serialize_with = "webe_auth::utility::serialize_as_string")]
+  pub deck_id: u64,
+  #[serde(serialize_with = "webe_auth::utility::serialize_as_string")]
+  pub account_id: u64,
+  #[serde(serialize_with = "serialize_role_id")]
+  pub(crate) role: u8,
+}
+
+impl DeckMember {
+  pub fn new(deck_id: u64, account_id: u64, role: DeckRole) -> DeckMember {
+    DeckMember {
+      deck_id: deck_id,
+      account_id: account_id,
+      role: role.id(),
+    }
+  }
+
+  // None if the stored role isn't recognized
+  pub fn role(&self) -> Option<DeckRole> {
+    DeckRole::from_id(self.role)
+  }
+}
+
+fn serialize_role_id<S>(id: &u8, serializer: S) -> Result<S::Ok, S::Error>
+where
+  S: Serializer,
+{
+  match DeckRole::from_id(*id) {
+    Some(role) => role.serialize(serializer),
+    None => serializer.serialize_u8(*id),
+  }
 }
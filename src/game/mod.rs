@@ -0,0 +1,112 @@
+use crate::card::BASIC_ITEM;
+use crate::schema::cardscores;
+
+use serde::Serialize;
+
+pub mod queue;
+pub mod review;
+pub mod scheduler;
+
+pub use queue::{StudyItem, StudyItemKind};
+pub use review::Review;
+pub use scheduler::{ReviewState, Scheduler, SchedulerKind};
+
+// Flashcard game based on Brainscape, scheduled by the deck's Scheduler
+// One score per reviewable item of a card, see Card::items
+#[derive(Serialize, AsChangeset, Identifiable, Insertable, Queryable, Clone, Debug)]
+#[table_name = "cardscores"]
+#[primary_key(account_id, card_id, item)]
+pub struct CardScore {
+  #[serde(serialize_with = "webe_auth::utility::serialize_as_string")]
+  pub(crate) account_id: u64,
+  #[serde(serialize_with = "webe_auth::utility::serialize_as_string")]
+  pub card_id: u64,
+  pub item: u16, // 0 for basic cards, the deletion number for cloze cards
+  pub score: u8, // most recent grade, 0-5. full history is kept in Review
+  pub ease_factor: f32,
+  pub interval: u32,
+  pub repetitions: u16,
+  pub due: u32,
+  pub last_reviewed: u32,
+  pub stability: f32,
+  pub difficulty: f32,
+}
+
+impl CardScore {
+  // a basic card that has never been reviewed. due immediately.
+  pub fn new(account_id: u64, card_id: u64) -> CardScore {
+    CardScore::for_item(account_id, card_id, BASIC_ITEM)
+  }
+
+  // an item of a card that has never been reviewed. due immediately.
+  pub fn for_item(account_id: u64, card_id: u64, item: u16) -> CardScore {
+    let mut card_score = CardScore {
+      account_id: account_id,
+      card_id: card_id,
+      item: item,
+      score: 0,
+      ease_factor: 0.0,
+      interval: 0,
+      repetitions: 0,
+      due: 0,
+      last_reviewed: 0,
+      stability: 0.0,
+      difficulty: 0.0,
+    };
+    card_score.set_state(&ReviewState::new());
+    return card_score;
+  }
+
+  // restore a score saved elsewhere, e.g. from a deck bundle
+  pub fn from_state(
+    account_id: u64,
+    card_id: u64,
+    item: u16,
+    score: u8,
+    state: &ReviewState,
+  ) -> CardScore {
+    let mut card_score = CardScore::for_item(account_id, card_id, item);
+    card_score.set_state(state);
+    card_score.score = score.min(scheduler::MAX_GRADE);
+    return card_score;
+  }
+
+  pub fn state(&self) -> ReviewState {
+    ReviewState {
+      ease_factor: self.ease_factor,
+      interval: self.interval,
+      repetitions: self.repetitions,
+      stability: self.stability,
+      difficulty: self.difficulty,
+      due: self.due,
+      last_reviewed: self.last_reviewed,
+    }
+  }
+
+  fn set_state(&mut self, state: &ReviewState) {
+    self.ease_factor = state.ease_factor;
+    self.interval = state.interval;
+    self.repetitions = state.repetitions;
+    self.stability = state.stability;
+    self.difficulty = state.difficulty;
+    self.due = state.due;
+    self.last_reviewed = state.last_reviewed;
+  }
+
+  // apply a review graded 0-5 at time 'now' (unix seconds)
+  pub fn review(&mut self, scheduler: &dyn Scheduler, grade: u8, now: u32) {
+    let next = scheduler.schedule(&self.state(), grade, now);
+    self.set_state(&next);
+    self.score = grade.min(scheduler::MAX_GRADE);
+  }
+
+  pub fn is_due(&self, now: u32) -> bool {
+    self.due <= now
+  }
+
+  // the most recent grade was a failure, whatever the scheduler did with it.
+  // e.g. Leitner moves a failed card back to box 1 rather than 0 repetitions
+  pub fn is_lapsed(&self) -> bool {
+    self.score < scheduler::PASSING_GRADE
+  }
+}
@@ -0,0 +1,156 @@
+// Builds the list of cards an account should study next.
+// Each entry is one reviewable item of a card, so a cloze card can appear once per deletion.
+use crate::card::choice::{self, Distractor};
+use crate::card::{BASIC_ITEM, Card, CardKind};
+use crate::game::CardScore;
+
+use serde::Serialize;
+
+pub const DEFAULT_QUEUE_LIMIT: u16 = 20;
+pub const DEFAULT_NEW_CARDS_PER_QUEUE: u16 = 5;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum StudyItemKind {
+  New,      // never reviewed by this account
+  Learning, // last review was a failure, needs to be relearned
+  Review,   // previously recalled and now due again
+}
+
+#[derive(Serialize, Debug)]
+pub struct StudyItem {
+  pub kind: StudyItemKind,
+  pub card: Card,
+  pub item: u16,        // which of the card's items to review, see Card::items
+  pub question: String, // what to show for this item, e.g. a cloze with its deletion hidden
+  // left out of multiple choice items, so the options don't give it away
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub answer: Option<String>,
+  pub score: Option<CardScore>,
+  // the shuffled options of a multiple choice card, see FlashManager::answer_choice
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub options: Option<Vec<String>>,
+}
+
+impl StudyItem {
+  pub fn new(card: Card, item: u16, score: Option<CardScore>) -> StudyItem {
+    let kind = match &score {
+      None => StudyItemKind::New,
+      Some(card_score) if card_score.is_lapsed() => StudyItemKind::Learning,
+      Some(_) => StudyItemKind::Review,
+    };
+    let (question, answer) = match card.item_text(item) {
+      Some(text) => text,
+      None => (card.question().to_owned(), card.answer().to_owned()),
+    };
+    StudyItem {
+      kind: kind,
+      card: card,
+      item: item,
+      question: question,
+      answer: Some(answer),
+      score: score,
+      options: None,
+    }
+  }
+}
+
+// Expand (card, score) rows into one candidate per reviewable item that the account
+// has never scored or that is due at 'now'. 'rows' are in deck order, with every row
+// for the same card next to each other, and a row without a score for cards never scored.
+// Scores for items the card no longer has, e.g. a removed cloze deletion, are ignored.
+pub fn expand_candidates(
+  rows: Vec<(Card, Option<CardScore>)>,
+  now: u32,
+) -> Vec<(Card, u16, Option<CardScore>)> {
+  let mut candidates = Vec::new();
+  let mut rows = rows.into_iter().peekable();
+  while let Some((card, score)) = rows.next() {
+    let mut scores: Vec<CardScore> = score.into_iter().collect();
+    while let Some((_same_card, score)) = rows.next_if(|(next, _score)| next.id == card.id) {
+      scores.extend(score);
+    }
+    for item in card.items() {
+      match scores.iter().position(|score| score.item == item) {
+        Some(index) => {
+          let score = scores.swap_remove(index);
+          if score.is_due(now) {
+            candidates.push((card.clone(), item, Some(score)));
+          }
+        }
+        None => candidates.push((card.clone(), item, None)),
+      }
+    }
+  }
+  return candidates;
+}
+
+// Mix due cards and new cards into a single queue of at most 'limit' items.
+// 'candidates' are card items that are either unscored or due at 'now', in deck order.
+// Learning cards come first, then the most overdue reviews, with up to
+// 'new_limit' new cards spread evenly between them.
+pub fn build_study_queue(
+  candidates: Vec<(Card, u16, Option<CardScore>)>,
+  limit: u16,
+  new_limit: u16,
+) -> Vec<StudyItem> {
+  let limit = limit as usize;
+  let mut learning = Vec::new();
+  let mut reviews = Vec::new();
+  let mut new_cards = Vec::new();
+  for (card, item, score) in candidates {
+    let item = StudyItem::new(card, item, score);
+    match item.kind {
+      StudyItemKind::New => new_cards.push(item),
+      StudyItemKind::Learning => learning.push(item),
+      StudyItemKind::Review => reviews.push(item),
+    }
+  }
+  reviews.sort_by_key(|item| item.score.as_ref().map(|s| s.due).unwrap_or(0));
+
+  // reserve room for new cards, then fill the rest with due cards
+  let new_count = new_cards.len().min(new_limit as usize).min(limit);
+  let mut due: Vec<StudyItem> = learning.into_iter().chain(reviews).collect();
+  due.truncate(limit - new_count);
+  new_cards.truncate(new_count);
+
+  if new_cards.is_empty() {
+    return due;
+  }
+  let spacing = due.len() / new_cards.len() + 1;
+  let mut queue = Vec::with_capacity(due.len() + new_cards.len());
+  let mut new_iter = new_cards.into_iter();
+  for (index, item) in due.into_iter().enumerate() {
+    if index % spacing == 0 {
+      if let Some(new_item) = new_iter.next() {
+        queue.push(new_item);
+      }
+    }
+    queue.push(item);
+  }
+  queue.extend(new_iter);
+  return queue;
+}
+
+// Give the multiple choice items in the queue their options, shuffled by 'seed'.
+// Those items, and their card, no longer show which option is the answer.
+// 'distractors' are those of the queue's deck, see card::choice
+pub fn add_choice_options(queue: &mut [StudyItem], distractors: &[Distractor], seed: u64) {
+  for study_item in queue.iter_mut() {
+    if study_item.card.kind() != CardKind::Basic || study_item.item != BASIC_ITEM {
+      continue;
+    }
+    let card_id = study_item.card.id;
+    let mut card_distractors = distractors
+      .iter()
+      .filter(|distractor| distractor.card_id == card_id)
+      .peekable();
+    if card_distractors.peek().is_some() {
+      // a different order for every card
+      let options = choice::options(study_item.card.answer(), card_distractors, seed ^ card_id);
+      study_item.options = Some(options);
+      study_item.answer = None;
+      study_item.card.update_answer(String::new());
+    }
+  }
+}
@@ -0,0 +1,71 @@
+use crate::game::{CardScore, ReviewState, SchedulerKind};
+use crate::schema::reviews;
+
+use serde::{Serialize, Serializer};
+
+// Append-only log of every answer, one record per update_score.
+// Scheduler states are stored as JSON so schedules can be recomputed
+// when a deck changes algorithms.
+#[derive(Serialize, Identifiable, Insertable, Queryable, Clone, Debug)]
+#[table_name = "reviews"]
+pub struct Review {
+  #[serde(serialize_with = "webe_auth::utility::serialize_as_string")]
+  pub id: u64,
+  #[serde(serialize_with = "webe_auth::utility::serialize_as_string")]
+  pub account_id: u64,
+  #[serde(serialize_with = "webe_auth::utility::serialize_as_string")]
+  pub card_id: u64,
+  pub item: u16, // see CardScore.item
+  pub grade: u8,
+  pub answered_at: u32,         // unix timestamp (seconds)
+  pub response_ms: Option<u32>, // time taken to answer, if the client measured it
+  #[serde(serialize_with = "crate::game::scheduler::serialize_scheduler_id")]
+  pub scheduler: u8,
+  #[serde(serialize_with = "serialize_json_text")]
+  pub(crate) state_before: String,
+  #[serde(serialize_with = "serialize_json_text")]
+  pub(crate) state_after: String,
+}
+
+impl Review {
+  // record the review that just produced 'card_score' from 'state_before'
+  pub fn new(
+    id: u64,
+    card_score: &CardScore,
+    scheduler: SchedulerKind,
+    state_before: &ReviewState,
+    response_ms: Option<u32>,
+  ) -> Result<Review, serde_json::Error> {
+    return Ok(Review {
+      id: id,
+      account_id: card_score.account_id,
+      card_id: card_score.card_id,
+      item: card_score.item,
+      grade: card_score.score,
+      answered_at: card_score.last_reviewed,
+      response_ms: response_ms,
+      scheduler: scheduler.id(),
+      state_before: serde_json::to_string(state_before)?,
+      state_after: serde_json::to_string(&card_score.state())?,
+    });
+  }
+
+  pub fn state_before(&self) -> Result<ReviewState, serde_json::Error> {
+    serde_json::from_str(&self.state_before)
+  }
+
+  pub fn state_after(&self) -> Result<ReviewState, serde_json::Error> {
+    serde_json::from_str(&self.state_after)
+  }
+}
+
+// embed stored JSON text as an object rather than as an escaped string
+fn serialize_json_text<S>(text: &String, serializer: S) -> Result<S::Ok, S::Error>
+where
+  S: Serializer,
+{
+  match serde_json::from_str::<serde_json::Value>(text) {
+    Ok(value) => value.serialize(serializer),
+    Err(_err) => serializer.serialize_str(text),
+  }
+}
@@ -0,0 +1,236 @@
+// Review schedulers. Every scheduler works on the same 0-5 grade scale
+// and the same persisted ReviewState so a deck can switch algorithms
+// without losing its history.
+use serde::{Deserialize, Serialize, Serializer};
+
+pub const MAX_GRADE: u8 = 5;
+pub const PASSING_GRADE: u8 = 3;
+const SECONDS_PER_DAY: u32 = 86_400;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct ReviewState {
+  pub ease_factor: f32,   // SM-2
+  pub interval: u32,      // days until the next review
+  pub repetitions: u16,   // consecutive successful reviews (Leitner: current box)
+  pub stability: f32,     // FSRS, 0 until the first FSRS review
+  pub difficulty: f32,    // FSRS
+  pub due: u32,           // unix timestamp (seconds) the card should next be shown
+  pub last_reviewed: u32, // unix timestamp (seconds), 0 if never reviewed
+}
+
+impl Default for ReviewState {
+  fn default() -> ReviewState {
+    ReviewState::new()
+  }
+}
+
+impl ReviewState {
+  // a card that has never been reviewed. due immediately.
+  pub fn new() -> ReviewState {
+    ReviewState {
+      ease_factor: Sm2::DEFAULT_EASE_FACTOR,
+      interval: 0,
+      repetitions: 0,
+      stability: 0.0,
+      difficulty: 0.0,
+      due: 0,
+      last_reviewed: 0,
+    }
+  }
+
+  // move the due date 'interval' days past 'now'
+  fn schedule_from(&mut self, now: u32) {
+    self.last_reviewed = now;
+    self.due = now.saturating_add(self.interval.saturating_mul(SECONDS_PER_DAY));
+  }
+}
+
+pub trait Scheduler: Send + Sync {
+  // produce the state after a review graded 0-5 at time 'now' (unix seconds)
+  // the returned state's 'due' is when the card should be shown next
+  fn schedule(&self, state: &ReviewState, grade: u8, now: u32) -> ReviewState;
+}
+
+// Which scheduler a deck uses. Stored as a tinyint on the deck record.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum SchedulerKind {
+  Sm2 = 0,
+  Leitner = 1,
+  Fsrs = 2,
+}
+
+static SM2: Sm2 = Sm2;
+static LEITNER: Leitner = Leitner;
+static FSRS: Fsrs = Fsrs;
+
+impl SchedulerKind {
+  pub fn from_id(id: u8) -> Option<SchedulerKind> {
+    match id {
+      0 => Some(SchedulerKind::Sm2),
+      1 => Some(SchedulerKind::Leitner),
+      2 => Some(SchedulerKind::Fsrs),
+      _ => None,
+    }
+  }
+
+  pub fn id(&self) -> u8 {
+    *self as u8
+  }
+
+  pub fn scheduler(&self) -> &'static dyn Scheduler {
+    match self {
+      SchedulerKind::Sm2 => &SM2,
+      SchedulerKind::Leitner => &LEITNER,
+      SchedulerKind::Fsrs => &FSRS,
+    }
+  }
+}
+
+// serialize a stored scheduler id by name, unknown ids fall back to the id itself
+pub fn serialize_scheduler_id<S>(id: &u8, serializer: S) -> Result<S::Ok, S::Error>
+where
+  S: Serializer,
+{
+  match SchedulerKind::from_id(*id) {
+    Some(kind) => kind.serialize(serializer),
+    None => serializer.serialize_u8(*id),
+  }
+}
+
+// SM-2, see https://www.supermemo.com/en/archives1990-2015/english/ol/sm2
+pub struct Sm2;
+
+impl Sm2 {
+  pub const DEFAULT_EASE_FACTOR: f32 = 2.5;
+  pub const MIN_EASE_FACTOR: f32 = 1.3;
+}
+
+impl Scheduler for Sm2 {
+  fn schedule(&self, state: &ReviewState, grade: u8, now: u32) -> ReviewState {
+    let grade = grade.min(MAX_GRADE);
+    let mut next = *state;
+    if grade >= PASSING_GRADE {
+      next.interval = match state.repetitions {
+        0 => 1,
+        1 => 6,
+        _ => (state.interval as f32 * state.ease_factor).round() as u32,
+      };
+      next.repetitions = state.repetitions.saturating_add(1);
+    } else {
+      // failed recall starts the card over, but keeps its ease factor
+      next.repetitions = 0;
+      next.interval = 1;
+    }
+    let miss = (MAX_GRADE - grade) as f32;
+    next.ease_factor =
+      (state.ease_factor + (0.1 - miss * (0.08 + miss * 0.02))).max(Sm2::MIN_EASE_FACTOR);
+    next.schedule_from(now);
+    return next;
+  }
+}
+
+// Leitner boxes. A passing grade promotes the card one box, a failing grade
+// sends it back to the first box. Each box doubles the review interval.
+pub struct Leitner;
+
+impl Leitner {
+  pub const BOX_INTERVALS: [u32; 5] = [1, 2, 4, 8, 16];
+}
+
+impl Scheduler for Leitner {
+  fn schedule(&self, state: &ReviewState, grade: u8, now: u32) -> ReviewState {
+    let last_box = Leitner::BOX_INTERVALS.len() as u16;
+    let mut next = *state;
+    next.repetitions = if grade.min(MAX_GRADE) >= PASSING_GRADE {
+      state.repetitions.saturating_add(1).min(last_box)
+    } else {
+      1
+    };
+    next.interval = Leitner::BOX_INTERVALS[(next.repetitions - 1) as usize];
+    next.schedule_from(now);
+    return next;
+  }
+}
+
+// FSRS v4.5 with the published default weights
+// see https://github.com/open-spaced-repetition/fsrs4anki/wiki/The-Algorithm
+pub struct Fsrs;
+
+impl Fsrs {
+  pub const WEIGHTS: [f32; 17] = [
+    0.4872, 1.4003, 3.7145, 13.8206, 5.1618, 1.2298, 0.8975, 0.031, 1.6474, 0.1367, 1.0461,
+    2.1072, 0.0793, 0.3246, 1.587, 0.2272, 2.8755,
+  ];
+  pub const DESIRED_RETENTION: f32 = 0.9;
+  pub const MAX_INTERVAL: u32 = 36_500;
+  const DECAY: f32 = -0.5;
+  const FACTOR: f32 = 19.0 / 81.0;
+
+  // map the shared 0-5 grade onto FSRS ratings: 1 again, 2 hard, 3 good, 4 easy
+  fn rating(grade: u8) -> u8 {
+    match grade.min(MAX_GRADE) {
+      0..=2 => 1,
+      3 => 2,
+      4 => 3,
+      _ => 4,
+    }
+  }
+
+  fn initial_difficulty(rating: u8) -> f32 {
+    let w = &Fsrs::WEIGHTS;
+    (w[4] - (rating as f32 - 3.0) * w[5]).clamp(1.0, 10.0)
+  }
+
+  fn retrievability(elapsed_days: f32, stability: f32) -> f32 {
+    (1.0 + Fsrs::FACTOR * elapsed_days / stability).powf(Fsrs::DECAY)
+  }
+
+  fn next_interval(stability: f32) -> u32 {
+    let days = stability / Fsrs::FACTOR
+      * (Fsrs::DESIRED_RETENTION.powf(1.0 / Fsrs::DECAY) - 1.0);
+    (days.round() as u32).clamp(1, Fsrs::MAX_INTERVAL)
+  }
+}
+
+impl Scheduler for Fsrs {
+  fn schedule(&self, state: &ReviewState, grade: u8, now: u32) -> ReviewState {
+    let w = &Fsrs::WEIGHTS;
+    let rating = Fsrs::rating(grade);
+    let mut next = *state;
+    if state.stability <= 0.0 {
+      // first FSRS review of this card
+      next.stability = w[(rating - 1) as usize];
+      next.difficulty = Fsrs::initial_difficulty(rating);
+    } else {
+      let elapsed_days = now.saturating_sub(state.last_reviewed) as f32 / SECONDS_PER_DAY as f32;
+      let r = Fsrs::retrievability(elapsed_days, state.stability);
+      let d = state.difficulty;
+      let s = state.stability;
+      next.difficulty = (w[7] * Fsrs::initial_difficulty(3)
+        + (1.0 - w[7]) * (d - w[6] * (rating as f32 - 3.0)))
+        .clamp(1.0, 10.0);
+      next.stability = if rating == 1 {
+        w[11] * d.powf(-w[12]) * ((s + 1.0).powf(w[13]) - 1.0) * (w[14] * (1.0 - r)).exp()
+      } else {
+        let hard_penalty = if rating == 2 { w[15] } else { 1.0 };
+        let easy_bonus = if rating == 4 { w[16] } else { 1.0 };
+        s * (w[8].exp()
+          * (11.0 - d)
+          * s.powf(-w[9])
+          * ((w[10] * (1.0 - r)).exp() - 1.0)
+          * hard_penalty
+          * easy_bonus
+          + 1.0)
+      };
+    }
+    if rating == 1 {
+      next.repetitions = 0;
+    } else {
+      next.repetitions = state.repetitions.saturating_add(1);
+    }
+    next.interval = Fsrs::next_interval(next.stability);
+    next.schedule_from(now);
+    return next;
+  }
+}
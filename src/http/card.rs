@@ -1,8 +1,8 @@
 use std::sync::Arc;
 
 use crate::FlashManager;
+use crate::card::CardKind;
 use serde::Deserialize;
-use tokio::sync::Mutex;
 use webe_auth::session::Session;
 use webe_web::request::Request;
 use webe_web::responders::Responder;
@@ -26,16 +26,20 @@ pub struct CreateCardForm {
     #[serde(deserialize_with = "webe_auth::utility::deserialize_from_string")]
     deck_id: u64,
     deck_pos: u16,
-    question: String,
-    answer: String,
+    #[serde(default)]
+    kind: CardKind,
+    question: String, // the text with its deletions for cloze cards
+    answer: String,   // extra notes for cloze cards
+    #[serde(default)]
+    reversible: bool, // basic cards only
 }
 
 pub struct CreateCardResponder {
-    flash_manager: Arc<Mutex<FlashManager>>,
+    flash_manager: Arc<FlashManager>,
 }
 
 impl CreateCardResponder {
-    pub fn new(flash_manager: Arc<Mutex<FlashManager>>) -> CreateCardResponder {
+    pub fn new(flash_manager: Arc<FlashManager>) -> CreateCardResponder {
         CreateCardResponder {
             flash_manager: flash_manager,
         }
@@ -65,13 +69,35 @@ impl Responder for CreateCardResponder {
                             .map_err(|_e| 400u16)?;
                         match serde_json::from_reader::<_, CreateCardForm>(body.as_slice()) {
                             Ok(form) => {
-                                match self.flash_manager.lock().await.create_card(
-                                    session_box.as_ref(),
-                                    form.deck_id,
-                                    form.deck_pos,
-                                    form.question,
-                                    form.answer,
-                                ) {
+                                match super::blocking(
+                                    &self.flash_manager,
+                                    move |manager| match form.kind {
+                                        CardKind::Basic if form.reversible => manager
+                                            .create_reversible_card(
+                                                session_box.as_ref(),
+                                                form.deck_id,
+                                                form.deck_pos,
+                                                form.question,
+                                                form.answer,
+                                            ),
+                                        CardKind::Basic => manager.create_card(
+                                            session_box.as_ref(),
+                                            form.deck_id,
+                                            form.deck_pos,
+                                            form.question,
+                                            form.answer,
+                                        ),
+                                        CardKind::Cloze => manager.create_cloze_card(
+                                            session_box.as_ref(),
+                                            form.deck_id,
+                                            form.deck_pos,
+                                            form.question,
+                                            form.answer,
+                                        ),
+                                    },
+                                )
+                                .await
+                                {
                                     Ok(card) => match serde_json::to_string(&card) {
                                         Ok(card_text) => {
                                             let responder = StaticResponder::new(200, card_text);
@@ -79,10 +105,7 @@ impl Responder for CreateCardResponder {
                                         }
                                         Err(_err) => return Err(500),
                                     },
-                                    Err(_err) => {
-                                        // TODO: Handle session errors / database errors
-                                        return Err(500);
-                                    }
+                                    Err(err) => return super::error_response(err),
                                 }
                             }
                             Err(_err) => return Err(400), // bad request
@@ -103,14 +126,15 @@ pub struct UpdateCardForm {
     id: u64,
     question: Option<String>,
     answer: Option<String>,
+    reversible: Option<bool>,
 }
 
 pub struct UpdateCardResponder {
-    flash_manager: Arc<Mutex<FlashManager>>,
+    flash_manager: Arc<FlashManager>,
 }
 
 impl UpdateCardResponder {
-    pub fn new(flash_manager: Arc<Mutex<FlashManager>>) -> UpdateCardResponder {
+    pub fn new(flash_manager: Arc<FlashManager>) -> UpdateCardResponder {
         UpdateCardResponder {
             flash_manager: flash_manager,
         }
@@ -139,20 +163,22 @@ impl Responder for UpdateCardResponder {
                             .map_err(|_e| 400u16)?;
                         match serde_json::from_reader::<_, UpdateCardForm>(body.as_slice()) {
                             Ok(form) => {
-                                match self.flash_manager.lock().await.update_card(
-                                    session_box.as_ref(),
-                                    form.id,
-                                    form.question,
-                                    form.answer,
-                                ) {
+                                match super::blocking(&self.flash_manager, move |manager| {
+                                    manager.update_card(
+                                        session_box.as_ref(),
+                                        form.id,
+                                        form.question,
+                                        form.answer,
+                                        form.reversible,
+                                    )
+                                })
+                                .await
+                                {
                                     Ok(()) => {
                                         let responder = StaticResponder::from_standard_code(200);
                                         return Ok(responder.quick_response());
                                     }
-                                    Err(_err) => {
-                                        // TODO: Handle session errors / database errors
-                                        return Err(500);
-                                    }
+                                    Err(err) => return super::error_response(err),
                                 }
                             }
                             Err(_err) => return Err(400), // bad request
@@ -180,11 +206,11 @@ pub struct UpdateCardPositionForm {
 }
 
 pub struct UpdateCardPositionResponder {
-    flash_manager: Arc<Mutex<FlashManager>>,
+    flash_manager: Arc<FlashManager>,
 }
 
 impl UpdateCardPositionResponder {
-    pub fn new(flash_manager: Arc<Mutex<FlashManager>>) -> UpdateCardPositionResponder {
+    pub fn new(flash_manager: Arc<FlashManager>) -> UpdateCardPositionResponder {
         UpdateCardPositionResponder {
             flash_manager: flash_manager,
         }
@@ -214,22 +240,174 @@ impl Responder for UpdateCardPositionResponder {
                         match serde_json::from_reader::<_, UpdateCardPositionForm>(body.as_slice())
                         {
                             Ok(form) => {
-                                match self.flash_manager.lock().await.update_card_position(
-                                    session_box.as_ref(),
-                                    form.id,
-                                    form.deck_id,
-                                    form.orig_pos,
-                                    form.new_pos,
-                                ) {
+                                match super::blocking(&self.flash_manager, move |manager| {
+                                    manager.update_card_position(
+                                        session_box.as_ref(),
+                                        form.id,
+                                        form.deck_id,
+                                        form.orig_pos,
+                                        form.new_pos,
+                                    )
+                                })
+                                .await
+                                {
                                     Ok(()) => {
                                         let responder = StaticResponder::from_standard_code(200);
                                         return Ok(responder.quick_response());
                                     }
-                                    Err(_err) => {
-                                        dbg!(_err);
-                                        // TODO: Handle session errors / database errors
-                                        return Err(500);
-                                    }
+                                    Err(err) => return super::error_response(err),
+                                }
+                            }
+                            Err(_err) => return Err(400),
+                        }
+                    }
+                    None => return Err(400),
+                },
+                Err(_err) => return Err(500),
+            },
+            None => return Err(400),
+        }
+    }
+}
+
+// SET CARD DISTRACTORS
+
+// the wrong options of a multiple choice card, an empty list makes it a regular card again
+#[derive(Deserialize)]
+pub struct SetDistractorsForm {
+    #[serde(deserialize_with = "webe_auth::utility::deserialize_from_string")]
+    id: u64,
+    distractors: Vec<String>,
+}
+
+pub struct SetDistractorsResponder {
+    flash_manager: Arc<FlashManager>,
+}
+
+impl SetDistractorsResponder {
+    pub fn new(flash_manager: Arc<FlashManager>) -> SetDistractorsResponder {
+        SetDistractorsResponder {
+            flash_manager: flash_manager,
+        }
+    }
+}
+
+#[async_trait]
+impl Responder for SetDistractorsResponder {
+    async fn build_response(
+        &self,
+        request: &mut Request,
+        _params: &Vec<(String, String)>,
+        validation: Validation,
+    ) -> Result<Response, u16> {
+        // Expecting session from an outer SecureResponder
+        match validation {
+            Some(dyn_box) => match dyn_box.downcast::<Session>() {
+                Ok(session_box) => match &mut request.message_body {
+                    Some(body_reader) => {
+                        let mut body = Vec::<u8>::new();
+                        // read the entire body or error.
+                        // TODO: improve workaround for serde not being able to handle async
+                        body_reader
+                            .read_to_end(&mut body)
+                            .await
+                            .map_err(|_e| 400u16)?;
+                        match serde_json::from_reader::<_, SetDistractorsForm>(body.as_slice()) {
+                            Ok(form) => {
+                                match super::blocking(&self.flash_manager, move |manager| {
+                                    manager.set_card_distractors(
+                                        session_box.as_ref(),
+                                        form.id,
+                                        form.distractors,
+                                    )
+                                })
+                                .await
+                                {
+                                    Ok(distractors) => match serde_json::to_string(&distractors) {
+                                        Ok(distractors_text) => {
+                                            let responder =
+                                                StaticResponder::new(200, distractors_text);
+                                            return Ok(responder.quick_response());
+                                        }
+                                        Err(_err) => return Err(500),
+                                    },
+                                    Err(err) => return super::error_response(err),
+                                }
+                            }
+                            Err(_err) => return Err(400),
+                        }
+                    }
+                    None => return Err(400),
+                },
+                Err(_err) => return Err(500),
+            },
+            None => return Err(400),
+        }
+    }
+}
+
+// SET CARD ACCEPTED ANSWERS
+
+// other answers a typed answer is accepted as, an empty list only accepts the card's own
+#[derive(Deserialize)]
+pub struct SetAcceptedAnswersForm {
+    #[serde(deserialize_with = "webe_auth::utility::deserialize_from_string")]
+    id: u64,
+    answers: Vec<String>,
+}
+
+pub struct SetAcceptedAnswersResponder {
+    flash_manager: Arc<FlashManager>,
+}
+
+impl SetAcceptedAnswersResponder {
+    pub fn new(flash_manager: Arc<FlashManager>) -> SetAcceptedAnswersResponder {
+        SetAcceptedAnswersResponder {
+            flash_manager: flash_manager,
+        }
+    }
+}
+
+#[async_trait]
+impl Responder for SetAcceptedAnswersResponder {
+    async fn build_response(
+        &self,
+        request: &mut Request,
+        _params: &Vec<(String, String)>,
+        validation: Validation,
+    ) -> Result<Response, u16> {
+        // Expecting session from an outer SecureResponder
+        match validation {
+            Some(dyn_box) => match dyn_box.downcast::<Session>() {
+                Ok(session_box) => match &mut request.message_body {
+                    Some(body_reader) => {
+                        let mut body = Vec::<u8>::new();
+                        // read the entire body or error.
+                        // TODO: improve workaround for serde not being able to handle async
+                        body_reader
+                            .read_to_end(&mut body)
+                            .await
+                            .map_err(|_e| 400u16)?;
+                        match serde_json::from_reader::<_, SetAcceptedAnswersForm>(body.as_slice())
+                        {
+                            Ok(form) => {
+                                match super::blocking(&self.flash_manager, move |manager| {
+                                    manager.set_card_answers(
+                                        session_box.as_ref(),
+                                        form.id,
+                                        form.answers,
+                                    )
+                                })
+                                .await
+                                {
+                                    Ok(answers) => match serde_json::to_string(&answers) {
+                                        Ok(answers_text) => {
+                                            let responder = StaticResponder::new(200, answers_text);
+                                            return Ok(responder.quick_response());
+                                        }
+                                        Err(_err) => return Err(500),
+                                    },
+                                    Err(err) => return super::error_response(err),
                                 }
                             }
                             Err(_err) => return Err(400),
@@ -247,11 +425,11 @@ impl Responder for UpdateCardPositionResponder {
 // DELETE CARD
 
 pub struct DeleteCardResponder {
-    flash_manager: Arc<Mutex<FlashManager>>,
+    flash_manager: Arc<FlashManager>,
 }
 
 impl DeleteCardResponder {
-    pub fn new(flash_manager: Arc<Mutex<FlashManager>>) -> DeleteCardResponder {
+    pub fn new(flash_manager: Arc<FlashManager>) -> DeleteCardResponder {
         DeleteCardResponder {
             flash_manager: flash_manager,
         }
@@ -280,20 +458,16 @@ impl Responder for DeleteCardResponder {
                             .map_err(|_e| 400u16)?;
                         match serde_json::from_reader::<_, CardIdForm>(body.as_slice()) {
                             Ok(form) => {
-                                match self
-                                    .flash_manager
-                                    .lock()
-                                    .await
-                                    .delete_card(session_box.as_ref(), form.card_id)
+                                match super::blocking(&self.flash_manager, move |manager| {
+                                    manager.delete_card(session_box.as_ref(), form.card_id)
+                                })
+                                .await
                                 {
                                     Ok(()) => {
                                         let responder = StaticResponder::from_standard_code(200);
                                         return Ok(responder.quick_response());
                                     }
-                                    Err(_err) => {
-                                        // TODO: Handle session errors / database errors                {
-                                        return Err(500);
-                                    }
+                                    Err(err) => return super::error_response(err),
                                 }
                             }
                             Err(_err) => return Err(400), // bad request
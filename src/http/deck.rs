@@ -1,7 +1,12 @@
 use std::sync::Arc;
-use tokio::sync::Mutex;
 
-use crate::FlashManager;
+use crate::{FlashError, FlashManager};
+use crate::deck::{DEFAULT_DECKS_PER_PAGE, DeckRole, DeckVisibility};
+use crate::game::SchedulerKind;
+use crate::transfer::TransferError;
+use crate::transfer::anki;
+use crate::transfer::bundle::DeckBundle;
+use crate::transfer::delimited::{ColumnMapping, DelimitedOptions};
 use serde::Deserialize;
 use webe_auth::session::Session;
 use webe_web::request::Request;
@@ -23,11 +28,11 @@ pub struct DeckIdForm {
 
 // FETCH DECKS FOR ACCOUNT
 pub struct DecksResponder {
-    flash_manager: Arc<Mutex<FlashManager>>,
+    flash_manager: Arc<FlashManager>,
 }
 
 impl DecksResponder {
-    pub fn new(flash_manager: Arc<Mutex<FlashManager>>) -> DecksResponder {
+    pub fn new(flash_manager: Arc<FlashManager>) -> DecksResponder {
         DecksResponder {
             flash_manager: flash_manager,
         }
@@ -46,33 +51,21 @@ impl Responder for DecksResponder {
         match validation {
             // TODO: maybe create some convenience function for unwrapping validation and parsing form from reader
             Some(dyn_box) => match dyn_box.downcast::<Session>() {
-                Ok(session_box) => match self
-                    .flash_manager
-                    .lock()
-                    .await
-                    .get_decks_for_session(session_box.as_ref())
+                Ok(session_box) => match super::blocking(&self.flash_manager, move |manager| {
+                    manager.get_decks_for_session(session_box.as_ref())
+                })
+                .await
                 {
                     Ok(decks) => match serde_json::to_string(&decks) {
                         Ok(deck_text) => {
                             let responder = StaticResponder::new(200, deck_text);
                             return Ok(responder.quick_response());
                         }
-                        Err(_err) => {
-                            dbg!(_err);
-                            return Err(500);
-                        } // TODO: parse session error, not found error, etc
+                        Err(_err) => return Err(500),
                     },
-                    Err(_err) => {
-                        println!("manager error");
-                        dbg!(session_box);
-                        return Err(500);
-                    }
+                    Err(err) => return super::error_response(err),
                 },
-                Err(_err) => {
-                    println!("session error");
-                    dbg!(_err);
-                    return Err(500);
-                }
+                Err(_err) => return Err(500),
             },
             None => return Err(400),
         }
@@ -82,15 +75,12 @@ impl Responder for DecksResponder {
 // FETCH SINGLE DECK WITH CARDS
 
 pub struct DeckDetailsResponder {
-    flash_manager: Arc<Mutex<FlashManager>>,
+    flash_manager: Arc<FlashManager>,
     deck_id_param: String,
 }
 
 impl DeckDetailsResponder {
-    pub fn new(
-        flash_manager: Arc<Mutex<FlashManager>>,
-        deck_id_param: String,
-    ) -> DeckDetailsResponder {
+    pub fn new(flash_manager: Arc<FlashManager>, deck_id_param: String) -> DeckDetailsResponder {
         DeckDetailsResponder {
             flash_manager: flash_manager,
             deck_id_param: deck_id_param,
@@ -113,35 +103,26 @@ impl Responder for DeckDetailsResponder {
                     .into_iter()
                     .find(|(key, _value)| key == &self.deck_id_param)
                 {
-                    Some((_key, deck_id_string)) => {
-                        match deck_id_string.parse::<u64>() {
-                            Ok(deck_id) => {
-                                match self
-                                    .flash_manager
-                                    .lock()
-                                    .await
-                                    .get_deck_details(session_box.as_ref(), &deck_id)
-                                {
-                                    Ok(details) => match serde_json::to_string(&details) {
-                                        Ok(details_text) => {
-                                            let responder = StaticResponder::new(200, details_text);
-                                            return Ok(responder.quick_response());
-                                        }
-                                        Err(_err) => return Err(500),
-                                    },
-                                    Err(_err) => {
-                                        // TODO: Handle session errors / database errors
-                                        return Err(500);
+                    Some((_key, deck_id_string)) => match deck_id_string.parse::<u64>() {
+                        Ok(deck_id) => {
+                            match super::blocking(&self.flash_manager, move |manager| {
+                                manager.get_deck_details(session_box.as_ref(), &deck_id)
+                            })
+                            .await
+                            {
+                                Ok(details) => match serde_json::to_string(&details) {
+                                    Ok(details_text) => {
+                                        let responder = StaticResponder::new(200, details_text);
+                                        return Ok(responder.quick_response());
                                     }
-                                }
+                                    Err(_err) => return Err(500),
+                                },
+                                Err(err) => return super::error_response(err),
                             }
-                            Err(_err) => return Err(400),
                         }
-                    }
-                    None => {
-                        println!("made it here");
-                        return Err(400);
-                    }
+                        Err(_err) => return Err(400),
+                    },
+                    None => return Err(400),
                 },
                 Err(_err) => return Err(500),
             },
@@ -157,11 +138,11 @@ pub struct CreateDeckForm {
 }
 
 pub struct CreateDeckResponder {
-    flash_manager: Arc<Mutex<FlashManager>>,
+    flash_manager: Arc<FlashManager>,
 }
 
 impl CreateDeckResponder {
-    pub fn new(flash_manager: Arc<Mutex<FlashManager>>) -> CreateDeckResponder {
+    pub fn new(flash_manager: Arc<FlashManager>) -> CreateDeckResponder {
         CreateDeckResponder {
             flash_manager: flash_manager,
         }
@@ -191,11 +172,10 @@ impl Responder for CreateDeckResponder {
                             .map_err(|_e| 400u16)?;
                         match serde_json::from_reader::<_, CreateDeckForm>(body.as_slice()) {
                             Ok(form) => {
-                                match self
-                                    .flash_manager
-                                    .lock()
-                                    .await
-                                    .create_deck(session_box.as_ref(), form.name)
+                                match super::blocking(&self.flash_manager, move |manager| {
+                                    manager.create_deck(session_box.as_ref(), form.name)
+                                })
+                                .await
                                 {
                                     Ok(deck) => match serde_json::to_string(&deck) {
                                         Ok(deck_text) => {
@@ -204,10 +184,7 @@ impl Responder for CreateDeckResponder {
                                         }
                                         Err(_err) => return Err(500),
                                     },
-                                    Err(_err) => {
-                                        // TODO: Handle session errors / database errors
-                                        return Err(500);
-                                    }
+                                    Err(err) => return super::error_response(err),
                                 }
                             }
                             Err(_err) => return Err(400), // bad request
@@ -231,11 +208,11 @@ pub struct RenameDeckForm {
 }
 
 pub struct UpdateDeckResponder {
-    flash_manager: Arc<Mutex<FlashManager>>,
+    flash_manager: Arc<FlashManager>,
 }
 
 impl UpdateDeckResponder {
-    pub fn new(flash_manager: Arc<Mutex<FlashManager>>) -> UpdateDeckResponder {
+    pub fn new(flash_manager: Arc<FlashManager>) -> UpdateDeckResponder {
         UpdateDeckResponder {
             flash_manager: flash_manager,
         }
@@ -264,19 +241,160 @@ impl Responder for UpdateDeckResponder {
                             .map_err(|_e| 400u16)?;
                         match serde_json::from_reader::<_, RenameDeckForm>(body.as_slice()) {
                             Ok(form) => {
-                                match self.flash_manager.lock().await.rename_deck(
-                                    session_box.as_ref(),
-                                    form.deck_id,
-                                    form.name.as_str(),
-                                ) {
+                                match super::blocking(&self.flash_manager, move |manager| {
+                                    manager.rename_deck(
+                                        session_box.as_ref(),
+                                        form.deck_id,
+                                        form.name.as_str(),
+                                    )
+                                })
+                                .await
+                                {
+                                    Ok(()) => {
+                                        let responder = StaticResponder::from_standard_code(200);
+                                        return Ok(responder.quick_response());
+                                    }
+                                    Err(err) => return super::error_response(err),
+                                }
+                            }
+                            Err(_err) => return Err(400), // bad request
+                        }
+                    }
+                    None => return Err(400),
+                },
+                Err(_err) => return Err(500),
+            },
+            None => return Err(400),
+        }
+    }
+}
+
+// SET DECK SCHEDULER
+#[derive(Deserialize)]
+pub struct SetDeckSchedulerForm {
+    #[serde(deserialize_with = "webe_auth::utility::deserialize_from_string")]
+    deck_id: u64,
+    scheduler: SchedulerKind,
+}
+
+pub struct SetDeckSchedulerResponder {
+    flash_manager: Arc<FlashManager>,
+}
+
+impl SetDeckSchedulerResponder {
+    pub fn new(flash_manager: Arc<FlashManager>) -> SetDeckSchedulerResponder {
+        SetDeckSchedulerResponder {
+            flash_manager: flash_manager,
+        }
+    }
+}
+
+#[async_trait]
+impl Responder for SetDeckSchedulerResponder {
+    async fn build_response(
+        &self,
+        request: &mut Request,
+        _params: &Vec<(String, String)>,
+        validation: Validation,
+    ) -> Result<Response, u16> {
+        // Expecting session from an outer SecureResponder
+        match validation {
+            Some(dyn_box) => match dyn_box.downcast::<Session>() {
+                Ok(session_box) => match &mut request.message_body {
+                    Some(body_reader) => {
+                        let mut body = Vec::<u8>::new();
+                        // read the entire body or error.
+                        // TODO: improve workaround for serde not being able to handle async
+                        body_reader
+                            .read_to_end(&mut body)
+                            .await
+                            .map_err(|_e| 400u16)?;
+                        match serde_json::from_reader::<_, SetDeckSchedulerForm>(body.as_slice()) {
+                            Ok(form) => {
+                                match super::blocking(&self.flash_manager, move |manager| {
+                                    manager.set_deck_scheduler(
+                                        session_box.as_ref(),
+                                        form.deck_id,
+                                        form.scheduler,
+                                    )
+                                })
+                                .await
+                                {
                                     Ok(()) => {
                                         let responder = StaticResponder::from_standard_code(200);
                                         return Ok(responder.quick_response());
                                     }
-                                    Err(_err) => {
-                                        // TODO: Handle session errors / database errors                {
-                                        return Err(500);
+                                    Err(err) => return super::error_response(err),
+                                }
+                            }
+                            Err(_err) => return Err(400), // bad request
+                        }
+                    }
+                    None => return Err(400),
+                },
+                Err(_err) => return Err(500),
+            },
+            None => return Err(400),
+        }
+    }
+}
+
+// SET DECK VISIBILITY
+#[derive(Deserialize)]
+pub struct SetDeckVisibilityForm {
+    #[serde(deserialize_with = "webe_auth::utility::deserialize_from_string")]
+    deck_id: u64,
+    visibility: DeckVisibility,
+}
+
+pub struct SetDeckVisibilityResponder {
+    flash_manager: Arc<FlashManager>,
+}
+
+impl SetDeckVisibilityResponder {
+    pub fn new(flash_manager: Arc<FlashManager>) -> SetDeckVisibilityResponder {
+        SetDeckVisibilityResponder {
+            flash_manager: flash_manager,
+        }
+    }
+}
+
+#[async_trait]
+impl Responder for SetDeckVisibilityResponder {
+    async fn build_response(
+        &self,
+        request: &mut Request,
+        _params: &Vec<(String, String)>,
+        validation: Validation,
+    ) -> Result<Response, u16> {
+        // Expecting session from an outer SecureResponder
+        match validation {
+            Some(dyn_box) => match dyn_box.downcast::<Session>() {
+                Ok(session_box) => match &mut request.message_body {
+                    Some(body_reader) => {
+                        let mut body = Vec::<u8>::new();
+                        // read the entire body or error.
+                        // TODO: improve workaround for serde not being able to handle async
+                        body_reader
+                            .read_to_end(&mut body)
+                            .await
+                            .map_err(|_e| 400u16)?;
+                        match serde_json::from_reader::<_, SetDeckVisibilityForm>(body.as_slice()) {
+                            Ok(form) => {
+                                match super::blocking(&self.flash_manager, move |manager| {
+                                    manager.set_deck_visibility(
+                                        session_box.as_ref(),
+                                        form.deck_id,
+                                        form.visibility,
+                                    )
+                                })
+                                .await
+                                {
+                                    Ok(()) => {
+                                        let responder = StaticResponder::from_standard_code(200);
+                                        return Ok(responder.quick_response());
                                     }
+                                    Err(err) => return super::error_response(err),
                                 }
                             }
                             Err(_err) => return Err(400), // bad request
@@ -294,11 +412,11 @@ impl Responder for UpdateDeckResponder {
 // DELETE DECK
 
 pub struct DeleteDeckResponder {
-    flash_manager: Arc<Mutex<FlashManager>>,
+    flash_manager: Arc<FlashManager>,
 }
 
 impl DeleteDeckResponder {
-    pub fn new(flash_manager: Arc<Mutex<FlashManager>>) -> DeleteDeckResponder {
+    pub fn new(flash_manager: Arc<FlashManager>) -> DeleteDeckResponder {
         DeleteDeckResponder {
             flash_manager: flash_manager,
         }
@@ -327,20 +445,727 @@ impl Responder for DeleteDeckResponder {
                             .map_err(|_e| 400u16)?;
                         match serde_json::from_reader::<_, DeckIdForm>(body.as_slice()) {
                             Ok(form) => {
-                                match self
-                                    .flash_manager
-                                    .lock()
-                                    .await
-                                    .delete_deck(session_box.as_ref(), form.deck_id)
+                                match super::blocking(&self.flash_manager, move |manager| {
+                                    manager.delete_deck(session_box.as_ref(), form.deck_id)
+                                })
+                                .await
+                                {
+                                    Ok(()) => {
+                                        let responder = StaticResponder::from_standard_code(200);
+                                        return Ok(responder.quick_response());
+                                    }
+                                    Err(err) => return super::error_response(err),
+                                }
+                            }
+                            Err(_err) => return Err(400), // bad request
+                        }
+                    }
+                    None => return Err(400),
+                },
+                Err(_err) => return Err(500),
+            },
+            None => return Err(400),
+        }
+    }
+}
+
+// SHARE DECK
+#[derive(Deserialize)]
+pub struct ShareDeckForm {
+    #[serde(deserialize_with = "webe_auth::utility::deserialize_from_string")]
+    deck_id: u64,
+    #[serde(deserialize_with = "webe_auth::utility::deserialize_from_string")]
+    account_id: u64,
+    role: DeckRole,
+}
+
+pub struct ShareDeckResponder {
+    flash_manager: Arc<FlashManager>,
+}
+
+impl ShareDeckResponder {
+    pub fn new(flash_manager: Arc<FlashManager>) -> ShareDeckResponder {
+        ShareDeckResponder {
+            flash_manager: flash_manager,
+        }
+    }
+}
+
+#[async_trait]
+impl Responder for ShareDeckResponder {
+    async fn build_response(
+        &self,
+        request: &mut Request,
+        _params: &Vec<(String, String)>,
+        validation: Validation,
+    ) -> Result<Response, u16> {
+        // Expecting session from an outer SecureResponder
+        match validation {
+            Some(dyn_box) => match dyn_box.downcast::<Session>() {
+                Ok(session_box) => match &mut request.message_body {
+                    Some(body_reader) => {
+                        let mut body = Vec::<u8>::new();
+                        // read the entire body or error.
+                        // TODO: improve workaround for serde not being able to handle async
+                        body_reader
+                            .read_to_end(&mut body)
+                            .await
+                            .map_err(|_e| 400u16)?;
+                        match serde_json::from_reader::<_, ShareDeckForm>(body.as_slice()) {
+                            Ok(form) => {
+                                match super::blocking(&self.flash_manager, move |manager| {
+                                    manager.share_deck(
+                                        session_box.as_ref(),
+                                        form.deck_id,
+                                        form.account_id,
+                                        form.role,
+                                    )
+                                })
+                                .await
                                 {
                                     Ok(()) => {
                                         let responder = StaticResponder::from_standard_code(200);
                                         return Ok(responder.quick_response());
                                     }
-                                    Err(_err) => {
-                                        // TODO: Handle session errors / database errors                {
-                                        return Err(500);
+                                    Err(err) => return super::error_response(err),
+                                }
+                            }
+                            Err(_err) => return Err(400), // bad request
+                        }
+                    }
+                    None => return Err(400),
+                },
+                Err(_err) => return Err(500),
+            },
+            None => return Err(400),
+        }
+    }
+}
+
+// UNSHARE DECK
+#[derive(Deserialize)]
+pub struct DeckMemberForm {
+    #[serde(deserialize_with = "webe_auth::utility::deserialize_from_string")]
+    deck_id: u64,
+    #[serde(deserialize_with = "webe_auth::utility::deserialize_from_string")]
+    account_id: u64,
+}
+
+pub struct UnshareDeckResponder {
+    flash_manager: Arc<FlashManager>,
+}
+
+impl UnshareDeckResponder {
+    pub fn new(flash_manager: Arc<FlashManager>) -> UnshareDeckResponder {
+        UnshareDeckResponder {
+            flash_manager: flash_manager,
+        }
+    }
+}
+
+#[async_trait]
+impl Responder for UnshareDeckResponder {
+    async fn build_response(
+        &self,
+        request: &mut Request,
+        _params: &Vec<(String, String)>,
+        validation: Validation,
+    ) -> Result<Response, u16> {
+        // Expecting session from an outer SecureResponder
+        match validation {
+            Some(dyn_box) => match dyn_box.downcast::<Session>() {
+                Ok(session_box) => match &mut request.message_body {
+                    Some(body_reader) => {
+                        let mut body = Vec::<u8>::new();
+                        // read the entire body or error.
+                        // TODO: improve workaround for serde not being able to handle async
+                        body_reader
+                            .read_to_end(&mut body)
+                            .await
+                            .map_err(|_e| 400u16)?;
+                        match serde_json::from_reader::<_, DeckMemberForm>(body.as_slice()) {
+                            Ok(form) => {
+                                match super::blocking(&self.flash_manager, move |manager| {
+                                    manager.unshare_deck(
+                                        session_box.as_ref(),
+                                        form.deck_id,
+                                        form.account_id,
+                                    )
+                                })
+                                .await
+                                {
+                                    Ok(()) => {
+                                        let responder = StaticResponder::from_standard_code(200);
+                                        return Ok(responder.quick_response());
                                     }
+                                    Err(err) => return super::error_response(err),
+                                }
+                            }
+                            Err(_err) => return Err(400), // bad request
+                        }
+                    }
+                    None => return Err(400),
+                },
+                Err(_err) => return Err(500),
+            },
+            None => return Err(400),
+        }
+    }
+}
+
+// FETCH DECK MEMBERS
+
+pub struct DeckMembersResponder {
+    flash_manager: Arc<FlashManager>,
+    deck_id_param: String,
+}
+
+impl DeckMembersResponder {
+    pub fn new(flash_manager: Arc<FlashManager>, deck_id_param: String) -> DeckMembersResponder {
+        DeckMembersResponder {
+            flash_manager: flash_manager,
+            deck_id_param: deck_id_param,
+        }
+    }
+}
+
+#[async_trait]
+impl Responder for DeckMembersResponder {
+    async fn build_response(
+        &self,
+        _request: &mut Request,
+        params: &Vec<(String, String)>,
+        validation: Validation,
+    ) -> Result<Response, u16> {
+        // Expecting session from an outer SecureResponder
+        match validation {
+            Some(dyn_box) => match dyn_box.downcast::<Session>() {
+                Ok(session_box) => match params
+                    .iter()
+                    .find(|(key, _value)| key == &self.deck_id_param)
+                {
+                    Some((_key, deck_id_string)) => match deck_id_string.parse::<u64>() {
+                        Ok(deck_id) => {
+                            match super::blocking(&self.flash_manager, move |manager| {
+                                manager.list_members(session_box.as_ref(), deck_id)
+                            })
+                            .await
+                            {
+                                Ok(members) => match serde_json::to_string(&members) {
+                                    Ok(members_text) => {
+                                        let responder = StaticResponder::new(200, members_text);
+                                        return Ok(responder.quick_response());
+                                    }
+                                    Err(_err) => return Err(500),
+                                },
+                                Err(err) => return super::error_response(err),
+                            }
+                        }
+                        Err(_err) => return Err(400),
+                    },
+                    None => return Err(400),
+                },
+                Err(_err) => return Err(500),
+            },
+            None => return Err(400),
+        }
+    }
+}
+
+// IMPORT ANKI PACKAGE, request body is the raw .apkg file
+
+pub struct ImportAnkiResponder {
+    flash_manager: Arc<FlashManager>,
+}
+
+impl ImportAnkiResponder {
+    pub fn new(flash_manager: Arc<FlashManager>) -> ImportAnkiResponder {
+        ImportAnkiResponder {
+            flash_manager: flash_manager,
+        }
+    }
+}
+
+#[async_trait]
+impl Responder for ImportAnkiResponder {
+    async fn build_response(
+        &self,
+        request: &mut Request,
+        _params: &Vec<(String, String)>,
+        validation: Validation,
+    ) -> Result<Response, u16> {
+        // Expecting session from an outer SecureResponder
+        match validation {
+            Some(dyn_box) => match dyn_box.downcast::<Session>() {
+                Ok(session_box) => match &mut request.message_body {
+                    Some(body_reader) => {
+                        // one byte past the limit is enough to tell the package is too large
+                        let max_bytes = anki::MAX_PACKAGE_BYTES;
+                        let mut body = Vec::<u8>::new();
+                        body_reader
+                            .take(max_bytes + 1)
+                            .read_to_end(&mut body)
+                            .await
+                            .map_err(|_e| 400u16)?;
+                        if body.len() as u64 > max_bytes {
+                            return super::error_response(FlashError::TransferError(
+                                TransferError::TooLarge,
+                            ));
+                        }
+                        match super::blocking(&self.flash_manager, move |manager| {
+                            manager.import_anki(session_box.as_ref(), body.as_slice())
+                        })
+                        .await
+                        {
+                            Ok(report) => match serde_json::to_string(&report) {
+                                Ok(report_text) => {
+                                    let responder = StaticResponder::new(200, report_text);
+                                    return Ok(responder.quick_response());
+                                }
+                                Err(_err) => return Err(500),
+                            },
+                            Err(err) => return super::error_response(err),
+                        }
+                    }
+                    None => return Err(400),
+                },
+                Err(_err) => return Err(500),
+            },
+            None => return Err(400),
+        }
+    }
+}
+
+// EXPORT DECK AS CSV/TSV
+
+pub struct ExportDeckCsvResponder {
+    flash_manager: Arc<FlashManager>,
+    deck_id_param: String,
+    format_param: String,  // optional, "csv" (default) or "tsv"
+    headers_param: String, // optional, "false" to leave out the header row
+    columns_param: String, // optional, see ColumnMapping::parse. defaults to "0,1"
+}
+
+impl ExportDeckCsvResponder {
+    pub fn new(
+        flash_manager: Arc<FlashManager>,
+        deck_id_param: String,
+        format_param: String,
+        headers_param: String,
+        columns_param: String,
+    ) -> ExportDeckCsvResponder {
+        ExportDeckCsvResponder {
+            flash_manager: flash_manager,
+            deck_id_param: deck_id_param,
+            format_param: format_param,
+            headers_param: headers_param,
+            columns_param: columns_param,
+        }
+    }
+}
+
+#[async_trait]
+impl Responder for ExportDeckCsvResponder {
+    async fn build_response(
+        &self,
+        _request: &mut Request,
+        params: &Vec<(String, String)>,
+        validation: Validation,
+    ) -> Result<Response, u16> {
+        let mut options = match params
+            .iter()
+            .find(|(key, _value)| key == &self.format_param)
+        {
+            Some((_key, format)) => match format.as_str() {
+                "csv" => DelimitedOptions::csv(),
+                "tsv" => DelimitedOptions::tsv(),
+                _ => return Err(400), // unknown format
+            },
+            None => DelimitedOptions::csv(),
+        };
+        if let Some((_key, headers)) = params
+            .iter()
+            .find(|(key, _value)| key == &self.headers_param)
+        {
+            options.has_headers = headers.parse::<bool>().map_err(|_e| 400u16)?;
+        }
+        if let Some((_key, columns)) = params
+            .iter()
+            .find(|(key, _value)| key == &self.columns_param)
+        {
+            options.columns = ColumnMapping::parse(columns).ok_or(400u16)?;
+        }
+        // Expecting session from an outer SecureResponder
+        match validation {
+            Some(dyn_box) => match dyn_box.downcast::<Session>() {
+                Ok(session_box) => match params
+                    .iter()
+                    .find(|(key, _value)| key == &self.deck_id_param)
+                {
+                    Some((_key, deck_id_string)) => match deck_id_string.parse::<u64>() {
+                        Ok(deck_id) => {
+                            match super::blocking(&self.flash_manager, move |manager| {
+                                manager.export_deck_csv(session_box.as_ref(), deck_id, &options)
+                            })
+                            .await
+                            {
+                                Ok(deck_text) => {
+                                    let responder = StaticResponder::new(200, deck_text);
+                                    return Ok(responder.quick_response());
+                                }
+                                Err(err) => return super::error_response(err),
+                            }
+                        }
+                        Err(_err) => return Err(400),
+                    },
+                    None => return Err(400),
+                },
+                Err(_err) => return Err(500),
+            },
+            None => return Err(400),
+        }
+    }
+}
+
+// IMPORT DECK FROM CSV/TSV
+#[derive(Deserialize)]
+pub struct ImportDeckCsvForm {
+    pub name: String,
+    pub data: String,
+    #[serde(default)]
+    pub options: DelimitedOptions,
+}
+
+pub struct ImportDeckCsvResponder {
+    flash_manager: Arc<FlashManager>,
+}
+
+impl ImportDeckCsvResponder {
+    pub fn new(flash_manager: Arc<FlashManager>) -> ImportDeckCsvResponder {
+        ImportDeckCsvResponder {
+            flash_manager: flash_manager,
+        }
+    }
+}
+
+#[async_trait]
+impl Responder for ImportDeckCsvResponder {
+    async fn build_response(
+        &self,
+        request: &mut Request,
+        _params: &Vec<(String, String)>,
+        validation: Validation,
+    ) -> Result<Response, u16> {
+        // Expecting session from an outer SecureResponder
+        match validation {
+            Some(dyn_box) => match dyn_box.downcast::<Session>() {
+                Ok(session_box) => match &mut request.message_body {
+                    Some(body_reader) => {
+                        let mut body = Vec::<u8>::new();
+                        // read the entire body or error.
+                        // TODO: improve workaround for serde not being able to handle async
+                        body_reader
+                            .read_to_end(&mut body)
+                            .await
+                            .map_err(|_e| 400u16)?;
+                        match serde_json::from_reader::<_, ImportDeckCsvForm>(body.as_slice()) {
+                            Ok(form) => {
+                                match super::blocking(&self.flash_manager, move |manager| {
+                                    manager.import_deck_csv(
+                                        session_box.as_ref(),
+                                        form.name,
+                                        form.data.as_str(),
+                                        &form.options,
+                                    )
+                                })
+                                .await
+                                {
+                                    Ok(report) => match serde_json::to_string(&report) {
+                                        Ok(report_text) => {
+                                            // rejected rows are a client problem
+                                            let code =
+                                                if report.errors.is_empty() { 200 } else { 400 };
+                                            let responder = StaticResponder::new(code, report_text);
+                                            return Ok(responder.quick_response());
+                                        }
+                                        Err(_err) => return Err(500),
+                                    },
+                                    Err(err) => return super::error_response(err),
+                                }
+                            }
+                            Err(_err) => return Err(400), // bad request
+                        }
+                    }
+                    None => return Err(400),
+                },
+                Err(_err) => return Err(500),
+            },
+            None => return Err(400),
+        }
+    }
+}
+
+// EXPORT DECK BUNDLE
+
+pub struct ExportBundleResponder {
+    flash_manager: Arc<FlashManager>,
+    deck_id_param: String,
+    history_param: String, // optional, "true" to include scores and reviews
+}
+
+impl ExportBundleResponder {
+    pub fn new(
+        flash_manager: Arc<FlashManager>,
+        deck_id_param: String,
+        history_param: String,
+    ) -> ExportBundleResponder {
+        ExportBundleResponder {
+            flash_manager: flash_manager,
+            deck_id_param: deck_id_param,
+            history_param: history_param,
+        }
+    }
+}
+
+#[async_trait]
+impl Responder for ExportBundleResponder {
+    async fn build_response(
+        &self,
+        _request: &mut Request,
+        params: &Vec<(String, String)>,
+        validation: Validation,
+    ) -> Result<Response, u16> {
+        let include_history = match params
+            .iter()
+            .find(|(key, _value)| key == &self.history_param)
+        {
+            Some((_key, history)) => match history.parse::<bool>() {
+                Ok(include_history) => include_history,
+                Err(_err) => return Err(400),
+            },
+            None => false,
+        };
+        // Expecting session from an outer SecureResponder
+        match validation {
+            Some(dyn_box) => match dyn_box.downcast::<Session>() {
+                Ok(session_box) => match params
+                    .iter()
+                    .find(|(key, _value)| key == &self.deck_id_param)
+                {
+                    Some((_key, deck_id_string)) => match deck_id_string.parse::<u64>() {
+                        Ok(deck_id) => {
+                            match super::blocking(&self.flash_manager, move |manager| {
+                                manager.export_bundle(
+                                    session_box.as_ref(),
+                                    deck_id,
+                                    include_history,
+                                )
+                            })
+                            .await
+                            {
+                                Ok(bundle) => match serde_json::to_string(&bundle) {
+                                    Ok(bundle_text) => {
+                                        let responder = StaticResponder::new(200, bundle_text);
+                                        return Ok(responder.quick_response());
+                                    }
+                                    Err(_err) => return Err(500),
+                                },
+                                Err(err) => return super::error_response(err),
+                            }
+                        }
+                        Err(_err) => return Err(400),
+                    },
+                    None => return Err(400),
+                },
+                Err(_err) => return Err(500),
+            },
+            None => return Err(400),
+        }
+    }
+}
+
+// IMPORT DECK BUNDLE, request body is the bundle JSON
+
+pub struct ImportBundleResponder {
+    flash_manager: Arc<FlashManager>,
+}
+
+impl ImportBundleResponder {
+    pub fn new(flash_manager: Arc<FlashManager>) -> ImportBundleResponder {
+        ImportBundleResponder {
+            flash_manager: flash_manager,
+        }
+    }
+}
+
+#[async_trait]
+impl Responder for ImportBundleResponder {
+    async fn build_response(
+        &self,
+        request: &mut Request,
+        _params: &Vec<(String, String)>,
+        validation: Validation,
+    ) -> Result<Response, u16> {
+        // Expecting session from an outer SecureResponder
+        match validation {
+            Some(dyn_box) => match dyn_box.downcast::<Session>() {
+                Ok(session_box) => match &mut request.message_body {
+                    Some(body_reader) => {
+                        let mut body = String::new();
+                        body_reader
+                            .read_to_string(&mut body)
+                            .await
+                            .map_err(|_e| 400u16)?;
+                        match DeckBundle::from_json(body.as_str()) {
+                            Ok(bundle) => {
+                                match super::blocking(&self.flash_manager, move |manager| {
+                                    manager.import_bundle(session_box.as_ref(), &bundle)
+                                })
+                                .await
+                                {
+                                    Ok(deck) => match serde_json::to_string(&deck) {
+                                        Ok(deck_text) => {
+                                            let responder = StaticResponder::new(200, deck_text);
+                                            return Ok(responder.quick_response());
+                                        }
+                                        Err(_err) => return Err(500),
+                                    },
+                                    Err(err) => return super::error_response(err),
+                                }
+                            }
+                            Err(_err) => return Err(400), // unreadable or unsupported bundle
+                        }
+                    }
+                    None => return Err(400),
+                },
+                Err(_err) => return Err(500),
+            },
+            None => return Err(400),
+        }
+    }
+}
+
+// BROWSE PUBLIC DECKS
+
+pub struct BrowseDecksResponder {
+    flash_manager: Arc<FlashManager>,
+    search_param: String, // optional, only list decks whose name contains it
+    page_param: String,   // optional, defaults to the first page
+}
+
+impl BrowseDecksResponder {
+    pub fn new(
+        flash_manager: Arc<FlashManager>,
+        search_param: String,
+        page_param: String,
+    ) -> BrowseDecksResponder {
+        BrowseDecksResponder {
+            flash_manager: flash_manager,
+            search_param: search_param,
+            page_param: page_param,
+        }
+    }
+}
+
+#[async_trait]
+impl Responder for BrowseDecksResponder {
+    async fn build_response(
+        &self,
+        _request: &mut Request,
+        params: &Vec<(String, String)>,
+        validation: Validation,
+    ) -> Result<Response, u16> {
+        let search = params
+            .iter()
+            .find(|(key, _value)| key == &self.search_param)
+            .map(|(_key, search)| search.to_owned());
+        let page = match params.iter().find(|(key, _value)| key == &self.page_param) {
+            Some((_key, page)) => match page.parse::<u32>() {
+                Ok(page) => page,
+                Err(_err) => return Err(400),
+            },
+            None => 0,
+        };
+        // Expecting session from an outer SecureResponder
+        match validation {
+            Some(dyn_box) => match dyn_box.downcast::<Session>() {
+                Ok(session_box) => {
+                    match super::blocking(&self.flash_manager, move |manager| {
+                        manager.browse_public_decks(
+                            session_box.as_ref(),
+                            search.as_deref(),
+                            page,
+                            DEFAULT_DECKS_PER_PAGE,
+                        )
+                    })
+                    .await
+                    {
+                        Ok(deck_page) => match serde_json::to_string(&deck_page) {
+                            Ok(page_text) => {
+                                let responder = StaticResponder::new(200, page_text);
+                                return Ok(responder.quick_response());
+                            }
+                            Err(_err) => return Err(500),
+                        },
+                        Err(err) => return super::error_response(err),
+                    }
+                }
+                Err(_err) => return Err(500),
+            },
+            None => return Err(400),
+        }
+    }
+}
+
+// CLONE DECK INTO THE SESSION'S LIBRARY
+
+pub struct CloneDeckResponder {
+    flash_manager: Arc<FlashManager>,
+}
+
+impl CloneDeckResponder {
+    pub fn new(flash_manager: Arc<FlashManager>) -> CloneDeckResponder {
+        CloneDeckResponder {
+            flash_manager: flash_manager,
+        }
+    }
+}
+
+#[async_trait]
+impl Responder for CloneDeckResponder {
+    async fn build_response(
+        &self,
+        request: &mut Request,
+        _params: &Vec<(String, String)>,
+        validation: Validation,
+    ) -> Result<Response, u16> {
+        // Expecting session from an outer SecureResponder
+        match validation {
+            Some(dyn_box) => match dyn_box.downcast::<Session>() {
+                Ok(session_box) => match &mut request.message_body {
+                    Some(body_reader) => {
+                        let mut body = Vec::<u8>::new();
+                        // read the entire body or error.
+                        // TODO: improve workaround for serde not being able to handle async
+                        body_reader
+                            .read_to_end(&mut body)
+                            .await
+                            .map_err(|_e| 400u16)?;
+                        match serde_json::from_reader::<_, DeckIdForm>(body.as_slice()) {
+                            Ok(form) => {
+                                match super::blocking(&self.flash_manager, move |manager| {
+                                    manager.clone_deck(session_box.as_ref(), form.deck_id)
+                                })
+                                .await
+                                {
+                                    Ok(deck) => match serde_json::to_string(&deck) {
+                                        Ok(deck_text) => {
+                                            let responder = StaticResponder::new(200, deck_text);
+                                            return Ok(responder.quick_response());
+                                        }
+                                        Err(_err) => return Err(500),
+                                    },
+                                    Err(err) => return super::error_response(err),
                                 }
                             }
                             Err(_err) => return Err(400), // bad request
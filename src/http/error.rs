@@ -0,0 +1,111 @@
+// Maps a FlashError to the HTTP status and JSON body sent to the client, e.g.
+//   404 {"code":"not_found","message":"the deck or card doesn't exist"}
+// 'code' is stable and meant for programs, 'message' is for people and may change.
+
+use diesel::result::DatabaseErrorKind as DBErrorKind;
+use diesel::result::Error as DieselError;
+use serde::Serialize;
+use webe_web::responders::static_message::StaticResponder;
+use webe_web::response::Response;
+
+use crate::FlashError;
+use crate::db::DBApiError;
+use crate::media::MediaError;
+use crate::transfer::TransferError;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    SessionExpired,   // 401
+    PermissionDenied, // 403
+    NotFound,         // 404
+    Conflict,         // 409, e.g. a card already at that position
+    NotAllowed,       // 422, the request can never succeed as given
+    InvalidTransfer,  // 422, an import that can't be read or an unsupported export option
+    TooLarge,         // 413, an upload over the configured size
+    UnsupportedMedia, // 415, an upload that isn't one of media::CONTENT_TYPES
+    Unavailable,      // 503, no database connection available, try again later
+    Internal,         // 500
+}
+
+impl ErrorCode {
+    pub fn status(&self) -> u16 {
+        match self {
+            ErrorCode::SessionExpired => 401,
+            ErrorCode::PermissionDenied => 403,
+            ErrorCode::NotFound => 404,
+            ErrorCode::Conflict => 409,
+            ErrorCode::NotAllowed => 422,
+            ErrorCode::InvalidTransfer => 422,
+            ErrorCode::TooLarge => 413,
+            ErrorCode::UnsupportedMedia => 415,
+            ErrorCode::Unavailable => 503,
+            ErrorCode::Internal => 500,
+        }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self {
+            ErrorCode::SessionExpired => "the session has expired, log in again",
+            ErrorCode::PermissionDenied => "the account doesn't have the role this requires",
+            ErrorCode::NotFound => "the deck, card or attachment doesn't exist",
+            ErrorCode::Conflict => "the change conflicts with the existing data",
+            ErrorCode::NotAllowed => "the change isn't allowed",
+            ErrorCode::InvalidTransfer => "the import or export couldn't be processed",
+            ErrorCode::TooLarge => "the upload is too large",
+            ErrorCode::UnsupportedMedia => "the upload isn't a supported image or audio type",
+            ErrorCode::Unavailable => "the database is busy, try again later",
+            ErrorCode::Internal => "internal server error",
+        }
+    }
+}
+
+impl From<&FlashError> for ErrorCode {
+    fn from(err: &FlashError) -> ErrorCode {
+        match err {
+            FlashError::SessionTimeout => ErrorCode::SessionExpired,
+            FlashError::PermissionError => ErrorCode::PermissionDenied,
+            FlashError::DBError(DBApiError::NotFound) => ErrorCode::NotFound,
+            FlashError::DBError(DBApiError::NotAllowed) => ErrorCode::NotAllowed,
+            FlashError::DBError(DBApiError::PoolError(_err)) => ErrorCode::Unavailable,
+            FlashError::DBError(DBApiError::OtherError(DieselError::DatabaseError(
+                DBErrorKind::UniqueViolation | DBErrorKind::ForeignKeyViolation,
+                _info,
+            ))) => ErrorCode::Conflict,
+            FlashError::TransferError(TransferError::TooLarge) => ErrorCode::TooLarge,
+            FlashError::TransferError(_err) => ErrorCode::InvalidTransfer,
+            FlashError::MediaError(MediaError::NotFound) => ErrorCode::NotFound,
+            FlashError::MediaError(MediaError::TooLarge) => ErrorCode::TooLarge,
+            FlashError::MediaError(MediaError::UnsupportedType) => ErrorCode::UnsupportedMedia,
+            _ => ErrorCode::Internal,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct ErrorBody {
+    pub code: ErrorCode,
+    pub message: &'static str,
+}
+
+impl From<&FlashError> for ErrorBody {
+    fn from(err: &FlashError) -> ErrorBody {
+        let code = ErrorCode::from(err);
+        ErrorBody {
+            code: code,
+            message: code.message(),
+        }
+    }
+}
+
+// the response for a failed FlashManager call
+pub fn error_response(err: FlashError) -> Result<Response, u16> {
+    let code = ErrorCode::from(&err);
+    match serde_json::to_string(&ErrorBody::from(&err)) {
+        Ok(body_text) => {
+            let responder = StaticResponder::new(code.status(), body_text);
+            return Ok(responder.quick_response());
+        }
+        Err(_err) => return Err(code.status()),
+    }
+}
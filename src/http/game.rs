@@ -1,9 +1,9 @@
 use std::sync::Arc;
 
 use crate::FlashManager;
+use crate::game::queue::DEFAULT_QUEUE_LIMIT;
 use serde::Deserialize;
 use tokio::io::AsyncReadExt;
-use tokio::sync::Mutex;
 use webe_auth::session::Session;
 use webe_web::request::Request;
 use webe_web::responders::Responder;
@@ -18,15 +18,18 @@ use async_trait::async_trait;
 pub struct UpdateScoreForm {
     #[serde(deserialize_with = "webe_auth::utility::deserialize_from_string")]
     card_id: u64,
+    #[serde(default)]
+    item: u16, // which of the card's items was answered, 0 for basic cards
     score: u8,
+    response_ms: Option<u32>,
 }
 
 pub struct UpdateScoreResponder {
-    flash_manager: Arc<Mutex<FlashManager>>,
+    flash_manager: Arc<FlashManager>,
 }
 
 impl UpdateScoreResponder {
-    pub fn new(flash_manager: Arc<Mutex<FlashManager>>) -> UpdateScoreResponder {
+    pub fn new(flash_manager: Arc<FlashManager>) -> UpdateScoreResponder {
         UpdateScoreResponder {
             flash_manager: flash_manager,
         }
@@ -56,19 +59,180 @@ impl Responder for UpdateScoreResponder {
                             .map_err(|_e| 400u16)?;
                         match serde_json::from_reader::<_, UpdateScoreForm>(body.as_slice()) {
                             Ok(form) => {
-                                match self.flash_manager.lock().await.update_score(
-                                    session_box.as_ref(),
-                                    form.card_id,
-                                    form.score,
-                                ) {
-                                    Ok(()) => {
-                                        let responder = StaticResponder::from_standard_code(200);
-                                        return Ok(responder.quick_response());
-                                    }
-                                    Err(_err) => {
-                                        // TODO: Handle session errors / database errors
-                                        return Err(500);
-                                    }
+                                match super::blocking(&self.flash_manager, move |manager| {
+                                    manager.update_item_score(
+                                        session_box.as_ref(),
+                                        form.card_id,
+                                        form.item,
+                                        form.score,
+                                        form.response_ms,
+                                    )
+                                })
+                                .await
+                                {
+                                    Ok(card_score) => match serde_json::to_string(&card_score) {
+                                        Ok(score_text) => {
+                                            let responder = StaticResponder::new(200, score_text);
+                                            return Ok(responder.quick_response());
+                                        }
+                                        Err(_err) => return Err(500),
+                                    },
+                                    Err(err) => return super::error_response(err),
+                                }
+                            }
+                            Err(_err) => return Err(400), // bad request
+                        }
+                    }
+                    None => return Err(400),
+                },
+                Err(_err) => return Err(500),
+            },
+            None => return Err(400),
+        }
+    }
+}
+
+// Form for picking one option of a multiple choice card
+#[derive(Deserialize)]
+pub struct AnswerChoiceForm {
+    #[serde(deserialize_with = "webe_auth::utility::deserialize_from_string")]
+    card_id: u64,
+    choice: String, // one of the options from the study queue
+    response_ms: Option<u32>,
+}
+
+// Grades the picked option on the server, clients never send a score for these cards
+pub struct AnswerChoiceResponder {
+    flash_manager: Arc<FlashManager>,
+}
+
+impl AnswerChoiceResponder {
+    pub fn new(flash_manager: Arc<FlashManager>) -> AnswerChoiceResponder {
+        AnswerChoiceResponder {
+            flash_manager: flash_manager,
+        }
+    }
+}
+
+#[async_trait]
+impl Responder for AnswerChoiceResponder {
+    async fn build_response(
+        &self,
+        request: &mut Request,
+        _params: &Vec<(String, String)>,
+        validation: Validation,
+    ) -> Result<Response, u16> {
+        // Expecting session from an outer SecureResponder
+        match validation {
+            Some(dyn_box) => match dyn_box.downcast::<Session>() {
+                Ok(session_box) => match &mut request.message_body {
+                    Some(body_reader) => {
+                        let mut body = Vec::<u8>::new();
+                        // read the entire body or error.
+                        // TODO: improve workaround for serde not being able to handle async
+                        body_reader
+                            .read_to_end(&mut body)
+                            .await
+                            .map_err(|_e| 400u16)?;
+                        match serde_json::from_reader::<_, AnswerChoiceForm>(body.as_slice()) {
+                            Ok(form) => {
+                                match super::blocking(&self.flash_manager, move |manager| {
+                                    manager.answer_choice(
+                                        session_box.as_ref(),
+                                        form.card_id,
+                                        &form.choice,
+                                        form.response_ms,
+                                    )
+                                })
+                                .await
+                                {
+                                    Ok(result) => match serde_json::to_string(&result) {
+                                        Ok(result_text) => {
+                                            let responder = StaticResponder::new(200, result_text);
+                                            return Ok(responder.quick_response());
+                                        }
+                                        Err(_err) => return Err(500),
+                                    },
+                                    Err(err) => return super::error_response(err),
+                                }
+                            }
+                            Err(_err) => return Err(400), // bad request
+                        }
+                    }
+                    None => return Err(400),
+                },
+                Err(_err) => return Err(500),
+            },
+            None => return Err(400),
+        }
+    }
+}
+
+// Form for typing the answer of a basic card
+#[derive(Deserialize)]
+pub struct AnswerTypedForm {
+    #[serde(deserialize_with = "webe_auth::utility::deserialize_from_string")]
+    card_id: u64,
+    #[serde(default)]
+    item: u16, // 0 for question -> answer, 1 for the reverse of a reversible card
+    typed: String,
+    response_ms: Option<u32>,
+}
+
+// Checks the typed answer on the server and responds with the grade and a diff
+pub struct AnswerTypedResponder {
+    flash_manager: Arc<FlashManager>,
+}
+
+impl AnswerTypedResponder {
+    pub fn new(flash_manager: Arc<FlashManager>) -> AnswerTypedResponder {
+        AnswerTypedResponder {
+            flash_manager: flash_manager,
+        }
+    }
+}
+
+#[async_trait]
+impl Responder for AnswerTypedResponder {
+    async fn build_response(
+        &self,
+        request: &mut Request,
+        _params: &Vec<(String, String)>,
+        validation: Validation,
+    ) -> Result<Response, u16> {
+        // Expecting session from an outer SecureResponder
+        match validation {
+            Some(dyn_box) => match dyn_box.downcast::<Session>() {
+                Ok(session_box) => match &mut request.message_body {
+                    Some(body_reader) => {
+                        let mut body = Vec::<u8>::new();
+                        // read the entire body or error.
+                        // TODO: improve workaround for serde not being able to handle async
+                        body_reader
+                            .read_to_end(&mut body)
+                            .await
+                            .map_err(|_e| 400u16)?;
+                        match serde_json::from_reader::<_, AnswerTypedForm>(body.as_slice()) {
+                            Ok(form) => {
+                                match super::blocking(&self.flash_manager, move |manager| {
+                                    manager.answer_typed(
+                                        session_box.as_ref(),
+                                        form.card_id,
+                                        form.item,
+                                        &form.typed,
+                                        form.response_ms,
+                                    )
+                                })
+                                .await
+                                {
+                                    Ok(result) => match serde_json::to_string(&result) {
+                                        Ok(result_text) => {
+                                            let responder = StaticResponder::new(200, result_text);
+                                            return Ok(responder.quick_response());
+                                        }
+                                        Err(_err) => return Err(500),
+                                    },
+                                    Err(err) => return super::error_response(err),
                                 }
                             }
                             Err(_err) => return Err(400), // bad request
@@ -85,15 +249,12 @@ impl Responder for UpdateScoreResponder {
 
 // Deck Scores Responder
 pub struct DeckScoresResponder {
-    flash_manager: Arc<Mutex<FlashManager>>,
+    flash_manager: Arc<FlashManager>,
     deck_id_param: String,
 }
 
 impl DeckScoresResponder {
-    pub fn new(
-        flash_manager: Arc<Mutex<FlashManager>>,
-        deck_id_param: String,
-    ) -> DeckScoresResponder {
+    pub fn new(flash_manager: Arc<FlashManager>, deck_id_param: String) -> DeckScoresResponder {
         DeckScoresResponder {
             flash_manager: flash_manager,
             deck_id_param: deck_id_param,
@@ -120,11 +281,10 @@ impl Responder for DeckScoresResponder {
                     Some((_key, deck_id_string)) => {
                         match deck_id_string.parse::<u64>() {
                             Ok(deck_id) => {
-                                match self
-                                    .flash_manager
-                                    .lock()
-                                    .await
-                                    .get_deck_scores(session_box.as_ref(), deck_id)
+                                match super::blocking(&self.flash_manager, move |manager| {
+                                    manager.get_deck_scores(session_box.as_ref(), deck_id)
+                                })
+                                .await
                                 {
                                     Ok(scores) => match serde_json::to_string(&scores) {
                                         Ok(scores_text) => {
@@ -133,22 +293,74 @@ impl Responder for DeckScoresResponder {
                                         }
                                         Err(_err) => return Err(500),
                                     },
-                                    Err(_err) => {
-                                        // TODO: Handle session errors / database errors
-                                        return Err(500);
-                                    }
+                                    Err(err) => return super::error_response(err),
                                 }
                             }
-                            Err(_err) => {
-                                dbg!(_err);
-                                return Err(400);
-                            } // bad request, failed to parse deck id
+                            Err(_err) => return Err(400), // bad request, failed to parse deck id
                         }
                     }
-                    None => {
-                        dbg!("reached None");
-                        return Err(400);
+                    None => return Err(400),
+                },
+                Err(_err) => return Err(500),
+            },
+            None => return Err(400),
+        }
+    }
+}
+
+// Review History Responder
+pub struct ReviewHistoryResponder {
+    flash_manager: Arc<FlashManager>,
+    deck_id_param: String,
+}
+
+impl ReviewHistoryResponder {
+    pub fn new(flash_manager: Arc<FlashManager>, deck_id_param: String) -> ReviewHistoryResponder {
+        ReviewHistoryResponder {
+            flash_manager: flash_manager,
+            deck_id_param: deck_id_param,
+        }
+    }
+}
+
+#[async_trait]
+impl Responder for ReviewHistoryResponder {
+    async fn build_response(
+        &self,
+        _request: &mut Request,
+        params: &Vec<(String, String)>,
+        validation: Validation,
+    ) -> Result<Response, u16> {
+        // Expecting session from an outer SecureResponder
+        match validation {
+            // TODO: maybe create some convenience function for unwrapping validation and parsing form from reader
+            Some(dyn_box) => match dyn_box.downcast::<Session>() {
+                Ok(session_box) => match params
+                    .into_iter()
+                    .find(|(key, _value)| key == &self.deck_id_param)
+                {
+                    Some((_key, deck_id_string)) => {
+                        match deck_id_string.parse::<u64>() {
+                            Ok(deck_id) => {
+                                match super::blocking(&self.flash_manager, move |manager| {
+                                    manager.get_review_history(session_box.as_ref(), deck_id)
+                                })
+                                .await
+                                {
+                                    Ok(reviews) => match serde_json::to_string(&reviews) {
+                                        Ok(reviews_text) => {
+                                            let responder = StaticResponder::new(200, reviews_text);
+                                            return Ok(responder.quick_response());
+                                        }
+                                        Err(_err) => return Err(500),
+                                    },
+                                    Err(err) => return super::error_response(err),
+                                }
+                            }
+                            Err(_err) => return Err(400), // bad request, failed to parse deck id
+                        }
                     }
+                    None => return Err(400),
                 },
                 Err(_err) => return Err(500),
             },
@@ -156,3 +368,79 @@ impl Responder for DeckScoresResponder {
         }
     }
 }
+
+// Study Queue Responder, the cards due for study in a deck
+pub struct StudyQueueResponder {
+    flash_manager: Arc<FlashManager>,
+    deck_id_param: String,
+    limit_param: String, // optional, defaults to DEFAULT_QUEUE_LIMIT
+}
+
+impl StudyQueueResponder {
+    pub fn new(
+        flash_manager: Arc<FlashManager>,
+        deck_id_param: String,
+        limit_param: String,
+    ) -> StudyQueueResponder {
+        StudyQueueResponder {
+            flash_manager: flash_manager,
+            deck_id_param: deck_id_param,
+            limit_param: limit_param,
+        }
+    }
+}
+
+#[async_trait]
+impl Responder for StudyQueueResponder {
+    async fn build_response(
+        &self,
+        _request: &mut Request,
+        params: &Vec<(String, String)>,
+        validation: Validation,
+    ) -> Result<Response, u16> {
+        let limit = match params
+            .iter()
+            .find(|(key, _value)| key == &self.limit_param)
+        {
+            Some((_key, limit_string)) => match limit_string.parse::<u16>() {
+                Ok(limit) => limit,
+                Err(_err) => return Err(400), // bad request, failed to parse limit
+            },
+            None => DEFAULT_QUEUE_LIMIT,
+        };
+        // Expecting session from an outer SecureResponder
+        match validation {
+            Some(dyn_box) => match dyn_box.downcast::<Session>() {
+                Ok(session_box) => match params
+                    .iter()
+                    .find(|(key, _value)| key == &self.deck_id_param)
+                {
+                    Some((_key, deck_id_string)) => match deck_id_string.parse::<u64>() {
+                        Ok(deck_id) => {
+                            match super::blocking(&self.flash_manager, move |manager| {
+                                manager.get_study_queue(session_box.as_ref(), deck_id, limit)
+                            })
+                            .await
+                            {
+                                Ok(queue) => match serde_json::to_string(&queue) {
+                                    Ok(queue_text) => {
+                                        let responder = StaticResponder::new(200, queue_text);
+                                        return Ok(responder.quick_response());
+                                    }
+                                    Err(_err) => return Err(500),
+                                },
+                                Err(err) => return super::error_response(err),
+                            }
+                        }
+                        Err(_err) => return Err(400), // bad request, failed to parse deck id
+                    },
+                    None => return Err(400),
+                },
+                Err(_err) => return Err(500),
+            },
+            None => return Err(400),
+        }
+    }
+}
+
+pub type DueCardsResponder = StudyQueueResponder;
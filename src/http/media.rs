@@ -0,0 +1,248 @@
+// Uploading, downloading and removing card attachments.
+// Uploads are the raw bytes of the file as the request body, with its Content-Type header,
+// and the card and side taken from the path, e.g. POST /card/{card_id}/media/{side}
+use std::sync::Arc;
+
+use crate::media::{MediaError, MediaSide};
+use crate::{FlashError, FlashManager};
+use serde::Deserialize;
+use webe_auth::session::Session;
+use webe_web::request::Request;
+use webe_web::responders::Responder;
+use webe_web::responders::static_message::StaticResponder;
+use webe_web::response::Response;
+use webe_web::validation::Validation;
+
+use async_trait::async_trait;
+
+use tokio::io::AsyncReadExt;
+
+// the value of a path parameter
+fn param<'a>(params: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    params
+        .iter()
+        .find(|(key, _value)| key == name)
+        .map(|(_key, value)| value.as_str())
+}
+
+// UPLOAD MEDIA
+
+pub struct UploadMediaResponder {
+    flash_manager: Arc<FlashManager>,
+    card_id_param: String,
+    side_param: String, // "question" or "answer"
+}
+
+impl UploadMediaResponder {
+    pub fn new(
+        flash_manager: Arc<FlashManager>,
+        card_id_param: String,
+        side_param: String,
+    ) -> UploadMediaResponder {
+        UploadMediaResponder {
+            flash_manager: flash_manager,
+            card_id_param: card_id_param,
+            side_param: side_param,
+        }
+    }
+}
+
+#[async_trait]
+impl Responder for UploadMediaResponder {
+    async fn build_response(
+        &self,
+        request: &mut Request,
+        params: &Vec<(String, String)>,
+        validation: Validation,
+    ) -> Result<Response, u16> {
+        let card_id = match param(params, &self.card_id_param).map(str::parse::<u64>) {
+            Some(Ok(card_id)) => card_id,
+            _ => return Err(400),
+        };
+        let side = match param(params, &self.side_param).and_then(MediaSide::from_name) {
+            Some(side) => side,
+            None => return Err(400),
+        };
+        let content_type = match request
+            .headers
+            .iter()
+            .find(|(name, _value)| name.eq_ignore_ascii_case("content-type"))
+        {
+            Some((_name, content_type)) => content_type.clone(),
+            None => return Err(400),
+        };
+        // Expecting session from an outer SecureResponder
+        match validation {
+            Some(dyn_box) => match dyn_box.downcast::<Session>() {
+                Ok(session_box) => match &mut request.message_body {
+                    Some(body_reader) => {
+                        // read one byte past the limit, so an oversized upload is never read in full
+                        let max_bytes = self.flash_manager.max_media_bytes() as u64;
+                        let mut bytes = Vec::<u8>::new();
+                        body_reader
+                            .take(max_bytes + 1)
+                            .read_to_end(&mut bytes)
+                            .await
+                            .map_err(|_e| 400u16)?;
+                        if bytes.len() as u64 > max_bytes {
+                            return super::error_response(FlashError::MediaError(
+                                MediaError::TooLarge,
+                            ));
+                        }
+                        match super::blocking(&self.flash_manager, move |manager| {
+                            manager.add_card_media(
+                                session_box.as_ref(),
+                                card_id,
+                                side,
+                                &content_type,
+                                &bytes,
+                            )
+                        })
+                        .await
+                        {
+                            Ok(card_media) => match serde_json::to_string(&card_media) {
+                                Ok(media_text) => {
+                                    let responder = StaticResponder::new(200, media_text);
+                                    return Ok(responder.quick_response());
+                                }
+                                Err(_err) => return Err(500),
+                            },
+                            Err(err) => return super::error_response(err),
+                        }
+                    }
+                    None => return Err(400),
+                },
+                Err(_err) => return Err(500),
+            },
+            None => return Err(400),
+        }
+    }
+}
+
+// DOWNLOAD MEDIA
+
+pub struct DownloadMediaResponder {
+    flash_manager: Arc<FlashManager>,
+    media_id_param: String,
+}
+
+impl DownloadMediaResponder {
+    pub fn new(flash_manager: Arc<FlashManager>, media_id_param: String) -> DownloadMediaResponder {
+        DownloadMediaResponder {
+            flash_manager: flash_manager,
+            media_id_param: media_id_param,
+        }
+    }
+}
+
+#[async_trait]
+impl Responder for DownloadMediaResponder {
+    async fn build_response(
+        &self,
+        _request: &mut Request,
+        params: &Vec<(String, String)>,
+        validation: Validation,
+    ) -> Result<Response, u16> {
+        let media_id = match param(params, &self.media_id_param).map(str::parse::<u64>) {
+            Some(Ok(media_id)) => media_id,
+            _ => return Err(400),
+        };
+        // Expecting session from an outer SecureResponder
+        match validation {
+            Some(dyn_box) => match dyn_box.downcast::<Session>() {
+                Ok(session_box) => {
+                    match super::blocking(&self.flash_manager, move |manager| {
+                        manager.get_media(session_box.as_ref(), media_id)
+                    })
+                    .await
+                    {
+                        Ok((card_media, bytes)) => {
+                            let mut response = Response::new(200);
+                            response
+                                .headers
+                                .insert("Content-Type".to_owned(), card_media.content_type);
+                            response
+                                .headers
+                                .insert("Content-Length".to_owned(), bytes.len().to_string());
+                            // only ever treat the bytes as the checked content type
+                            response
+                                .headers
+                                .insert("X-Content-Type-Options".to_owned(), "nosniff".to_owned());
+                            response.message_body = Some(Box::pin(std::io::Cursor::new(bytes)));
+                            return Ok(response);
+                        }
+                        Err(err) => return super::error_response(err),
+                    }
+                }
+                Err(_err) => return Err(500),
+            },
+            None => return Err(400),
+        }
+    }
+}
+
+// REMOVE MEDIA
+
+#[derive(Deserialize)]
+pub struct RemoveMediaForm {
+    #[serde(deserialize_with = "webe_auth::utility::deserialize_from_string")]
+    id: u64,
+}
+
+pub struct RemoveMediaResponder {
+    flash_manager: Arc<FlashManager>,
+}
+
+impl RemoveMediaResponder {
+    pub fn new(flash_manager: Arc<FlashManager>) -> RemoveMediaResponder {
+        RemoveMediaResponder {
+            flash_manager: flash_manager,
+        }
+    }
+}
+
+#[async_trait]
+impl Responder for RemoveMediaResponder {
+    async fn build_response(
+        &self,
+        request: &mut Request,
+        _params: &Vec<(String, String)>,
+        validation: Validation,
+    ) -> Result<Response, u16> {
+        // Expecting session from an outer SecureResponder
+        match validation {
+            Some(dyn_box) => match dyn_box.downcast::<Session>() {
+                Ok(session_box) => match &mut request.message_body {
+                    Some(body_reader) => {
+                        let mut body = Vec::<u8>::new();
+                        // read the entire body or error.
+                        // TODO: improve workaround for serde not being able to handle async
+                        body_reader
+                            .read_to_end(&mut body)
+                            .await
+                            .map_err(|_e| 400u16)?;
+                        match serde_json::from_reader::<_, RemoveMediaForm>(body.as_slice()) {
+                            Ok(form) => {
+                                match super::blocking(&self.flash_manager, move |manager| {
+                                    manager.remove_card_media(session_box.as_ref(), form.id)
+                                })
+                                .await
+                                {
+                                    Ok(()) => {
+                                        let responder = StaticResponder::from_standard_code(200);
+                                        return Ok(responder.quick_response());
+                                    }
+                                    Err(err) => return super::error_response(err),
+                                }
+                            }
+                            Err(_err) => return Err(400), // bad request
+                        }
+                    }
+                    None => return Err(400),
+                },
+                Err(_err) => return Err(500),
+            },
+            None => return Err(400),
+        }
+    }
+}
@@ -1,3 +1,27 @@
+use std::sync::Arc;
+
+use crate::{FlashError, FlashManager};
+
 pub mod card;
 pub mod deck;
+pub mod error;
 pub mod game;
+pub mod media;
+pub mod study;
+
+pub use error::error_response;
+
+// run a FlashManager call on tokio's blocking thread pool.
+// the db layer is synchronous, so calling it directly from a responder would stall
+// the runtime worker thread, and every other request waiting on it, for the whole query
+pub async fn blocking<F, T>(flash_manager: &Arc<FlashManager>, f: F) -> Result<T, FlashError>
+where
+    F: FnOnce(&FlashManager) -> Result<T, FlashError> + Send + 'static,
+    T: Send + 'static,
+{
+    let manager = flash_manager.clone();
+    match tokio::task::spawn_blocking(move || f(&manager)).await {
+        Ok(result) => return result,
+        Err(_err) => return Err(FlashError::OtherError), // the call panicked
+    }
+}
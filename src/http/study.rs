@@ -0,0 +1,296 @@
+use std::sync::Arc;
+
+use crate::FlashManager;
+use serde::Deserialize;
+use tokio::io::AsyncReadExt;
+use webe_auth::session::Session;
+use webe_web::request::Request;
+use webe_web::responders::Responder;
+use webe_web::responders::static_message::StaticResponder;
+use webe_web::response::Response;
+use webe_web::validation::Validation;
+
+use async_trait::async_trait;
+
+// Form for targeting a single study session
+#[derive(Deserialize)]
+pub struct StudyIdForm {
+    #[serde(deserialize_with = "webe_auth::utility::deserialize_from_string")]
+    study_id: u64,
+}
+
+// START (OR RESUME) STUDY SESSION
+#[derive(Deserialize)]
+pub struct StartStudyForm {
+    #[serde(deserialize_with = "webe_auth::utility::deserialize_from_string")]
+    deck_id: u64,
+}
+
+pub struct StartStudyResponder {
+    flash_manager: Arc<FlashManager>,
+}
+
+impl StartStudyResponder {
+    pub fn new(flash_manager: Arc<FlashManager>) -> StartStudyResponder {
+        StartStudyResponder {
+            flash_manager: flash_manager,
+        }
+    }
+}
+
+#[async_trait]
+impl Responder for StartStudyResponder {
+    async fn build_response(
+        &self,
+        request: &mut Request,
+        _params: &Vec<(String, String)>,
+        validation: Validation,
+    ) -> Result<Response, u16> {
+        // Expecting session from an outer SecureResponder
+        match validation {
+            Some(dyn_box) => match dyn_box.downcast::<Session>() {
+                Ok(session_box) => match &mut request.message_body {
+                    Some(body_reader) => {
+                        let mut body = Vec::<u8>::new();
+                        // read the entire body or error.
+                        // TODO: improve workaround for serde not being able to handle async
+                        body_reader
+                            .read_to_end(&mut body)
+                            .await
+                            .map_err(|_e| 400u16)?;
+                        match serde_json::from_reader::<_, StartStudyForm>(body.as_slice()) {
+                            Ok(form) => {
+                                match super::blocking(&self.flash_manager, move |manager| {
+                                    manager.start_study_session(session_box.as_ref(), form.deck_id)
+                                })
+                                .await
+                                {
+                                    Ok(study_session) => {
+                                        match serde_json::to_string(&study_session) {
+                                            Ok(study_text) => {
+                                                let responder =
+                                                    StaticResponder::new(200, study_text);
+                                                return Ok(responder.quick_response());
+                                            }
+                                            Err(_err) => return Err(500),
+                                        }
+                                    }
+                                    Err(err) => return super::error_response(err),
+                                }
+                            }
+                            Err(_err) => return Err(400), // bad request
+                        }
+                    }
+                    None => return Err(400),
+                },
+                Err(_err) => return Err(500),
+            },
+            None => return Err(400),
+        }
+    }
+}
+
+// ANSWER A CARD WITHIN A STUDY SESSION
+#[derive(Deserialize)]
+pub struct StudyAnswerForm {
+    #[serde(deserialize_with = "webe_auth::utility::deserialize_from_string")]
+    study_id: u64,
+    #[serde(deserialize_with = "webe_auth::utility::deserialize_from_string")]
+    card_id: u64,
+    #[serde(default)]
+    item: u16, // which of the card's items was answered, 0 for basic cards
+    score: u8,
+    response_ms: Option<u32>,
+}
+
+pub struct StudyAnswerResponder {
+    flash_manager: Arc<FlashManager>,
+}
+
+impl StudyAnswerResponder {
+    pub fn new(flash_manager: Arc<FlashManager>) -> StudyAnswerResponder {
+        StudyAnswerResponder {
+            flash_manager: flash_manager,
+        }
+    }
+}
+
+#[async_trait]
+impl Responder for StudyAnswerResponder {
+    async fn build_response(
+        &self,
+        request: &mut Request,
+        _params: &Vec<(String, String)>,
+        validation: Validation,
+    ) -> Result<Response, u16> {
+        // Expecting session from an outer SecureResponder
+        match validation {
+            Some(dyn_box) => match dyn_box.downcast::<Session>() {
+                Ok(session_box) => match &mut request.message_body {
+                    Some(body_reader) => {
+                        let mut body = Vec::<u8>::new();
+                        // read the entire body or error.
+                        // TODO: improve workaround for serde not being able to handle async
+                        body_reader
+                            .read_to_end(&mut body)
+                            .await
+                            .map_err(|_e| 400u16)?;
+                        match serde_json::from_reader::<_, StudyAnswerForm>(body.as_slice()) {
+                            Ok(form) => {
+                                match super::blocking(&self.flash_manager, move |manager| {
+                                    manager.answer_study_item(
+                                        session_box.as_ref(),
+                                        form.study_id,
+                                        form.card_id,
+                                        form.item,
+                                        form.score,
+                                        form.response_ms,
+                                    )
+                                })
+                                .await
+                                {
+                                    Ok(card_score) => match serde_json::to_string(&card_score) {
+                                        Ok(score_text) => {
+                                            let responder = StaticResponder::new(200, score_text);
+                                            return Ok(responder.quick_response());
+                                        }
+                                        Err(_err) => return Err(500),
+                                    },
+                                    Err(err) => return super::error_response(err),
+                                }
+                            }
+                            Err(_err) => return Err(400), // bad request
+                        }
+                    }
+                    None => return Err(400),
+                },
+                Err(_err) => return Err(500),
+            },
+            None => return Err(400),
+        }
+    }
+}
+
+// FINISH STUDY SESSION, responds with the session summary
+pub struct FinishStudyResponder {
+    flash_manager: Arc<FlashManager>,
+}
+
+impl FinishStudyResponder {
+    pub fn new(flash_manager: Arc<FlashManager>) -> FinishStudyResponder {
+        FinishStudyResponder {
+            flash_manager: flash_manager,
+        }
+    }
+}
+
+#[async_trait]
+impl Responder for FinishStudyResponder {
+    async fn build_response(
+        &self,
+        request: &mut Request,
+        _params: &Vec<(String, String)>,
+        validation: Validation,
+    ) -> Result<Response, u16> {
+        // Expecting session from an outer SecureResponder
+        match validation {
+            Some(dyn_box) => match dyn_box.downcast::<Session>() {
+                Ok(session_box) => match &mut request.message_body {
+                    Some(body_reader) => {
+                        let mut body = Vec::<u8>::new();
+                        // read the entire body or error.
+                        // TODO: improve workaround for serde not being able to handle async
+                        body_reader
+                            .read_to_end(&mut body)
+                            .await
+                            .map_err(|_e| 400u16)?;
+                        match serde_json::from_reader::<_, StudyIdForm>(body.as_slice()) {
+                            Ok(form) => {
+                                match super::blocking(&self.flash_manager, move |manager| {
+                                    manager
+                                        .finish_study_session(session_box.as_ref(), form.study_id)
+                                })
+                                .await
+                                {
+                                    Ok(study_session) => {
+                                        match serde_json::to_string(&study_session) {
+                                            Ok(study_text) => {
+                                                let responder =
+                                                    StaticResponder::new(200, study_text);
+                                                return Ok(responder.quick_response());
+                                            }
+                                            Err(_err) => return Err(500),
+                                        }
+                                    }
+                                    Err(err) => return super::error_response(err),
+                                }
+                            }
+                            Err(_err) => return Err(400), // bad request
+                        }
+                    }
+                    None => return Err(400),
+                },
+                Err(_err) => return Err(500),
+            },
+            None => return Err(400),
+        }
+    }
+}
+
+// FETCH A SINGLE STUDY SESSION
+pub struct StudySessionResponder {
+    flash_manager: Arc<FlashManager>,
+    study_id_param: String,
+}
+
+impl StudySessionResponder {
+    pub fn new(flash_manager: Arc<FlashManager>, study_id_param: String) -> StudySessionResponder {
+        StudySessionResponder {
+            flash_manager: flash_manager,
+            study_id_param: study_id_param,
+        }
+    }
+}
+
+#[async_trait]
+impl Responder for StudySessionResponder {
+    async fn build_response(
+        &self,
+        _request: &mut Request,
+        params: &Vec<(String, String)>,
+        validation: Validation,
+    ) -> Result<Response, u16> {
+        // Expecting session from an outer SecureResponder
+        match validation {
+            Some(dyn_box) => match dyn_box.downcast::<Session>() {
+                Ok(session_box) => match params
+                    .iter()
+                    .find(|(key, _value)| key == &self.study_id_param)
+                {
+                    Some((_key, study_id_string)) => match study_id_string.parse::<u64>() {
+                        Ok(study_id) => {
+                            match super::blocking(&self.flash_manager, move |manager| {
+                                manager.get_study_session(session_box.as_ref(), study_id)
+                            })
+                            .await
+                            {
+                                Ok(study_session) => match serde_json::to_string(&study_session) {
+                                    Ok(study_text) => {
+                                        let responder = StaticResponder::new(200, study_text);
+                                        return Ok(responder.quick_response());
+                                    }
+                                    Err(_err) => return Err(500),
+                                },
+                                Err(err) => return super::error_response(err),
+                            }
+                        }
+                        Err(_err) => return Err(400), // bad request, failed to parse study id
+                    },
+                    None => return Err(400),
+                },
+                Err(_err) => return Err(500),
+            },
+            None => return Err(400),
+        }
+    }
+}
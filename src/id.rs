@@ -0,0 +1,105 @@
+// Lock-free generator for the unique ids of decks, cards, reviews etc.
+// Every thread can take ids from the same generator at the same time, so FlashManager
+// doesn't need a lock around it.
+//
+// An id is made of, from the highest bits down:
+// - 42 bits: milliseconds since the generator's epoch (~139 years)
+// - 8 bits: node id, so several servers can create ids without talking to each other
+// - 14 bits: sequence, counting the ids created within the same millisecond
+// Ids from one generator always increase, even if the system clock goes backwards.
+//
+// Databases filled before this generator hold ids from webe_id's WebeIDFactory, whose
+// layout isn't guaranteed to match. Pass the largest id already stored to skip_past when
+// starting up on such a database: every id handed out afterwards is larger, so the two can
+// never collide.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+
+const NODE_BITS: u32 = 8;
+const SEQUENCE_BITS: u32 = 14;
+const TIMESTAMP_BITS: u32 = 64 - NODE_BITS - SEQUENCE_BITS;
+const SEQUENCE_MASK: u64 = (1 << SEQUENCE_BITS) - 1;
+const MAX_TIMESTAMP: u64 = (1 << TIMESTAMP_BITS) - 1;
+
+#[derive(Debug)]
+pub enum IdError {
+    EpochInFuture, // the epoch is later than the current time
+    Exhausted,     // every timestamp after the epoch has been used up
+}
+
+#[derive(Debug)]
+pub struct IdGenerator {
+    epoch: SystemTime,
+    node_id: u8,
+    // the last (timestamp, sequence) handed out, packed as 'timestamp << SEQUENCE_BITS | sequence'
+    last: AtomicU64,
+}
+
+impl IdGenerator {
+    pub fn new(epoch: SystemTime, node_id: u8) -> Result<IdGenerator, IdError> {
+        if epoch > SystemTime::now() {
+            return Err(IdError::EpochInFuture);
+        }
+        return Ok(IdGenerator {
+            epoch: epoch,
+            node_id: node_id,
+            last: AtomicU64::new(0),
+        });
+    }
+
+    pub fn node_id(&self) -> u8 {
+        self.node_id
+    }
+
+    // make every following id larger than 'id', whatever generator created it
+    pub fn skip_past(&self, id: u64) {
+        // ids are ordered by timestamp first, so act as if every id in the
+        // millisecond of 'id' had been handed out already
+        let timestamp = id >> (NODE_BITS + SEQUENCE_BITS);
+        self.last.fetch_max(
+            (timestamp << SEQUENCE_BITS) | SEQUENCE_MASK,
+            Ordering::Relaxed,
+        );
+    }
+
+    pub fn next(&self) -> Result<u64, IdError> {
+        let now = self.millis_since_epoch()?;
+        let mut last = self.last.load(Ordering::Relaxed);
+        loop {
+            // a new millisecond starts a new sequence.
+            // otherwise keep counting up from the last id, a full sequence carries
+            // into the next millisecond rather than waiting for the clock
+            let next = if now > last >> SEQUENCE_BITS {
+                now << SEQUENCE_BITS
+            } else {
+                last + 1
+            };
+            if next >> SEQUENCE_BITS > MAX_TIMESTAMP {
+                return Err(IdError::Exhausted);
+            }
+            match self
+                .last
+                .compare_exchange_weak(last, next, Ordering::Relaxed, Ordering::Relaxed)
+            {
+                Ok(_previous) => {
+                    let timestamp = next >> SEQUENCE_BITS;
+                    let sequence = next & SEQUENCE_MASK;
+                    return Ok((timestamp << (NODE_BITS + SEQUENCE_BITS))
+                        | ((self.node_id as u64) << SEQUENCE_BITS)
+                        | sequence);
+                }
+                // another thread took an id first, try again after it
+                Err(actual) => last = actual,
+            }
+        }
+    }
+
+    fn millis_since_epoch(&self) -> Result<u64, IdError> {
+        match SystemTime::now().duration_since(self.epoch) {
+            Ok(elapsed) => return Ok(elapsed.as_millis().min(MAX_TIMESTAMP as u128 + 1) as u64),
+            // the clock went back past the epoch, the last id still keeps us increasing
+            Err(_err) => return Ok(0),
+        }
+    }
+}
@@ -2,25 +2,39 @@
 extern crate diesel;
 extern crate r2d2;
 extern crate webe_auth;
-extern crate webe_id;
 extern crate webe_web;
 
 pub mod card;
+pub mod config;
 pub mod db;
 pub mod deck;
 pub mod game;
 pub mod http;
+pub mod id;
+pub mod media;
 pub mod schema;
+pub mod study;
+pub mod transfer;
 
 use webe_auth::session::Session;
 
-use card::Card;
+use card::choice::{ChoiceResult, Distractor};
+use card::typed::{AcceptedAnswer, TypedResult};
+use card::{BASIC_ITEM, Card, CardKind};
 use db::DBApiError;
-use deck::{Deck, DeckDetails};
-use game::CardScore;
+use deck::{Deck, DeckDetails, DeckMember, DeckPage, DeckRole, DeckVisibility, MAX_DECKS_PER_PAGE};
+use game::{CardScore, Review, SchedulerKind, StudyItem};
+use media::{CardMedia, MediaError, MediaSide};
+use study::StudySession;
+use transfer::TransferError;
+use transfer::anki::AnkiImportReport;
+use transfer::bundle::DeckBundle;
+use transfer::delimited::{DelimitedImportReport, DelimitedOptions};
 
-use std::sync::{Arc, Mutex};
-use std::time::SystemTimeError;
+use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::time::{SystemTime, SystemTimeError, UNIX_EPOCH};
 
 #[derive(Debug)]
 pub enum FlashError {
@@ -29,11 +43,22 @@ pub enum FlashError {
     OtherError,
     SystemTimeError,
     SessionTimeout,
+    TransferError(TransferError),
+    MediaError(MediaError),
 }
 
-pub struct FlashManager {
-    pub db_manager: db::DBManager,
-    pub id_factory: Arc<Mutex<webe_id::WebeIDFactory>>,
+// 'S' is the storage backend, see db::FlashStore.
+// e.g. db::memory::MemoryStore for tests, or Box<dyn db::FlashStore> to choose at runtime
+// FlashManager is Send + Sync for any store that is, share it between threads as Arc<FlashManager>
+// Create one with FlashManager::new and change settings through the setters, or configure
+// everything at once through FlashManager::builder
+pub struct FlashManager<S = db::DBManager> {
+    pub db_manager: S,
+    pub id_generator: id::IdGenerator,
+    new_cards_per_queue: u16, // max new cards mixed into each study queue
+    typing: config::TypingConfig, // how typed answers are checked
+    media_store: Box<dyn media::MediaStore>, // the bytes of card attachments
+    max_media_bytes: u32, // largest attachment accepted
 }
 
 impl From<SystemTimeError> for FlashError {
@@ -42,22 +67,125 @@ impl From<SystemTimeError> for FlashError {
     }
 }
 
+// current unix timestamp in seconds, matching the 'last_updated' columns
+pub(crate) fn unix_now() -> Result<u32, SystemTimeError> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?;
+    return Ok(now.as_secs() as u32);
+}
+
+impl<S> FlashManager<S> {
+    // a FlashManager with the default settings, keeping attachments in config::DEFAULT_MEDIA_DIR
+    pub fn new(db_manager: S, id_generator: id::IdGenerator) -> FlashManager<S> {
+        FlashManager {
+            db_manager: db_manager,
+            id_generator: id_generator,
+            new_cards_per_queue: game::queue::DEFAULT_NEW_CARDS_PER_QUEUE,
+            typing: config::TypingConfig::default(),
+            media_store: Box::new(media::local::LocalMediaStore::new(config::DEFAULT_MEDIA_DIR)),
+            max_media_bytes: config::DEFAULT_MAX_MEDIA_BYTES,
+        }
+    }
+
+    pub fn new_cards_per_queue(&self) -> u16 {
+        self.new_cards_per_queue
+    }
+
+    pub fn set_new_cards_per_queue(&mut self, new_cards: u16) {
+        self.new_cards_per_queue = new_cards;
+    }
+
+    pub fn typing(&self) -> &config::TypingConfig {
+        &self.typing
+    }
+
+    pub fn set_typing(&mut self, typing: config::TypingConfig) {
+        self.typing = typing;
+    }
+
+    pub fn media_store(&self) -> &dyn media::MediaStore {
+        self.media_store.as_ref()
+    }
+
+    pub fn set_media_store(&mut self, media_store: Box<dyn media::MediaStore>) {
+        self.media_store = media_store;
+    }
+
+    pub fn max_media_bytes(&self) -> u32 {
+        self.max_media_bytes
+    }
+
+    pub fn set_max_media_bytes(&mut self, max_bytes: u32) {
+        self.max_media_bytes = max_bytes;
+    }
+}
+
 impl FlashManager {
+    // configure a FlashManager, see config.rs
+    pub fn builder(database_url: String) -> config::FlashManagerBuilder {
+        config::FlashManagerBuilder::new(database_url)
+    }
+}
+
+// PermissionError unless 'role' is at least the 'required' role
+fn check_role(role: Option<DeckRole>, required: DeckRole) -> Result<DeckRole, FlashError> {
+    match role {
+        Some(role) if role >= required => return Ok(role),
+        _ => return Err(FlashError::PermissionError),
+    }
+}
+
+impl<S: db::FlashStore> FlashManager<S> {
     pub fn new_id(&self) -> Result<u64, FlashError> {
-        match self.id_factory.lock() {
-            Ok(mut factory) => match factory.next() {
-                Ok(id) => return Ok(id),
-                _ => return Err(FlashError::OtherError),
-            },
-            // TODO: find a way to make the lock not poisonable
-            _ => return Err(FlashError::OtherError), // mutex is poisoned
+        match self.id_generator.next() {
+            Ok(id) => return Ok(id),
+            Err(_err) => return Err(FlashError::OtherError),
+        }
+    }
+
+    // the deck, if the session's account has at least the 'required' role on it
+    fn find_deck_for(
+        &self,
+        session: &Session,
+        deck_id: u64,
+        required: DeckRole,
+    ) -> Result<Deck, FlashError> {
+        let (deck, role) =
+            db::DeckApi::find_for_account(&self.db_manager, deck_id, session.account_id)?;
+        check_role(role, required)?;
+        return Ok(deck);
+    }
+
+    // the card and its deck, if the session's account has at least the 'required' role on the deck
+    fn find_card_for(
+        &self,
+        session: &Session,
+        card_id: u64,
+        required: DeckRole,
+    ) -> Result<(Card, Deck), FlashError> {
+        let (card, deck, role) =
+            db::CardApi::find_for_account(&self.db_manager, card_id, session.account_id)?;
+        check_role(role, required)?;
+        return Ok((card, deck));
+    }
+
+    // an account-scoped write matched nothing. tell a missing card apart from a card
+    // the account isn't allowed to change, without slowing down the successful path
+    fn card_write_error(&self, session: &Session, card_id: u64, err: DBApiError) -> FlashError {
+        match err {
+            DBApiError::NotFound => {
+                match db::CardApi::find_for_account(&self.db_manager, card_id, session.account_id) {
+                    Ok(_found) => return FlashError::PermissionError,
+                    Err(err) => return FlashError::DBError(err),
+                }
+            }
+            _ => return FlashError::DBError(err),
         }
     }
 
     // get deck info
     pub fn get_deck_info(&self, session: &Session, deck_id: &u64) -> Result<Deck, FlashError> {
         if !session.is_expired() {
-            let deck = db::DeckApi::find(&self.db_manager, deck_id)?;
+            let deck = self.find_deck_for(session, *deck_id, DeckRole::Viewer)?;
             return Ok(deck);
         } else {
             return Err(FlashError::SessionTimeout);
@@ -87,11 +215,7 @@ impl FlashManager {
         // TODO: validate name isn't empty
         if !session.is_expired() {
             // find the existing deck in the db
-            // TODO: include session.account_id as a filter in the db query
-            let existing = db::DeckApi::find(&self.db_manager, &deck_id)?;
-            if session.account_id != existing.owner_id {
-                return Err(FlashError::PermissionError);
-            }
+            let existing = self.find_deck_for(session, deck_id, DeckRole::Owner)?;
             // provide db the modified object
             let mut updated = existing;
             updated.rename(name);
@@ -102,15 +226,211 @@ impl FlashManager {
         }
     }
 
+    // choose the review algorithm used when scoring cards in this deck
+    pub fn set_deck_scheduler(
+        &self,
+        session: &Session,
+        deck_id: u64,
+        scheduler: SchedulerKind,
+    ) -> Result<(), FlashError> {
+        if !session.is_expired() {
+            let existing = self.find_deck_for(session, deck_id, DeckRole::Owner)?;
+            let mut updated = existing;
+            updated.set_scheduler(scheduler);
+            db::DeckApi::update(&self.db_manager, &updated).map_err(|e| FlashError::DBError(e))
+        } else {
+            return Err(FlashError::SessionTimeout);
+        }
+    }
+
+    // choose who can find and view the deck without being a member
+    pub fn set_deck_visibility(
+        &self,
+        session: &Session,
+        deck_id: u64,
+        visibility: DeckVisibility,
+    ) -> Result<(), FlashError> {
+        if !session.is_expired() {
+            let existing = self.find_deck_for(session, deck_id, DeckRole::Owner)?;
+            let mut updated = existing;
+            updated.set_visibility(visibility);
+            db::DeckApi::update(&self.db_manager, &updated).map_err(|e| FlashError::DBError(e))
+        } else {
+            return Err(FlashError::SessionTimeout);
+        }
+    }
+
+    // page through the public deck catalog, 'page' starts at 0
+    // 'search' only matches decks whose name contains it
+    pub fn browse_public_decks(
+        &self,
+        session: &Session,
+        search: Option<&str>,
+        page: u32,
+        per_page: u16,
+    ) -> Result<DeckPage, FlashError> {
+        if !session.is_expired() {
+            let per_page = per_page.max(1).min(MAX_DECKS_PER_PAGE);
+            let search = search.map(|text| text.trim()).filter(|text| !text.is_empty());
+            // fetch one extra deck to find out if there is another page
+            let mut decks = db::DeckApi::find_public_decks(
+                &self.db_manager,
+                search,
+                page as i64 * per_page as i64,
+                per_page as i64 + 1,
+            )?;
+            let has_more = decks.len() > per_page as usize;
+            decks.truncate(per_page as usize);
+            return Ok(DeckPage {
+                decks: decks,
+                page: page,
+                has_more: has_more,
+            });
+        } else {
+            return Err(FlashError::SessionTimeout);
+        }
+    }
+
+    // copy a deck the session can view, and all of its cards, into the session's library
+    // the copy starts out private and keeps the original's scheduler and card order
+    pub fn clone_deck(&self, session: &Session, deck_id: u64) -> Result<Deck, FlashError> {
+        let details = self.get_deck_details(session, &deck_id)?;
+        let mut deck = Deck::new(
+            self.new_id()?,
+            session.account_id,
+            details.info.name().to_owned(),
+        )?;
+        deck.set_scheduler(details.info.scheduler());
+        let mut cards = Vec::with_capacity(details.cards.len());
+        let mut copy_ids = HashMap::with_capacity(details.cards.len());
+        for card in &details.cards {
+            let mut copy = Card::new(
+                self.new_id()?,
+                deck.id,
+                card.deck_pos,
+                card.question().to_owned(),
+                card.answer().to_owned(),
+            )?;
+            copy.set_kind(card.kind());
+            copy.set_reversible(card.is_reversible());
+            copy_ids.insert(card.id, copy.id);
+            cards.push(copy);
+        }
+        let distractors: Vec<Distractor> = details
+            .distractors
+            .iter()
+            .filter_map(|distractor| {
+                let card_id = copy_ids.get(&distractor.card_id)?;
+                Some(Distractor::new(*card_id, distractor.position, distractor.text.clone()))
+            })
+            .collect();
+        let accepted_answers: Vec<AcceptedAnswer> = details
+            .accepted_answers
+            .iter()
+            .filter_map(|answer| {
+                let card_id = copy_ids.get(&answer.card_id)?;
+                Some(AcceptedAnswer::new(*card_id, answer.position, answer.text.clone()))
+            })
+            .collect();
+        // (original, copy) of every attachment
+        let mut media_copies = Vec::with_capacity(details.media.len());
+        for card_media in &details.media {
+            if let Some(card_id) = copy_ids.get(&card_media.card_id) {
+                let copy = CardMedia::new(
+                    self.new_id()?,
+                    *card_id,
+                    card_media.side(),
+                    &card_media.content_type,
+                    card_media.byte_size,
+                )?;
+                media_copies.push((card_media.id, copy));
+            }
+        }
+        let mut records = db::DeckRecords::new(deck, cards);
+        records.distractors = distractors;
+        records.accepted_answers = accepted_answers;
+        records.media = media_copies.iter().map(|(_id, copy)| copy.clone()).collect();
+        db::DeckApi::insert_records(&self.db_manager, std::slice::from_ref(&records))?;
+        // the records go first, see media. an attachment whose bytes can't be copied
+        // is left off the copy rather than failing the whole clone
+        for (original_id, copy) in &media_copies {
+            let copied = self
+                .media_store
+                .get(*original_id)
+                .and_then(|bytes| self.media_store.put(copy.id, &bytes));
+            if copied.is_err() {
+                let _ = db::MediaApi::delete(&self.db_manager, copy.id);
+            }
+        }
+        return Ok(records.deck);
+    }
+
     // delete deck
     pub fn delete_deck(&self, session: &Session, deck_id: u64) -> Result<(), FlashError> {
         if !session.is_expired() {
-            // TODO: include session.account_id as a filter in the db query
-            let existing = db::DeckApi::find(&self.db_manager, &deck_id)?;
-            if session.account_id != existing.owner_id {
-                return Err(FlashError::PermissionError);
+            self.find_deck_for(session, deck_id, DeckRole::Owner)?;
+            // the records cascade away with the deck, so look them up first
+            let media = db::MediaApi::find_for_deck(&self.db_manager, deck_id)?;
+            // still checks the role, in case it was taken away since
+            db::DeckApi::delete_for_owner(&self.db_manager, deck_id, session.account_id)?;
+            self.remove_media_files(&media);
+            return Ok(());
+        } else {
+            return Err(FlashError::SessionTimeout);
+        }
+    }
+
+    // give another account a role on the deck, or change the role they already have
+    pub fn share_deck(
+        &self,
+        session: &Session,
+        deck_id: u64,
+        account_id: u64,
+        role: DeckRole,
+    ) -> Result<(), FlashError> {
+        if !session.is_expired() {
+            let deck = self.find_deck_for(session, deck_id, DeckRole::Owner)?;
+            // the creator is always an owner
+            if account_id == deck.owner_id {
+                return Err(FlashError::DBError(DBApiError::NotAllowed));
+            }
+            let member = DeckMember::new(deck_id, account_id, role);
+            db::MemberApi::upsert(&self.db_manager, &member).map_err(|e| FlashError::DBError(e))
+        } else {
+            return Err(FlashError::SessionTimeout);
+        }
+    }
+
+    // remove an account's access to the deck. members may always remove themselves.
+    pub fn unshare_deck(
+        &self,
+        session: &Session,
+        deck_id: u64,
+        account_id: u64,
+    ) -> Result<(), FlashError> {
+        if !session.is_expired() {
+            let (_deck, role) =
+                db::DeckApi::find_for_account(&self.db_manager, deck_id, session.account_id)?;
+            if account_id != session.account_id {
+                check_role(role, DeckRole::Owner)?;
             }
-            db::DeckApi::delete(&self.db_manager, &deck_id).map_err(|e| FlashError::DBError(e))
+            db::MemberApi::delete(&self.db_manager, deck_id, account_id)
+                .map_err(|e| FlashError::DBError(e))
+        } else {
+            return Err(FlashError::SessionTimeout);
+        }
+    }
+
+    // accounts the deck has been shared with, not including the creator
+    pub fn list_members(
+        &self,
+        session: &Session,
+        deck_id: u64,
+    ) -> Result<Vec<DeckMember>, FlashError> {
+        if !session.is_expired() {
+            self.find_deck_for(session, deck_id, DeckRole::Owner)?;
+            return db::MemberApi::find_members_for_deck(&self.db_manager, deck_id)
+                .map_err(|e| FlashError::DBError(e));
         } else {
             return Err(FlashError::SessionTimeout);
         }
@@ -126,17 +446,59 @@ impl FlashManager {
         answer: String,
     ) -> Result<Card, FlashError> {
         if !session.is_expired() {
-            // TODO: like most things, checking valid session, checking deck owner, etc
-            // - can be done entirely in database with one call instead of many api calls
-            let deck = db::DeckApi::find(&self.db_manager, &deck_id)?;
-            if deck.owner_id == session.account_id {
-                let id = self.new_id()?;
-                let card = Card::new(id, deck_id, deck_pos, question, answer)?;
-                db::CardApi::insert(&self.db_manager, &card)?;
-                return Ok(card);
-            } else {
-                return Err(FlashError::PermissionError);
+            self.find_deck_for(session, deck_id, DeckRole::Editor)?;
+            let id = self.new_id()?;
+            let card = Card::new(id, deck_id, deck_pos, question, answer)?;
+            db::CardApi::insert(&self.db_manager, &card)?;
+            return Ok(card);
+        } else {
+            return Err(FlashError::SessionTimeout);
+        }
+    }
+
+    // create a card that is studied both question -> answer and answer -> question
+    // each direction has its own score, see Card::set_reversible
+    pub fn create_reversible_card(
+        &self,
+        session: &Session,
+        deck_id: u64,
+        deck_pos: u16,
+        question: String,
+        answer: String,
+    ) -> Result<Card, FlashError> {
+        if !session.is_expired() {
+            self.find_deck_for(session, deck_id, DeckRole::Editor)?;
+            let id = self.new_id()?;
+            let mut card = Card::new(id, deck_id, deck_pos, question, answer)?;
+            card.set_reversible(true);
+            db::CardApi::insert(&self.db_manager, &card)?;
+            return Ok(card);
+        } else {
+            return Err(FlashError::SessionTimeout);
+        }
+    }
+
+    // create a cloze card, e.g. "The capital of {{c1::France}} is {{c2::Paris}}"
+    // 'extra' holds optional notes to show with the answers. see card::cloze
+    pub fn create_cloze_card(
+        &self,
+        session: &Session,
+        deck_id: u64,
+        deck_pos: u16,
+        text: String,
+        extra: String,
+    ) -> Result<Card, FlashError> {
+        if !session.is_expired() {
+            // a cloze without deletions could never be studied
+            if card::cloze::numbers(&text).is_empty() {
+                return Err(FlashError::DBError(DBApiError::NotAllowed));
             }
+            self.find_deck_for(session, deck_id, DeckRole::Editor)?;
+            let id = self.new_id()?;
+            let mut card = Card::new(id, deck_id, deck_pos, text, extra)?;
+            card.set_kind(CardKind::Cloze);
+            db::CardApi::insert(&self.db_manager, &card)?;
+            return Ok(card);
         } else {
             return Err(FlashError::SessionTimeout);
         }
@@ -145,14 +507,7 @@ impl FlashManager {
     // fetch card
     pub fn get_card(&self, session: &Session, card_id: u64) -> Result<Card, FlashError> {
         if !session.is_expired() {
-            // find the existing card in the db
-            let existing = db::CardApi::find(&self.db_manager, &card_id)?;
-            // verify owner. TODO: can these two be made into one database call?
-            let deck = db::DeckApi::find(&self.db_manager, &existing.deck_id)?;
-            if deck.owner_id != session.account_id {
-                return Err(FlashError::PermissionError);
-            }
-            let card = db::CardApi::find(&self.db_manager, &card_id)?;
+            let (card, _deck) = self.find_card_for(session, card_id, DeckRole::Viewer)?;
             return Ok(card);
         } else {
             return Err(FlashError::SessionTimeout);
@@ -160,31 +515,51 @@ impl FlashManager {
     }
 
     // update card
+    // both directions of a reversible card share the question and answer, so they always match.
+    // turning 'reversible' off keeps the reverse direction's scores in case it's turned back on
     pub fn update_card(
         &self,
         session: &Session,
         card_id: u64,
         question: Option<String>,
         answer: Option<String>,
+        reversible: Option<bool>,
     ) -> Result<(), FlashError> {
         if !session.is_expired() {
-            // find the existing card in the db
-            let existing = db::CardApi::find(&self.db_manager, &card_id)?;
-            // verify owner. TODO: can these two be made into one database call?
-            let deck = db::DeckApi::find(&self.db_manager, &existing.deck_id)?;
-            if deck.owner_id != session.account_id {
-                return Err(FlashError::PermissionError);
+            // nothing to change, but the card must still exist and be editable
+            if question.is_none() && answer.is_none() && reversible.is_none() {
+                self.find_card_for(session, card_id, DeckRole::Editor)?;
+                return Ok(());
             }
-            // provide db the modified object
-            let mut updated = existing;
-            if let Some(question) = question {
-                updated.update_question(question);
+            // cloze text must keep a deletion to study, and only basic cards have a reverse.
+            // multiple choice ones don't either, it would show their answer as its question
+            if question.is_some() || reversible == Some(true) {
+                let (card, _deck) = self.find_card_for(session, card_id, DeckRole::Editor)?;
+                let no_deletions = match &question {
+                    Some(text) => card::cloze::numbers(text).is_empty(),
+                    None => false,
+                };
+                if card.kind() == CardKind::Cloze && no_deletions {
+                    return Err(FlashError::DBError(DBApiError::NotAllowed));
+                }
+                if card.kind() != CardKind::Basic && reversible == Some(true) {
+                    return Err(FlashError::DBError(DBApiError::NotAllowed));
+                }
+                if reversible == Some(true)
+                    && !db::DistractorApi::find_for_card(&self.db_manager, card_id)?.is_empty()
+                {
+                    return Err(FlashError::DBError(DBApiError::NotAllowed));
+                }
             }
-            if let Some(answer) = answer {
-                updated.update_answer(answer);
-            }
-            // TODO: should this function be split into update_pos , update_question etc?
-            db::CardApi::update(&self.db_manager, &updated).map_err(|e| FlashError::DBError(e))
+            db::CardApi::update_for_editor(
+                &self.db_manager,
+                card_id,
+                session.account_id,
+                question.as_deref(),
+                answer.as_deref(),
+                reversible,
+            )
+            .map_err(|e| self.card_write_error(session, card_id, e))
         } else {
             return Err(FlashError::SessionTimeout);
         }
@@ -199,11 +574,7 @@ impl FlashManager {
         new_pos: u16,
     ) -> Result<(), FlashError> {
         if !session.is_expired() {
-            // verify owner. TODO: can these two be made into one database call?
-            let deck = db::DeckApi::find(&self.db_manager, &deck_id)?;
-            if deck.owner_id != session.account_id {
-                return Err(FlashError::PermissionError);
-            }
+            self.find_deck_for(session, deck_id, DeckRole::Editor)?;
             db::CardApi::update_position(&self.db_manager, card_id, deck_id, orig_pos, new_pos)
                 .map_err(|e| FlashError::DBError(e))
         } else {
@@ -214,43 +585,230 @@ impl FlashManager {
     // delete card
     pub fn delete_card(&self, session: &Session, card_id: u64) -> Result<(), FlashError> {
         if !session.is_expired() {
-            // find the existing card in the db
-            let existing = db::CardApi::find(&self.db_manager, &card_id)?;
-            // verify owner. TODO: can these two be made into one database call?
-            let deck = db::DeckApi::find(&self.db_manager, &existing.deck_id)?;
-            if deck.owner_id != session.account_id {
-                return Err(FlashError::PermissionError);
+            self.find_card_for(session, card_id, DeckRole::Editor)?;
+            // the records cascade away with the card, so look them up first
+            let media = db::MediaApi::find_for_card(&self.db_manager, card_id)?;
+            db::CardApi::delete_for_editor(&self.db_manager, card_id, session.account_id)
+                .map_err(|e| self.card_write_error(session, card_id, e))?;
+            self.remove_media_files(&media);
+            return Ok(());
+        } else {
+            return Err(FlashError::SessionTimeout);
+        }
+    }
+
+    // the wrong options of a multiple choice card
+    pub fn get_card_distractors(
+        &self,
+        session: &Session,
+        card_id: u64,
+    ) -> Result<Vec<Distractor>, FlashError> {
+        if !session.is_expired() {
+            self.find_card_for(session, card_id, DeckRole::Viewer)?;
+            return db::DistractorApi::find_for_card(&self.db_manager, card_id)
+                .map_err(|e| FlashError::DBError(e));
+        } else {
+            return Err(FlashError::SessionTimeout);
+        }
+    }
+
+    // make a basic card multiple choice by giving it wrong options to pick from,
+    // replacing any it already had. no distractors makes it a regular card again.
+    // reversible cards can't be, their reverse would give the answer away.
+    // see card::choice::from_texts for what is allowed
+    pub fn set_card_distractors(
+        &self,
+        session: &Session,
+        card_id: u64,
+        distractors: Vec<String>,
+    ) -> Result<Vec<Distractor>, FlashError> {
+        if !session.is_expired() {
+            let (card, _deck) = self.find_card_for(session, card_id, DeckRole::Editor)?;
+            let basic = card.kind() == CardKind::Basic && !card.is_reversible();
+            if !basic && !distractors.is_empty() {
+                return Err(FlashError::DBError(DBApiError::NotAllowed));
             }
-            db::CardApi::delete(&self.db_manager, &card_id).map_err(|e| FlashError::DBError(e))
+            let distractors = match card::choice::from_texts(card_id, card.answer(), distractors) {
+                Some(distractors) => distractors,
+                None => return Err(FlashError::DBError(DBApiError::NotAllowed)),
+            };
+            db::DistractorApi::replace_for_card(&self.db_manager, card_id, &distractors)?;
+            return Ok(distractors);
         } else {
             return Err(FlashError::SessionTimeout);
         }
     }
 
-    pub fn get_decks_for_session(&self, session: &Session) -> Result<Vec<Deck>, FlashError> {
+    // the answers a typed answer is accepted as besides the card's own
+    pub fn get_card_answers(
+        &self,
+        session: &Session,
+        card_id: u64,
+    ) -> Result<Vec<AcceptedAnswer>, FlashError> {
+        if !session.is_expired() {
+            self.find_card_for(session, card_id, DeckRole::Viewer)?;
+            return db::AnswerApi::find_for_card(&self.db_manager, card_id)
+                .map_err(|e| FlashError::DBError(e));
+        } else {
+            return Err(FlashError::SessionTimeout);
+        }
+    }
+
+    // set the other answers a basic card accepts when typed, replacing any it already had.
+    // see card::typed::from_texts for what is allowed
+    pub fn set_card_answers(
+        &self,
+        session: &Session,
+        card_id: u64,
+        answers: Vec<String>,
+    ) -> Result<Vec<AcceptedAnswer>, FlashError> {
+        if !session.is_expired() {
+            let (card, _deck) = self.find_card_for(session, card_id, DeckRole::Editor)?;
+            if card.kind() != CardKind::Basic && !answers.is_empty() {
+                return Err(FlashError::DBError(DBApiError::NotAllowed));
+            }
+            let answers = match card::typed::from_texts(card_id, card.answer(), answers) {
+                Some(answers) => answers,
+                None => return Err(FlashError::DBError(DBApiError::NotAllowed)),
+            };
+            db::AnswerApi::replace_for_card(&self.db_manager, card_id, &answers)?;
+            return Ok(answers);
+        } else {
+            return Err(FlashError::SessionTimeout);
+        }
+    }
+
+    // the images and audio attached to a card, oldest first
+    pub fn get_card_media(
+        &self,
+        session: &Session,
+        card_id: u64,
+    ) -> Result<Vec<CardMedia>, FlashError> {
         if !session.is_expired() {
-            return db::DeckApi::find_decks_for_owner(&self.db_manager, &session.account_id)
+            self.find_card_for(session, card_id, DeckRole::Viewer)?;
+            return db::MediaApi::find_for_card(&self.db_manager, card_id)
                 .map_err(|e| FlashError::DBError(e));
         } else {
             return Err(FlashError::SessionTimeout);
         }
     }
 
+    // attach an image or audio clip to one side of a card.
+    // see media::check_upload for which content types and sizes are accepted
+    pub fn add_card_media(
+        &self,
+        session: &Session,
+        card_id: u64,
+        side: MediaSide,
+        content_type: &str,
+        bytes: &[u8],
+    ) -> Result<CardMedia, FlashError> {
+        if !session.is_expired() {
+            self.find_card_for(session, card_id, DeckRole::Editor)?;
+            let content_type = media::check_upload(content_type, bytes, self.max_media_bytes)?;
+            let card_media = CardMedia::new(
+                self.new_id()?,
+                card_id,
+                side,
+                content_type,
+                bytes.len() as u32,
+            )?;
+            // the record goes first, see media
+            db::MediaApi::insert_limited(&self.db_manager, &card_media, media::MAX_MEDIA_PER_CARD)?;
+            if let Err(err) = self.media_store.put(card_media.id, bytes) {
+                let _ = db::MediaApi::delete(&self.db_manager, card_media.id);
+                return Err(FlashError::MediaError(err));
+            }
+            return Ok(card_media);
+        } else {
+            return Err(FlashError::SessionTimeout);
+        }
+    }
+
+    // an attachment and its bytes, for accounts that can view the card
+    pub fn get_media(
+        &self,
+        session: &Session,
+        media_id: u64,
+    ) -> Result<(CardMedia, Vec<u8>), FlashError> {
+        if !session.is_expired() {
+            let card_media = db::MediaApi::find(&self.db_manager, media_id)?;
+            self.find_card_for(session, card_media.card_id, DeckRole::Viewer)?;
+            let bytes = self.media_store.get(media_id)?;
+            return Ok((card_media, bytes));
+        } else {
+            return Err(FlashError::SessionTimeout);
+        }
+    }
+
+    pub fn remove_card_media(&self, session: &Session, media_id: u64) -> Result<(), FlashError> {
+        if !session.is_expired() {
+            let card_media = db::MediaApi::find(&self.db_manager, media_id)?;
+            self.find_card_for(session, card_media.card_id, DeckRole::Editor)?;
+            db::MediaApi::delete(&self.db_manager, media_id)?;
+            self.remove_media_files(&[card_media]);
+            return Ok(());
+        } else {
+            return Err(FlashError::SessionTimeout);
+        }
+    }
+
+    // the records are already gone, so bytes that fail to be removed here are orphans
+    // and clean_orphaned_media takes care of them later
+    fn remove_media_files(&self, media: &[CardMedia]) {
+        for card_media in media {
+            let _ = self.media_store.remove(card_media.id);
+        }
+    }
+
+    // remove stored bytes that no longer have a record, e.g. when removing them failed
+    // after their card was deleted. safe to run at any time, returns how many were removed
+    pub fn clean_orphaned_media(&self) -> Result<usize, FlashError> {
+        let stored = self.media_store.ids()?;
+        let existing: HashSet<u64> = db::MediaApi::find_existing(&self.db_manager, &stored)?
+            .into_iter()
+            .collect();
+        let mut removed = 0;
+        for media_id in stored {
+            if !existing.contains(&media_id) {
+                self.media_store.remove(media_id)?;
+                removed += 1;
+            }
+        }
+        return Ok(removed);
+    }
+
+    pub fn get_decks_for_session(&self, session: &Session) -> Result<Vec<Deck>, FlashError> {
+        if !session.is_expired() {
+            let mut decks =
+                db::DeckApi::find_decks_for_owner(&self.db_manager, &session.account_id)?;
+            decks.append(&mut db::DeckApi::find_decks_for_member(
+                &self.db_manager,
+                &session.account_id,
+            )?);
+            return Ok(decks);
+        } else {
+            return Err(FlashError::SessionTimeout);
+        }
+    }
+
     pub fn get_deck_details(
         &self,
         session: &Session,
         deck_id: &u64,
     ) -> Result<DeckDetails, FlashError> {
         if !session.is_expired() {
-            let deck_info = self.get_deck_info(session, deck_id)?;
-            // verify owner. TODO: can these two be made into one database call?
-            if deck_info.owner_id != session.account_id {
-                return Err(FlashError::PermissionError);
-            }
-            let cards = self.get_cards_for_deck(session, deck_id)?; // TODO: turn into single sql statement using join
+            let deck_info = self.find_deck_for(session, *deck_id, DeckRole::Viewer)?;
+            let cards = db::CardApi::find_cards_for_deck(&self.db_manager, deck_id)?;
+            let distractors = db::DistractorApi::find_for_deck(&self.db_manager, *deck_id)?;
+            let accepted_answers = db::AnswerApi::find_for_deck(&self.db_manager, *deck_id)?;
+            let media = db::MediaApi::find_for_deck(&self.db_manager, *deck_id)?;
             return Ok(DeckDetails {
                 info: deck_info,
                 cards: cards,
+                distractors: distractors,
+                accepted_answers: accepted_answers,
+                media: media,
             });
         } else {
             return Err(FlashError::SessionTimeout);
@@ -263,11 +821,7 @@ impl FlashManager {
         deck_id: &u64,
     ) -> Result<Vec<Card>, FlashError> {
         if !session.is_expired() {
-            // verify owner. TODO: can these two be made into one database call?
-            let deck = db::DeckApi::find(&self.db_manager, &deck_id)?;
-            if deck.owner_id != session.account_id {
-                return Err(FlashError::PermissionError);
-            }
+            self.find_deck_for(session, *deck_id, DeckRole::Viewer)?;
             return db::CardApi::find_cards_for_deck(&self.db_manager, deck_id)
                 .map_err(|e| FlashError::DBError(e));
         } else {
@@ -275,20 +829,192 @@ impl FlashManager {
         }
     }
 
+    // grade a basic card 0-5 and reschedule it for this account
+    // every call is also appended to the account's review history.
+    // multiple choice cards can't be graded this way, see answer_choice
     pub fn update_score(
         &self,
         session: &Session,
         card_id: u64,
         score: u8,
-    ) -> Result<(), FlashError> {
+        response_ms: Option<u32>,
+    ) -> Result<CardScore, FlashError> {
+        self.update_item_score(session, card_id, BASIC_ITEM, score, response_ms)
+    }
+
+    // grade one item of a card 0-5, e.g. a single cloze deletion. see Card::items
+    // each item is scheduled on its own
+    pub fn update_item_score(
+        &self,
+        session: &Session,
+        card_id: u64,
+        item: u16,
+        score: u8,
+        response_ms: Option<u32>,
+    ) -> Result<CardScore, FlashError> {
+        self.grade_item(session, None, card_id, item, score, response_ms, false)
+    }
+
+    // grade one item of a card, counting the answer against the study session if there is one.
+    // the session counts and the score are stored together, see db::StudyApi::record_answer.
+    // 'from_choice' is set when answer_choice worked out the score from the picked option
+    #[allow(clippy::too_many_arguments)]
+    fn grade_item(
+        &self,
+        session: &Session,
+        study_id: Option<u64>,
+        card_id: u64,
+        item: u16,
+        score: u8,
+        response_ms: Option<u32>,
+        from_choice: bool,
+    ) -> Result<CardScore, FlashError> {
         if !session.is_expired() {
-            let card_score = CardScore::new(session.account_id, card_id, score);
-            let card = db::CardApi::find(&self.db_manager, &card_id)?;
-            let deck = db::DeckApi::find(&self.db_manager, &card.deck_id)?;
-            if deck.owner_id != session.account_id {
-                return Err(FlashError::PermissionError);
+            let (card, deck) = self.find_card_for(session, card_id, DeckRole::Viewer)?;
+            if !card.items().contains(&item) {
+                return Err(FlashError::DBError(DBApiError::NotAllowed));
+            }
+            // multiple choice items are only graded from the picked option, see answer_choice
+            if !from_choice && card.kind() == CardKind::Basic && item == BASIC_ITEM {
+                let distractors = db::DistractorApi::find_for_card(&self.db_manager, card_id)?;
+                if !distractors.is_empty() {
+                    return Err(FlashError::DBError(DBApiError::NotAllowed));
+                }
             }
-            return db::GameApi::update_score(&self.db_manager, card_score)
+            let mut card_score = match db::GameApi::find_score(
+                &self.db_manager,
+                session.account_id,
+                card_id,
+                item,
+            ) {
+                Ok(existing) => existing,
+                Err(DBApiError::NotFound) => CardScore::for_item(session.account_id, card_id, item),
+                Err(err) => return Err(FlashError::DBError(err)),
+            };
+            let state_before = card_score.state();
+            card_score.review(deck.scheduler().scheduler(), score, unix_now()?);
+            let review = Review::new(
+                self.new_id()?,
+                &card_score,
+                deck.scheduler(),
+                &state_before,
+                response_ms,
+            )
+            .map_err(|_e| FlashError::OtherError)?;
+            match study_id {
+                Some(study_id) => {
+                    let correct = card_score.score >= game::scheduler::PASSING_GRADE;
+                    match db::StudyApi::record_answer(
+                        &self.db_manager,
+                        study_id,
+                        correct,
+                        &card_score,
+                        &review,
+                    ) {
+                        Ok(()) => {}
+                        // the session was finished after it was checked
+                        Err(DBApiError::NotFound) => {
+                            return Err(FlashError::DBError(DBApiError::NotAllowed))
+                        }
+                        Err(err) => return Err(FlashError::DBError(err)),
+                    }
+                }
+                None => db::GameApi::update_score(&self.db_manager, &card_score, &review)?,
+            }
+            return Ok(card_score);
+        } else {
+            return Err(FlashError::SessionTimeout);
+        }
+    }
+
+    // grade a multiple choice card from the option the account picked, one of the
+    // card's answer or distractors. the grade is decided here instead of by the client,
+    // see card::choice::CORRECT_GRADE
+    pub fn answer_choice(
+        &self,
+        session: &Session,
+        card_id: u64,
+        choice: &str,
+        response_ms: Option<u32>,
+    ) -> Result<ChoiceResult, FlashError> {
+        if !session.is_expired() {
+            let (card, _deck) = self.find_card_for(session, card_id, DeckRole::Viewer)?;
+            let distractors = db::DistractorApi::find_for_card(&self.db_manager, card_id)?;
+            if card.kind() != CardKind::Basic || distractors.is_empty() {
+                return Err(FlashError::DBError(DBApiError::NotAllowed));
+            }
+            let correct = match card::choice::grade(card.answer(), &distractors, choice) {
+                Some(correct) => correct,
+                None => return Err(FlashError::DBError(DBApiError::NotAllowed)),
+            };
+            let grade = if correct {
+                card::choice::CORRECT_GRADE
+            } else {
+                card::choice::INCORRECT_GRADE
+            };
+            let card_score =
+                self.grade_item(session, None, card_id, BASIC_ITEM, grade, response_ms, true)?;
+            return Ok(ChoiceResult {
+                correct: correct,
+                answer: card.answer().to_owned(),
+                score: card_score,
+            });
+        } else {
+            return Err(FlashError::SessionTimeout);
+        }
+    }
+
+    // grade an item of a basic card from the answer the account typed, checked against
+    // the item's answer and, going from question to answer, the card's accepted answers.
+    // see card::typed for how it's graded, and self.typing for how lenient it is
+    pub fn answer_typed(
+        &self,
+        session: &Session,
+        card_id: u64,
+        item: u16,
+        typed: &str,
+        response_ms: Option<u32>,
+    ) -> Result<TypedResult, FlashError> {
+        if !session.is_expired() {
+            if typed.chars().count() > card::typed::MAX_TYPED_CHARS {
+                return Err(FlashError::DBError(DBApiError::NotAllowed));
+            }
+            let (card, _deck) = self.find_card_for(session, card_id, DeckRole::Viewer)?;
+            if card.kind() != CardKind::Basic {
+                return Err(FlashError::DBError(DBApiError::NotAllowed));
+            }
+            let (_question, answer) = match card.item_text(item) {
+                Some(text) => text,
+                None => return Err(FlashError::DBError(DBApiError::NotAllowed)),
+            };
+            let others = if item == BASIC_ITEM {
+                db::AnswerApi::find_for_card(&self.db_manager, card_id)?
+            } else {
+                Vec::new()
+            };
+            let mut accepted = vec![answer.as_str()];
+            accepted.extend(others.iter().map(|other| other.text.as_str()));
+            let check = card::typed::check(typed, &accepted, &self.typing);
+            let card_score =
+                self.update_item_score(session, card_id, item, check.grade, response_ms)?;
+            return Ok(TypedResult {
+                check: check,
+                score: card_score,
+            });
+        } else {
+            return Err(FlashError::SessionTimeout);
+        }
+    }
+
+    // every answer this account has given for cards in the deck, oldest first
+    pub fn get_review_history(
+        &self,
+        session: &Session,
+        deck_id: u64,
+    ) -> Result<Vec<Review>, FlashError> {
+        if !session.is_expired() {
+            self.find_deck_for(session, deck_id, DeckRole::Viewer)?;
+            return db::GameApi::get_deck_reviews(&self.db_manager, deck_id, session.account_id)
                 .map_err(|e| FlashError::DBError(e));
         } else {
             return Err(FlashError::SessionTimeout);
@@ -301,14 +1027,272 @@ impl FlashManager {
         deck_id: u64,
     ) -> Result<Vec<CardScore>, FlashError> {
         if !session.is_expired() {
-            let deck = db::DeckApi::find(&self.db_manager, &deck_id)?;
-            if deck.owner_id != session.account_id {
-                return Err(FlashError::PermissionError);
-            }
+            self.find_deck_for(session, deck_id, DeckRole::Viewer)?;
             return db::GameApi::get_deck_scores(&self.db_manager, deck_id, session.account_id)
                 .map_err(|e| FlashError::DBError(e));
         } else {
             return Err(FlashError::SessionTimeout);
         }
     }
+
+    // scores for cards in the deck that are due for review now, most overdue first
+    pub fn get_due_scores(
+        &self,
+        session: &Session,
+        deck_id: u64,
+    ) -> Result<Vec<CardScore>, FlashError> {
+        if !session.is_expired() {
+            self.find_deck_for(session, deck_id, DeckRole::Viewer)?;
+            return db::GameApi::get_due_scores(
+                &self.db_manager,
+                deck_id,
+                session.account_id,
+                unix_now()?,
+            )
+            .map_err(|e| FlashError::DBError(e));
+        } else {
+            return Err(FlashError::SessionTimeout);
+        }
+    }
+
+    // the next cards this account should study in the deck, at most 'limit' of them
+    pub fn get_study_queue(
+        &self,
+        session: &Session,
+        deck_id: u64,
+        limit: u16,
+    ) -> Result<Vec<StudyItem>, FlashError> {
+        if !session.is_expired() {
+            self.find_deck_for(session, deck_id, DeckRole::Viewer)?;
+            let candidates = db::GameApi::get_study_candidates(
+                &self.db_manager,
+                deck_id,
+                session.account_id,
+                unix_now()?,
+            )?;
+            let mut queue =
+                game::queue::build_study_queue(candidates, limit, self.new_cards_per_queue);
+            if !queue.is_empty() {
+                let distractors = db::DistractorApi::find_for_deck(&self.db_manager, deck_id)?;
+                // shuffle the options differently every time the queue is fetched
+                let seed = RandomState::new().hash_one(session.account_id);
+                game::queue::add_choice_options(&mut queue, &distractors, seed);
+            }
+            return Ok(queue);
+        } else {
+            return Err(FlashError::SessionTimeout);
+        }
+    }
+
+    // start studying a deck, or resume the account's unfinished session for it
+    pub fn start_study_session(
+        &self,
+        session: &Session,
+        deck_id: u64,
+    ) -> Result<StudySession, FlashError> {
+        if !session.is_expired() {
+            self.find_deck_for(session, deck_id, DeckRole::Viewer)?;
+            match db::StudyApi::find_open(&self.db_manager, session.account_id, deck_id) {
+                Ok(existing) => return Ok(existing),
+                Err(DBApiError::NotFound) => {}
+                Err(err) => return Err(FlashError::DBError(err)),
+            }
+            let study_session =
+                StudySession::new(self.new_id()?, deck_id, session.account_id, unix_now()?);
+            db::StudyApi::insert(&self.db_manager, &study_session)?;
+            return Ok(study_session);
+        } else {
+            return Err(FlashError::SessionTimeout);
+        }
+    }
+
+    pub fn get_study_session(
+        &self,
+        session: &Session,
+        study_id: u64,
+    ) -> Result<StudySession, FlashError> {
+        if !session.is_expired() {
+            let study_session = db::StudyApi::find(&self.db_manager, &study_id)?;
+            if study_session.account_id != session.account_id {
+                return Err(FlashError::PermissionError);
+            }
+            return Ok(study_session);
+        } else {
+            return Err(FlashError::SessionTimeout);
+        }
+    }
+
+    // score a basic card as part of an open study session
+    pub fn answer_study_card(
+        &self,
+        session: &Session,
+        study_id: u64,
+        card_id: u64,
+        score: u8,
+        response_ms: Option<u32>,
+    ) -> Result<CardScore, FlashError> {
+        self.answer_study_item(session, study_id, card_id, BASIC_ITEM, score, response_ms)
+    }
+
+    // score one item of a card as part of an open study session
+    pub fn answer_study_item(
+        &self,
+        session: &Session,
+        study_id: u64,
+        card_id: u64,
+        item: u16,
+        score: u8,
+        response_ms: Option<u32>,
+    ) -> Result<CardScore, FlashError> {
+        let study_session = self.get_study_session(session, study_id)?;
+        if !study_session.is_open() {
+            return Err(FlashError::DBError(DBApiError::NotAllowed));
+        }
+        let card = db::CardApi::find(&self.db_manager, &card_id)?;
+        if card.deck_id != study_session.deck_id {
+            return Err(FlashError::DBError(DBApiError::NotAllowed));
+        }
+        return self.grade_item(session, Some(study_id), card_id, item, score, response_ms, false);
+    }
+
+    // close the study session and return its summary
+    pub fn finish_study_session(
+        &self,
+        session: &Session,
+        study_id: u64,
+    ) -> Result<StudySession, FlashError> {
+        let study_session = self.get_study_session(session, study_id)?;
+        if !study_session.is_open() {
+            return Ok(study_session);
+        }
+        db::StudyApi::finish(&self.db_manager, study_id, unix_now()?)?;
+        // read it back, answers recorded while finishing are part of the summary
+        return self.get_study_session(session, study_id);
+    }
+
+    // create a deck for every Anki deck in the .apkg package, with its notes as cards
+    pub fn import_anki(
+        &self,
+        session: &Session,
+        apkg: &[u8],
+    ) -> Result<AnkiImportReport, FlashError> {
+        if !session.is_expired() {
+            let package =
+                transfer::anki::read_package(apkg, transfer::anki::MAX_COLLECTION_BYTES)?;
+            let mut report = AnkiImportReport {
+                decks: Vec::new(),
+                cards_imported: 0,
+                skipped: package.skipped,
+            };
+            let mut records = Vec::with_capacity(package.decks.len());
+            for anki_deck in package.decks {
+                let deck = Deck::new(self.new_id()?, session.account_id, anki_deck.name)?;
+                let mut cards = Vec::with_capacity(anki_deck.cards.len());
+                for (index, anki_card) in anki_deck.cards.into_iter().enumerate() {
+                    cards.push(Card::new(
+                        self.new_id()?,
+                        deck.id,
+                        (index + 1) as u16, // position 0 is reserved
+                        anki_card.question,
+                        anki_card.answer,
+                    )?);
+                }
+                records.push(db::DeckRecords::new(deck, cards));
+            }
+            // every deck is imported, or none are
+            db::DeckApi::insert_records(&self.db_manager, &records)?;
+            for deck_records in records {
+                report.cards_imported += deck_records.cards.len();
+                report.decks.push(deck_records.deck);
+            }
+            return Ok(report);
+        } else {
+            return Err(FlashError::SessionTimeout);
+        }
+    }
+
+    // write the deck's cards as CSV/TSV, in deck order
+    pub fn export_deck_csv(
+        &self,
+        session: &Session,
+        deck_id: u64,
+        options: &DelimitedOptions,
+    ) -> Result<String, FlashError> {
+        let details = self.get_deck_details(session, &deck_id)?;
+        return Ok(transfer::delimited::write_deck(&details, options)?);
+    }
+
+    // create a new deck from CSV/TSV rows
+    // either every row is imported or, if any row is bad, none are and the errors are reported
+    pub fn import_deck_csv(
+        &self,
+        session: &Session,
+        name: String,
+        data: &str,
+        options: &DelimitedOptions,
+    ) -> Result<DelimitedImportReport, FlashError> {
+        if !session.is_expired() {
+            let (rows, errors) = transfer::delimited::read_rows(data, options)?;
+            if !errors.is_empty() {
+                return Ok(DelimitedImportReport {
+                    deck: None,
+                    cards_imported: 0,
+                    errors: errors,
+                });
+            }
+            let deck = Deck::new(self.new_id()?, session.account_id, name)?;
+            let mut cards = Vec::with_capacity(rows.len());
+            for (index, row) in rows.into_iter().enumerate() {
+                cards.push(Card::new(
+                    self.new_id()?,
+                    deck.id,
+                    (index + 1) as u16, // position 0 is reserved
+                    row.question,
+                    row.answer,
+                )?);
+            }
+            db::DeckApi::insert_with_cards(&self.db_manager, &deck, &cards)?;
+            return Ok(DelimitedImportReport {
+                deck: Some(deck),
+                cards_imported: cards.len(),
+                errors: errors,
+            });
+        } else {
+            return Err(FlashError::SessionTimeout);
+        }
+    }
+
+    // package the deck as a portable bundle
+    // 'include_history' adds this account's scores and reviews for the deck
+    pub fn export_bundle(
+        &self,
+        session: &Session,
+        deck_id: u64,
+        include_history: bool,
+    ) -> Result<DeckBundle, FlashError> {
+        let details = self.get_deck_details(session, &deck_id)?;
+        if include_history {
+            let scores =
+                db::GameApi::get_deck_scores(&self.db_manager, deck_id, session.account_id)?;
+            let reviews =
+                db::GameApi::get_deck_reviews(&self.db_manager, deck_id, session.account_id)?;
+            return Ok(DeckBundle::new(
+                &details,
+                Some((scores.as_slice(), reviews.as_slice())),
+            )?);
+        }
+        return Ok(DeckBundle::new(&details, None)?);
+    }
+
+    // re-create a bundled deck under this account, with fresh ids
+    pub fn import_bundle(&self, session: &Session, bundle: &DeckBundle) -> Result<Deck, FlashError> {
+        if !session.is_expired() {
+            let records = bundle.records(session.account_id, || self.new_id())?;
+            // the deck is imported whole, or not at all
+            db::DeckApi::insert_records(&self.db_manager, std::slice::from_ref(&records))?;
+            return Ok(records.deck);
+        } else {
+            return Err(FlashError::SessionTimeout);
+        }
+    }
 }
@@ -0,0 +1,68 @@
+// Keeps attachments as files in one directory, named by their id.
+// New files are written under a temporary name and renamed into place, so a partly written
+// upload is never served. The directory is created on the first upload.
+
+use super::{MediaError, MediaStore};
+
+use std::fs;
+use std::path::PathBuf;
+
+const PARTIAL_EXTENSION: &str = "partial";
+
+pub struct LocalMediaStore {
+    dir: PathBuf,
+}
+
+impl LocalMediaStore {
+    pub fn new<P: Into<PathBuf>>(dir: P) -> LocalMediaStore {
+        LocalMediaStore { dir: dir.into() }
+    }
+
+    fn path(&self, media_id: u64) -> PathBuf {
+        self.dir.join(media_id.to_string())
+    }
+}
+
+impl MediaStore for LocalMediaStore {
+    fn put(&self, media_id: u64, bytes: &[u8]) -> Result<(), MediaError> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.path(media_id);
+        let partial_path = path.with_extension(PARTIAL_EXTENSION);
+        fs::write(&partial_path, bytes)?;
+        if let Err(err) = fs::rename(&partial_path, &path) {
+            let _ = fs::remove_file(&partial_path);
+            return Err(MediaError::IOError(err));
+        }
+        return Ok(());
+    }
+
+    fn get(&self, media_id: u64) -> Result<Vec<u8>, MediaError> {
+        return Ok(fs::read(self.path(media_id))?);
+    }
+
+    fn remove(&self, media_id: u64) -> Result<(), MediaError> {
+        match fs::remove_file(self.path(media_id)) {
+            Ok(()) => return Ok(()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(MediaError::IOError(err)),
+        }
+    }
+
+    // files that aren't named by an id, e.g. partial uploads, aren't listed
+    fn ids(&self) -> Result<Vec<u64>, MediaError> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            // nothing uploaded yet
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(MediaError::IOError(err)),
+        };
+        let mut ids = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            if let Some(Ok(media_id)) = entry.file_name().to_str().map(str::parse::<u64>) {
+                ids.push(media_id);
+            }
+        }
+        return Ok(ids);
+    }
+}
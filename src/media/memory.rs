@@ -0,0 +1,48 @@
+// Keeps attachments in memory, for tests and prototyping alongside db::memory::MemoryStore.
+
+use super::{MediaError, MediaStore};
+
+use std::collections::BTreeMap;
+use std::sync::{Mutex, MutexGuard};
+
+#[derive(Default)]
+pub struct MemoryMediaStore {
+    files: Mutex<BTreeMap<u64, Vec<u8>>>,
+}
+
+impl MemoryMediaStore {
+    pub fn new() -> MemoryMediaStore {
+        MemoryMediaStore::default()
+    }
+
+    fn files(&self) -> MutexGuard<'_, BTreeMap<u64, Vec<u8>>> {
+        // every operation is a single insert or remove, so the map is never left half changed
+        match self.files.lock() {
+            Ok(files) => files,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+impl MediaStore for MemoryMediaStore {
+    fn put(&self, media_id: u64, bytes: &[u8]) -> Result<(), MediaError> {
+        self.files().insert(media_id, bytes.to_vec());
+        return Ok(());
+    }
+
+    fn get(&self, media_id: u64) -> Result<Vec<u8>, MediaError> {
+        match self.files().get(&media_id) {
+            Some(bytes) => return Ok(bytes.clone()),
+            None => return Err(MediaError::NotFound),
+        }
+    }
+
+    fn remove(&self, media_id: u64) -> Result<(), MediaError> {
+        self.files().remove(&media_id);
+        return Ok(());
+    }
+
+    fn ids(&self) -> Result<Vec<u64>, MediaError> {
+        return Ok(self.files().keys().cloned().collect());
+    }
+}
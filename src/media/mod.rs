@@ -0,0 +1,203 @@
+// Images and audio attached to the question or answer side of a card.
+// The 'card_media' table records which card each attachment belongs to, and the bytes are
+// kept apart from the database in a MediaStore under the attachment's id.
+// Records are written before the bytes and removed before them, so stored bytes without a
+// record are always safe to remove, see FlashManager::clean_orphaned_media.
+//
+// Uploads are checked against the declared content type: the bytes have to start the way
+// that type does, so nothing else can be served back under an image or audio type.
+
+pub mod local;
+pub mod memory;
+
+use crate::schema::card_media;
+
+use serde::{Deserialize, Serialize, Serializer};
+
+use std::time::SystemTimeError;
+
+// most attachments a card can have, over both sides
+pub const MAX_MEDIA_PER_CARD: usize = 10;
+
+#[derive(Debug)]
+pub enum MediaError {
+    NotFound,        // nothing is stored under the id
+    TooLarge,        // bigger than the configured 'max_bytes'
+    UnsupportedType, // not one of CONTENT_TYPES, or the bytes don't match the declared type
+    IOError(std::io::Error),
+}
+
+impl From<std::io::Error> for MediaError {
+    fn from(err: std::io::Error) -> MediaError {
+        match err.kind() {
+            std::io::ErrorKind::NotFound => MediaError::NotFound,
+            _ => MediaError::IOError(err),
+        }
+    }
+}
+
+impl From<MediaError> for crate::FlashError {
+    fn from(err: MediaError) -> crate::FlashError {
+        crate::FlashError::MediaError(err)
+    }
+}
+
+// the bytes of attachments, by attachment id. see local::LocalMediaStore
+pub trait MediaStore: Send + Sync {
+    fn put(&self, media_id: u64, bytes: &[u8]) -> Result<(), MediaError>;
+
+    // NotFound if nothing is stored under the id
+    fn get(&self, media_id: u64) -> Result<Vec<u8>, MediaError>;
+
+    // removing an id that isn't stored isn't an error
+    fn remove(&self, media_id: u64) -> Result<(), MediaError>;
+
+    // every stored id, in no particular order
+    fn ids(&self) -> Result<Vec<u64>, MediaError>;
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaSide {
+    Question = 0,
+    Answer = 1,
+}
+
+impl MediaSide {
+    pub fn from_id(id: u8) -> Option<MediaSide> {
+        match id {
+            0 => Some(MediaSide::Question),
+            1 => Some(MediaSide::Answer),
+            _ => None,
+        }
+    }
+
+    pub fn id(&self) -> u8 {
+        *self as u8
+    }
+
+    // "question" or "answer", e.g. from a request path
+    pub fn from_name(name: &str) -> Option<MediaSide> {
+        match name {
+            "question" => Some(MediaSide::Question),
+            "answer" => Some(MediaSide::Answer),
+            _ => None,
+        }
+    }
+}
+
+fn serialize_side_id<S>(id: &u8, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match MediaSide::from_id(*id) {
+        Some(side) => side.serialize(serializer),
+        None => serializer.serialize_u8(*id),
+    }
+}
+
+#[derive(Serialize, Insertable, Queryable, Clone, PartialEq, Debug)]
+#[table_name = "card_media"]
+pub struct CardMedia {
+    #[serde(serialize_with = "webe_auth::utility::serialize_as_string")]
+    pub id: u64, // also the id the bytes are stored under
+    #[serde(serialize_with = "webe_auth::utility::serialize_as_string")]
+    pub card_id: u64,
+    #[serde(serialize_with = "serialize_side_id")]
+    pub(crate) side: u8,
+    pub content_type: String, // one of CONTENT_TYPES
+    pub byte_size: u32,
+    pub uploaded: u32,
+}
+
+impl CardMedia {
+    pub fn new(
+        id: u64,
+        card_id: u64,
+        side: MediaSide,
+        content_type: &str,
+        byte_size: u32,
+    ) -> Result<CardMedia, SystemTimeError> {
+        return Ok(CardMedia {
+            id: id,
+            card_id: card_id,
+            side: side.id(),
+            content_type: content_type.to_owned(),
+            byte_size: byte_size,
+            uploaded: crate::unix_now()?,
+        });
+    }
+
+    // attachments with an unrecognized side id are shown with the question
+    pub fn side(&self) -> MediaSide {
+        MediaSide::from_id(self.side).unwrap_or(MediaSide::Question)
+    }
+}
+
+// the content types attachments can have
+pub const CONTENT_TYPES: [&str; 7] = [
+    "image/png",
+    "image/jpeg",
+    "image/gif",
+    "image/webp",
+    "audio/mpeg",
+    "audio/ogg",
+    "audio/wav",
+];
+
+// the content type the bytes start like, if it's one of CONTENT_TYPES
+pub fn sniff_content_type(bytes: &[u8]) -> Option<&'static str> {
+    // WebP and WAV are both RIFF files, told apart by the form type after the size
+    let riff_kind = if bytes.len() >= 12 && bytes.starts_with(b"RIFF") {
+        Some(&bytes[8..12])
+    } else {
+        None
+    };
+    // an ID3 tag, or straight into an MPEG audio frame
+    let mpeg =
+        bytes.starts_with(b"ID3") || (bytes.len() >= 2 && bytes[0] == 0xFF && bytes[1] >= 0xE0);
+    if bytes.starts_with(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]) {
+        return Some("image/png");
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        return Some("image/jpeg");
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        return Some("image/gif");
+    } else if riff_kind == Some(&b"WEBP"[..]) {
+        return Some("image/webp");
+    } else if riff_kind == Some(&b"WAVE"[..]) {
+        return Some("audio/wav");
+    } else if bytes.starts_with(b"OggS") {
+        return Some("audio/ogg");
+    } else if mpeg {
+        return Some("audio/mpeg");
+    }
+    return None;
+}
+
+// the content type to store an upload under. the declared type may carry parameters and
+// a few common aliases, e.g. "image/jpg" or "audio/mp3", and has to match the bytes
+pub fn check_upload(
+    declared_type: &str,
+    bytes: &[u8],
+    max_bytes: u32,
+) -> Result<&'static str, MediaError> {
+    if bytes.len() > max_bytes as usize {
+        return Err(MediaError::TooLarge);
+    }
+    let declared = declared_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase();
+    let declared = match declared.as_str() {
+        "image/jpg" => "image/jpeg",
+        "audio/mp3" => "audio/mpeg",
+        "audio/x-wav" | "audio/wave" => "audio/wav",
+        other => other,
+    };
+    match sniff_content_type(bytes) {
+        Some(content_type) if content_type == declared => return Ok(content_type),
+        _ => return Err(MediaError::UnsupportedType),
+    }
+}
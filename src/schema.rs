@@ -18,10 +18,23 @@ table! {
         question -> Varchar,
         answer -> Varchar,
         last_updated -> Unsigned<Integer>,
+        kind -> Unsigned<Tinyint>,
+        reversible -> Bool,
     }
 }
 
 // VIEWS TO HELP KEEP POSITIONS IN ORDER, since you can't 'order by' during a sql update
+table! {
+    card_media (id) {
+        id -> Unsigned<Bigint>,
+        card_id -> Unsigned<Bigint>,
+        side -> Unsigned<Tinyint>,
+        content_type -> Varchar,
+        byte_size -> Unsigned<Integer>,
+        uploaded -> Unsigned<Integer>,
+    }
+}
+
 table! {
     card_pos_asc (id) {
         id -> Unsigned<Bigint>,
@@ -38,10 +51,34 @@ table! {
 }
 
 table! {
-    cardscores (account_id, card_id) {
+    card_answers (card_id, position) {
+        card_id -> Unsigned<Bigint>,
+        position -> Unsigned<Smallint>,
+        text -> Varchar,
+    }
+}
+
+table! {
+    card_distractors (card_id, position) {
+        card_id -> Unsigned<Bigint>,
+        position -> Unsigned<Smallint>,
+        text -> Varchar,
+    }
+}
+
+table! {
+    cardscores (account_id, card_id, item) {
         account_id -> Unsigned<Bigint>,
         card_id -> Unsigned<Bigint>,
+        item -> Unsigned<Smallint>,
         score -> Unsigned<Tinyint>,
+        ease_factor -> Float,
+        interval -> Unsigned<Integer>,
+        repetitions -> Unsigned<Smallint>,
+        due -> Unsigned<Integer>,
+        last_reviewed -> Unsigned<Integer>,
+        stability -> Float,
+        difficulty -> Float,
     }
 }
 
@@ -75,12 +112,22 @@ table! {
     }
 }
 
+table! {
+    deck_members (deck_id, account_id) {
+        deck_id -> Unsigned<Bigint>,
+        account_id -> Unsigned<Bigint>,
+        role -> Unsigned<Tinyint>,
+    }
+}
+
 table! {
     decks (id) {
         id -> Unsigned<Bigint>,
         name -> Varchar,
         owner_id -> Unsigned<Bigint>,
         last_updated -> Unsigned<Integer>,
+        scheduler -> Unsigned<Tinyint>,
+        visibility -> Unsigned<Tinyint>,
     }
 }
 
@@ -169,6 +216,21 @@ table! {
     }
 }
 
+table! {
+    reviews (id) {
+        id -> Unsigned<Bigint>,
+        account_id -> Unsigned<Bigint>,
+        card_id -> Unsigned<Bigint>,
+        item -> Unsigned<Smallint>,
+        grade -> Unsigned<Tinyint>,
+        answered_at -> Unsigned<Integer>,
+        response_ms -> Nullable<Unsigned<Integer>>,
+        scheduler -> Unsigned<Tinyint>,
+        state_before -> Text,
+        state_after -> Text,
+    }
+}
+
 table! {
     self_referential_fk (id) {
         id -> Integer,
@@ -191,6 +253,18 @@ table! {
     }
 }
 
+table! {
+    study_sessions (id) {
+        id -> Unsigned<Bigint>,
+        deck_id -> Unsigned<Bigint>,
+        account_id -> Unsigned<Bigint>,
+        started -> Unsigned<Integer>,
+        ended -> Nullable<Unsigned<Integer>>,
+        cards_seen -> Unsigned<Smallint>,
+        correct_count -> Unsigned<Smallint>,
+    }
+}
+
 table! {
     trees (id) {
         id -> Integer,
@@ -238,15 +312,31 @@ table! {
     }
 }
 
+joinable!(card_answers -> cards (card_id));
+joinable!(card_distractors -> cards (card_id));
+joinable!(card_media -> cards (card_id));
 joinable!(cards -> decks (deck_id));
 joinable!(cardscores -> cards (card_id));
 joinable!(comments -> posts (post_id));
 joinable!(cyclic_fk_1 -> cyclic_fk_2 (cyclic_fk_2_id));
+joinable!(deck_members -> decks (deck_id));
 joinable!(fk_tests -> fk_inits (fk_id));
 joinable!(followings -> posts (post_id));
 joinable!(followings -> users (user_id));
 joinable!(likes -> comments (comment_id));
 joinable!(likes -> users (user_id));
 joinable!(posts -> users (user_id));
-
-allow_tables_to_appear_in_same_query!(cards, cardscores, decks,);
+joinable!(reviews -> cards (card_id));
+joinable!(study_sessions -> decks (deck_id));
+
+allow_tables_to_appear_in_same_query!(
+    card_answers,
+    card_distractors,
+    card_media,
+    cards,
+    cardscores,
+    deck_members,
+    decks,
+    reviews,
+    study_sessions,
+);
@@ -0,0 +1,46 @@
+use crate::schema::study_sessions;
+
+use serde::Serialize;
+
+// A single sitting of studying one deck.
+// Answers submitted through the session are scored as usual and counted here
+// so the client can show a summary, or resume the session if interrupted.
+#[derive(Serialize, AsChangeset, Identifiable, Insertable, Queryable, Clone, Debug)]
+#[table_name = "study_sessions"]
+#[changeset_options(treat_none_as_null = "true")]
+pub struct StudySession {
+    #[serde(serialize_with = "webe_auth::utility::serialize_as_string")]
+    pub id: u64,
+    #[serde(serialize_with = "webe_auth::utility::serialize_as_string")]
+    pub deck_id: u64,
+    #[serde(serialize_with = "webe_auth::utility::serialize_as_string")]
+    pub account_id: u64,
+    pub started: u32,
+    pub ended: Option<u32>, // None while the session is still open
+    pub cards_seen: u16,
+    pub correct_count: u16,
+}
+
+impl StudySession {
+    pub fn new(id: u64, deck_id: u64, account_id: u64, started: u32) -> StudySession {
+        StudySession {
+            id: id,
+            deck_id: deck_id,
+            account_id: account_id,
+            started: started,
+            ended: None,
+            cards_seen: 0,
+            correct_count: 0,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.ended.is_none()
+    }
+
+    pub fn finish(&mut self, now: u32) {
+        if self.ended.is_none() {
+            self.ended = Some(now);
+        }
+    }
+}
@@ -0,0 +1,294 @@
+// Reads Anki .apkg packages: a zip archive holding a SQLite collection.
+// Each note becomes a question/answer pair in the deck its first card belongs to.
+// see https://github.com/ankitects/anki for the collection schema
+use crate::deck::Deck;
+use crate::transfer::TransferError;
+
+use serde::Serialize;
+
+use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::fs::OpenOptions;
+use std::hash::BuildHasher;
+use std::io::{Cursor, Read, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// newest readable collection first
+const COLLECTION_NAMES: [&str; 2] = ["collection.anki21", "collection.anki2"];
+// zstd compressed collection written by Anki 2.1.50+, only readable by Anki itself
+const COMPRESSED_COLLECTION: &str = "collection.anki21b";
+const FIELD_SEPARATOR: char = '\u{1f}';
+const CLOZE_MODEL_TYPE: i64 = 1;
+const MAX_DECK_CARDS: usize = u16::MAX as usize; // deck_pos is a u16
+const SCRATCH_ATTEMPTS: usize = 8; // names to try before giving up on a scratch file
+
+// largest .apkg accepted for import
+pub const MAX_PACKAGE_BYTES: u64 = 100 * 1024 * 1024;
+// largest collection read out of a package. it's compressed in the archive,
+// so a small package could otherwise unpack to gigabytes
+pub const MAX_COLLECTION_BYTES: u64 = 200 * 1024 * 1024;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    UnsupportedNoteType, // e.g. cloze notes
+    MissingFields,       // fewer than two fields to use as question and answer
+    EmptyQuestion,
+    DeckFull, // deck already holds the maximum number of cards
+    MissingCard, // the note has no first card (ord 0) to decide its deck
+}
+
+#[derive(Serialize, Debug)]
+pub struct SkippedNote {
+    pub note_id: i64,
+    pub note_type: String,
+    pub reason: SkipReason,
+}
+
+pub struct AnkiCard {
+    pub note_id: i64,
+    pub question: String,
+    pub answer: String,
+}
+
+pub struct AnkiDeck {
+    pub name: String,
+    pub cards: Vec<AnkiCard>, // in note creation order
+}
+
+pub struct AnkiPackage {
+    pub decks: Vec<AnkiDeck>, // only decks that hold at least one card
+    pub skipped: Vec<SkippedNote>,
+}
+
+// result of FlashManager::import_anki
+#[derive(Serialize)]
+pub struct AnkiImportReport {
+    pub decks: Vec<Deck>,
+    pub cards_imported: usize,
+    pub skipped: Vec<SkippedNote>,
+}
+
+// TooLarge if the collection unpacks to more than 'max_collection_bytes'
+pub fn read_package(apkg: &[u8], max_collection_bytes: u64) -> Result<AnkiPackage, TransferError> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(apkg))?;
+    let mut collection = Vec::<u8>::new();
+    for name in COLLECTION_NAMES.iter() {
+        // packages with a compressed collection also ship a placeholder 'collection.anki2'
+        if *name == "collection.anki2" && archive.by_name(COMPRESSED_COLLECTION).is_ok() {
+            return Err(TransferError::UnsupportedCollection);
+        }
+        match archive.by_name(name) {
+            Ok(file) => {
+                if file.size() > max_collection_bytes {
+                    return Err(TransferError::TooLarge);
+                }
+                // the size in the archive can't be trusted, so read one byte past the limit
+                file.take(max_collection_bytes + 1).read_to_end(&mut collection)?;
+                if collection.len() as u64 > max_collection_bytes {
+                    return Err(TransferError::TooLarge);
+                }
+                break;
+            }
+            Err(zip::result::ZipError::FileNotFound) => {}
+            Err(err) => return Err(TransferError::from(err)),
+        }
+    }
+    if collection.is_empty() {
+        return Err(TransferError::MissingCollection);
+    }
+
+    // SQLite can only open collections from disk
+    let scratch = ScratchFile::new(&collection)?;
+    let conn = rusqlite::Connection::open_with_flags(
+        &scratch.path,
+        rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY,
+    )?;
+    return read_collection(&conn);
+}
+
+fn read_collection(conn: &rusqlite::Connection) -> Result<AnkiPackage, TransferError> {
+    let (decks_json, models_json): (String, String) =
+        conn.query_row("SELECT decks, models FROM col", [], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?;
+    let deck_names = parse_json_map(&decks_json)?;
+    let models = parse_json_map(&models_json)?;
+
+    let mut package = AnkiPackage {
+        decks: Vec::new(),
+        skipped: Vec::new(),
+    };
+    let mut deck_index = HashMap::<i64, usize>::new();
+
+    // the first card (ord 0) of every note decides which deck the note lands in
+    let mut statement = conn.prepare(
+        "SELECT n.id, n.mid, n.flds, c.did FROM notes n \
+         LEFT JOIN cards c ON c.nid = n.id AND c.ord = 0 \
+         ORDER BY n.id",
+    )?;
+    let rows = statement.query_map([], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, i64>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, Option<i64>>(3)?,
+        ))
+    })?;
+    for row in rows {
+        let (note_id, model_id, fields, deck_id) = row?;
+        let (note_type, model_type) = match models.get(&model_id) {
+            Some(model) => (
+                model["name"].as_str().unwrap_or("").to_owned(),
+                model["type"].as_i64().unwrap_or(0),
+            ),
+            None => (String::new(), 0),
+        };
+        let skip = |reason: SkipReason| SkippedNote {
+            note_id: note_id,
+            note_type: note_type.clone(),
+            reason: reason,
+        };
+        let deck_id = match deck_id {
+            Some(deck_id) => deck_id,
+            None => {
+                package.skipped.push(skip(SkipReason::MissingCard));
+                continue;
+            }
+        };
+        if model_type == CLOZE_MODEL_TYPE {
+            package.skipped.push(skip(SkipReason::UnsupportedNoteType));
+            continue;
+        }
+        let fields: Vec<&str> = fields.split(FIELD_SEPARATOR).collect();
+        if fields.len() < 2 {
+            package.skipped.push(skip(SkipReason::MissingFields));
+            continue;
+        }
+        let question = clean_field(fields[0]);
+        if question.is_empty() {
+            package.skipped.push(skip(SkipReason::EmptyQuestion));
+            continue;
+        }
+        let index = *deck_index.entry(deck_id).or_insert_with(|| {
+            let name = match deck_names.get(&deck_id) {
+                Some(deck) => deck["name"].as_str().unwrap_or("Anki").to_owned(),
+                None => "Anki".to_owned(),
+            };
+            package.decks.push(AnkiDeck {
+                name: name,
+                cards: Vec::new(),
+            });
+            package.decks.len() - 1
+        });
+        let deck = &mut package.decks[index];
+        if deck.cards.len() >= MAX_DECK_CARDS {
+            package.skipped.push(skip(SkipReason::DeckFull));
+            continue;
+        }
+        deck.cards.push(AnkiCard {
+            note_id: note_id,
+            question: question,
+            answer: clean_field(fields[1]),
+        });
+    }
+    return Ok(package);
+}
+
+// 'col.decks' and 'col.models' are JSON objects keyed by stringified id
+fn parse_json_map(json: &str) -> Result<HashMap<i64, serde_json::Value>, TransferError> {
+    let mut map = HashMap::new();
+    let value: serde_json::Value = serde_json::from_str(json)?;
+    if let serde_json::Value::Object(entries) = value {
+        for (key, entry) in entries {
+            if let Ok(id) = key.parse::<i64>() {
+                map.insert(id, entry);
+            }
+        }
+    }
+    return Ok(map);
+}
+
+// Anki fields are HTML. Keep the text and line breaks, drop markup and media references.
+fn clean_field(field: &str) -> String {
+    let mut text = String::with_capacity(field.len());
+    let mut in_tag = false;
+    let mut tag = String::new();
+    for c in field.chars() {
+        if in_tag {
+            if c == '>' {
+                in_tag = false;
+                // closing tags have an empty name here and are ignored
+                let name = tag
+                    .split(|c: char| c.is_whitespace() || c == '/')
+                    .next()
+                    .unwrap_or("")
+                    .to_lowercase();
+                if (name == "br" || name == "div" || name == "p") && !text.is_empty() {
+                    text.push('\n');
+                }
+            } else {
+                tag.push(c);
+            }
+        } else if c == '<' {
+            in_tag = true;
+            tag.clear();
+        } else {
+            text.push(c);
+        }
+    }
+    while let Some(start) = text.find("[sound:") {
+        match text[start..].find(']') {
+            Some(len) => text.replace_range(start..start + len + 1, ""),
+            None => break,
+        }
+    }
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+    return text.trim().to_owned();
+}
+
+// temporary copy of the collection, removed when dropped
+struct ScratchFile {
+    path: PathBuf,
+}
+
+static SCRATCH_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+impl ScratchFile {
+    // the name is unpredictable and the file must not already exist,
+    // so another user of the temp dir can't swap in their own file or a link
+    fn new(contents: &[u8]) -> Result<ScratchFile, std::io::Error> {
+        for _attempt in 0..SCRATCH_ATTEMPTS {
+            let random = RandomState::new()
+                .hash_one((std::process::id(), SCRATCH_COUNT.fetch_add(1, Ordering::Relaxed)));
+            let path = std::env::temp_dir().join(format!("lib_flashcard_{:016x}.anki2", random));
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    // removed on drop, even if the write fails
+                    let scratch = ScratchFile { path: path };
+                    file.write_all(contents)?;
+                    return Ok(scratch);
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            "no free scratch file name",
+        ));
+    }
+}
+
+impl Drop for ScratchFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
@@ -0,0 +1,321 @@
+// Portable JSON deck bundle, for backups and moving decks between installations.
+//
+// {
+//   "format_version": 5,
+//   "deck": { "name": "Spanish", "scheduler": "sm2" },
+//   "cards": [ { "question": "hablar", "answer": "to speak", "kind": "basic",          // in order
+//                "reversible": true, "distractors": [ "to eat", ... ],
+//                "accepted_answers": [ "speak", ... ] }, ... ],
+//   "scores": [ { "card": 0, "item": 0, "score": 4, "state": { ... } } ],              // optional
+//   "reviews": [ { "card": 0, "item": 0, "grade": 4, "answered_at": 1600000000,        // optional
+//                  "response_ms": 1200, "scheduler": "sm2",
+//                  "state_before": { ... }, "state_after": { ... } } ]
+// }
+//
+// Bundles never contain database ids or account ids. Scores and reviews refer to
+// cards by their index in "cards" and are imported for the importing account.
+// Card media isn't bundled, the attachments stay in the installation's MediaStore.
+// Readers must reject bundles with a newer format_version than they understand.
+// Version 2 added card kinds and per item scores, version 1 bundles only hold basic cards.
+// Version 3 added reversible cards, version 4 the distractors of multiple choice cards,
+// and version 5 the accepted answers of typed answers.
+use crate::card::choice;
+use crate::card::cloze;
+use crate::card::typed;
+use crate::card::{BASIC_ITEM, Card, CardKind, REVERSE_ITEM};
+use crate::db::DeckRecords;
+use crate::deck::{Deck, DeckDetails};
+use crate::game::{CardScore, Review, ReviewState, SchedulerKind};
+use crate::transfer::TransferError;
+
+use serde::{Deserialize, Serialize};
+
+use std::collections::{HashMap, HashSet};
+
+pub const BUNDLE_FORMAT_VERSION: u32 = 5;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DeckBundle {
+    pub format_version: u32,
+    pub deck: BundleDeck,
+    pub cards: Vec<BundleCard>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scores: Option<Vec<BundleScore>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reviews: Option<Vec<BundleReview>>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BundleDeck {
+    pub name: String,
+    pub scheduler: SchedulerKind,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BundleCard {
+    pub question: String,
+    pub answer: String,
+    #[serde(default)]
+    pub kind: CardKind,
+    #[serde(default)]
+    pub reversible: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub distractors: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub accepted_answers: Vec<String>,
+}
+
+impl BundleCard {
+    // the items the imported card will have, see Card::items
+    fn items(&self) -> Vec<u16> {
+        match self.kind {
+            CardKind::Basic if self.reversible => vec![BASIC_ITEM, REVERSE_ITEM],
+            CardKind::Basic => vec![BASIC_ITEM],
+            CardKind::Cloze => cloze::numbers(&self.question),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BundleScore {
+    pub card: usize, // index into DeckBundle.cards
+    #[serde(default)]
+    pub item: u16,
+    pub score: u8,
+    pub state: ReviewState,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BundleReview {
+    pub card: usize, // index into DeckBundle.cards
+    #[serde(default)]
+    pub item: u16,
+    pub grade: u8,
+    pub answered_at: u32,
+    pub response_ms: Option<u32>,
+    pub scheduler: SchedulerKind,
+    pub state_before: ReviewState,
+    pub state_after: ReviewState,
+}
+
+impl DeckBundle {
+    // 'history' is the account's scores and reviews for the deck, if they should be included
+    pub fn new(
+        details: &DeckDetails,
+        history: Option<(&[CardScore], &[Review])>,
+    ) -> Result<DeckBundle, TransferError> {
+        let mut bundle = DeckBundle {
+            format_version: BUNDLE_FORMAT_VERSION,
+            deck: BundleDeck {
+                name: details.info.name().to_owned(),
+                scheduler: details.info.scheduler(),
+            },
+            cards: details
+                .cards
+                .iter()
+                .map(|card| BundleCard {
+                    question: card.question().to_owned(),
+                    answer: card.answer().to_owned(),
+                    kind: card.kind(),
+                    reversible: card.is_reversible(),
+                    distractors: details
+                        .distractors
+                        .iter()
+                        .filter(|distractor| distractor.card_id == card.id)
+                        .map(|distractor| distractor.text.clone())
+                        .collect(),
+                    accepted_answers: details
+                        .accepted_answers
+                        .iter()
+                        .filter(|answer| answer.card_id == card.id)
+                        .map(|answer| answer.text.clone())
+                        .collect(),
+                })
+                .collect(),
+            scores: None,
+            reviews: None,
+        };
+        if let Some((scores, reviews)) = history {
+            // history of items a card no longer has, e.g. a removed cloze deletion, is left out
+            let card_index: HashMap<u64, (usize, Vec<u16>)> = details
+                .cards
+                .iter()
+                .enumerate()
+                .map(|(index, card)| (card.id, (index, card.items())))
+                .collect();
+            let index_of = |card_id: u64, item: u16| match card_index.get(&card_id) {
+                Some((index, items)) if items.contains(&item) => Some(*index),
+                _ => None,
+            };
+            let mut bundle_scores = Vec::with_capacity(scores.len());
+            for card_score in scores.iter() {
+                if let Some(index) = index_of(card_score.card_id, card_score.item) {
+                    bundle_scores.push(BundleScore {
+                        card: index,
+                        item: card_score.item,
+                        score: card_score.score,
+                        state: card_score.state(),
+                    });
+                }
+            }
+            let mut bundle_reviews = Vec::with_capacity(reviews.len());
+            for review in reviews.iter() {
+                if let Some(index) = index_of(review.card_id, review.item) {
+                    bundle_reviews.push(BundleReview {
+                        card: index,
+                        item: review.item,
+                        grade: review.grade,
+                        answered_at: review.answered_at,
+                        response_ms: review.response_ms,
+                        scheduler: SchedulerKind::from_id(review.scheduler)
+                            .unwrap_or(SchedulerKind::Sm2),
+                        state_before: review.state_before()?,
+                        state_after: review.state_after()?,
+                    });
+                }
+            }
+            bundle.scores = Some(bundle_scores);
+            bundle.reviews = Some(bundle_reviews);
+        }
+        return Ok(bundle);
+    }
+
+    pub fn from_json(json: &str) -> Result<DeckBundle, TransferError> {
+        let bundle: DeckBundle = serde_json::from_str(json)?;
+        bundle.check()?;
+        return Ok(bundle);
+    }
+
+    // make sure we understand this bundle before touching the database
+    pub fn check(&self) -> Result<(), TransferError> {
+        if self.format_version == 0 || self.format_version > BUNDLE_FORMAT_VERSION {
+            return Err(TransferError::UnsupportedVersion(self.format_version));
+        }
+        if self.cards.len() > u16::MAX as usize {
+            return Err(TransferError::BadBundle);
+        }
+        // a cloze without deletions could never be studied
+        let cloze_ok = self
+            .cards
+            .iter()
+            .all(|card| card.kind != CardKind::Cloze || !cloze::numbers(&card.question).is_empty());
+        // only basic cards have a reverse side
+        let reversible_ok = self
+            .cards
+            .iter()
+            .all(|card| !card.reversible || card.kind == CardKind::Basic);
+        // history only for items the cards have
+        let card_items: Vec<Vec<u16>> = self.cards.iter().map(BundleCard::items).collect();
+        let has_item = |card: usize, item: u16| {
+            card_items
+                .get(card)
+                .is_some_and(|items| items.contains(&item))
+        };
+        // one score per card item, like the cardscores table
+        let mut score_keys = HashSet::new();
+        let scores_ok = self.scores.iter().flatten().all(|score| {
+            has_item(score.card, score.item) && score_keys.insert((score.card, score.item))
+        });
+        let reviews_ok = self
+            .reviews
+            .iter()
+            .flatten()
+            .all(|review| has_item(review.card, review.item));
+        // only basic cards without a reverse can be multiple choice, see card::choice::from_texts
+        let distractors_ok = self.cards.iter().all(|card| {
+            card.distractors.is_empty()
+                || (card.kind == CardKind::Basic
+                    && !card.reversible
+                    && choice::from_texts(0, &card.answer, card.distractors.clone()).is_some())
+        });
+        // and only basic cards have accepted answers, see card::typed::from_texts
+        let answers_ok = self.cards.iter().all(|card| {
+            card.accepted_answers.is_empty()
+                || (card.kind == CardKind::Basic
+                    && typed::from_texts(0, &card.answer, card.accepted_answers.clone()).is_some())
+        });
+        if !cloze_ok
+            || !reversible_ok
+            || !scores_ok
+            || !reviews_ok
+            || !distractors_ok
+            || !answers_ok
+        {
+            return Err(TransferError::BadBundle);
+        }
+        return Ok(());
+    }
+
+    // database records for the bundle under 'account_id', built with fresh ids
+    pub fn records<F>(
+        &self,
+        account_id: u64,
+        mut new_id: F,
+    ) -> Result<DeckRecords, crate::FlashError>
+    where
+        F: FnMut() -> Result<u64, crate::FlashError>,
+    {
+        self.check()?;
+        let mut deck = Deck::new(new_id()?, account_id, self.deck.name.clone())?;
+        deck.set_scheduler(self.deck.scheduler);
+        let mut cards = Vec::with_capacity(self.cards.len());
+        let mut distractors = Vec::new();
+        let mut accepted_answers = Vec::new();
+        for (index, bundle_card) in self.cards.iter().enumerate() {
+            let mut card = Card::new(
+                new_id()?,
+                deck.id,
+                (index + 1) as u16, // position 0 is reserved
+                bundle_card.question.clone(),
+                bundle_card.answer.clone(),
+            )?;
+            card.set_kind(bundle_card.kind);
+            card.set_reversible(bundle_card.reversible);
+            // already checked
+            let texts = bundle_card.distractors.clone();
+            if let Some(card_distractors) = choice::from_texts(card.id, card.answer(), texts) {
+                distractors.extend(card_distractors);
+            }
+            let texts = bundle_card.accepted_answers.clone();
+            if let Some(card_answers) = typed::from_texts(card.id, card.answer(), texts) {
+                accepted_answers.extend(card_answers);
+            }
+            cards.push(card);
+        }
+        let mut scores = Vec::new();
+        for bundle_score in self.scores.iter().flatten() {
+            scores.push(CardScore::from_state(
+                account_id,
+                cards[bundle_score.card].id,
+                bundle_score.item,
+                bundle_score.score,
+                &bundle_score.state,
+            ));
+        }
+        let mut reviews = Vec::new();
+        for bundle_review in self.reviews.iter().flatten() {
+            reviews.push(Review {
+                id: new_id()?,
+                account_id: account_id,
+                card_id: cards[bundle_review.card].id,
+                item: bundle_review.item,
+                grade: bundle_review.grade,
+                answered_at: bundle_review.answered_at,
+                response_ms: bundle_review.response_ms,
+                scheduler: bundle_review.scheduler.id(),
+                state_before: serde_json::to_string(&bundle_review.state_before)
+                    .map_err(TransferError::from)?,
+                state_after: serde_json::to_string(&bundle_review.state_after)
+                    .map_err(TransferError::from)?,
+            });
+        }
+        return Ok(DeckRecords {
+            deck: deck,
+            cards: cards,
+            distractors: distractors,
+            accepted_answers: accepted_answers,
+            scores: scores,
+            reviews: reviews,
+        });
+    }
+}
@@ -0,0 +1,236 @@
+// Reads and writes decks as delimiter separated text (CSV, TSV, etc)
+// for round-tripping through spreadsheets.
+use crate::deck::{Deck, DeckDetails};
+use crate::transfer::TransferError;
+
+use serde::{Deserialize, Serialize};
+
+// zero-based column index of each card field
+#[derive(Deserialize, Clone, Debug)]
+pub struct ColumnMapping {
+    pub question: usize,
+    pub answer: usize,
+    pub position: Option<usize>, // without a position column rows are kept in file order
+}
+
+impl Default for ColumnMapping {
+    fn default() -> ColumnMapping {
+        ColumnMapping {
+            question: 0,
+            answer: 1,
+            position: None,
+        }
+    }
+}
+
+impl ColumnMapping {
+    // "question,answer" or "question,answer,position" column indexes, e.g. "0,1,2"
+    pub fn parse(text: &str) -> Option<ColumnMapping> {
+        let indexes: Vec<usize> = text
+            .split(',')
+            .map(|index| index.trim().parse::<usize>())
+            .collect::<Result<_, _>>()
+            .ok()?;
+        match indexes.as_slice() {
+            [question, answer] => Some(ColumnMapping {
+                question: *question,
+                answer: *answer,
+                position: None,
+            }),
+            [question, answer, position] => Some(ColumnMapping {
+                question: *question,
+                answer: *answer,
+                position: Some(*position),
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct DelimitedOptions {
+    pub delimiter: char, // must be a single byte character
+    pub has_headers: bool,
+    pub columns: ColumnMapping,
+}
+
+impl Default for DelimitedOptions {
+    fn default() -> DelimitedOptions {
+        DelimitedOptions::csv()
+    }
+}
+
+impl DelimitedOptions {
+    pub fn csv() -> DelimitedOptions {
+        DelimitedOptions {
+            delimiter: ',',
+            has_headers: true,
+            columns: ColumnMapping::default(),
+        }
+    }
+
+    pub fn tsv() -> DelimitedOptions {
+        DelimitedOptions {
+            delimiter: '\t',
+            ..DelimitedOptions::csv()
+        }
+    }
+
+    // the delimiter as a byte, after checking the options make sense
+    fn delimiter_byte(&self) -> Result<u8, TransferError> {
+        let columns = &self.columns;
+        if !self.delimiter.is_ascii()
+            || columns.question == columns.answer
+            || columns.position == Some(columns.question)
+            || columns.position == Some(columns.answer)
+        {
+            return Err(TransferError::BadOptions);
+        }
+        return Ok(self.delimiter as u8);
+    }
+}
+
+#[derive(Debug)]
+pub struct DelimitedRow {
+    pub line: u64,
+    pub question: String,
+    pub answer: String,
+    pub position: Option<u16>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct RowError {
+    pub line: u64, // 1-based line in the source text
+    pub message: String,
+}
+
+// result of FlashManager::import_deck_csv
+// when any row has an error nothing is imported and 'deck' is None
+#[derive(Serialize)]
+pub struct DelimitedImportReport {
+    pub deck: Option<Deck>,
+    pub cards_imported: usize,
+    pub errors: Vec<RowError>,
+}
+
+pub fn write_deck(details: &DeckDetails, options: &DelimitedOptions) -> Result<String, TransferError> {
+    let columns = &options.columns;
+    let width = columns
+        .question
+        .max(columns.answer)
+        .max(columns.position.unwrap_or(0))
+        + 1;
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(options.delimiter_byte()?)
+        .from_writer(Vec::<u8>::new());
+    if options.has_headers {
+        let mut header = vec![String::new(); width];
+        header[columns.question] = "question".to_owned();
+        header[columns.answer] = "answer".to_owned();
+        if let Some(position) = columns.position {
+            header[position] = "position".to_owned();
+        }
+        writer.write_record(&header)?;
+    }
+    for card in details.cards.iter() {
+        let mut record = vec![String::new(); width];
+        record[columns.question] = card.question().to_owned();
+        record[columns.answer] = card.answer().to_owned();
+        if let Some(position) = columns.position {
+            record[position] = card.deck_pos.to_string();
+        }
+        writer.write_record(&record)?;
+    }
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| TransferError::IoError(e.into_error()))?;
+    return String::from_utf8(bytes).map_err(|_e| TransferError::BadOptions);
+}
+
+// parse every row, collecting one error per bad row instead of stopping at the first
+pub fn read_rows(
+    data: &str,
+    options: &DelimitedOptions,
+) -> Result<(Vec<DelimitedRow>, Vec<RowError>), TransferError> {
+    let columns = &options.columns;
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(options.delimiter_byte()?)
+        .has_headers(options.has_headers)
+        .flexible(true)
+        .from_reader(data.as_bytes());
+    let mut rows = Vec::new();
+    let mut errors = Vec::new();
+    for result in reader.records() {
+        let record = match result {
+            Ok(record) => record,
+            Err(err) => {
+                let line = err.position().map(|p| p.line()).unwrap_or(0);
+                errors.push(RowError {
+                    line: line,
+                    message: err.to_string(),
+                });
+                continue;
+            }
+        };
+        let line = record.position().map(|p| p.line()).unwrap_or(0);
+        let row_error = |message: &str| RowError {
+            line: line,
+            message: message.to_owned(),
+        };
+        if record.iter().all(|field| field.trim().is_empty()) {
+            continue; // ignore blank lines
+        }
+        let question = match record.get(columns.question) {
+            Some(question) if !question.trim().is_empty() => question.to_owned(),
+            _ => {
+                errors.push(row_error("missing question"));
+                continue;
+            }
+        };
+        let answer = match record.get(columns.answer) {
+            Some(answer) => answer.to_owned(),
+            None => {
+                errors.push(row_error("missing answer"));
+                continue;
+            }
+        };
+        let position = match columns.position {
+            Some(index) => match record.get(index).map(|p| p.trim().parse::<u16>()) {
+                Some(Ok(position)) if position > 0 => Some(position),
+                _ => {
+                    errors.push(row_error("position must be a number from 1 to 65535"));
+                    continue;
+                }
+            },
+            None => None,
+        };
+        rows.push(DelimitedRow {
+            line: line,
+            question: question,
+            answer: answer,
+            position: position,
+        });
+    }
+    if rows.len() > u16::MAX as usize {
+        errors.push(RowError {
+            line: 0,
+            message: "too many rows for a single deck".to_owned(),
+        });
+    }
+    // positions only decide order, but two rows claiming the same one is likely a mistake
+    let mut seen = std::collections::HashMap::<u16, u64>::new();
+    for row in rows.iter() {
+        if let Some(position) = row.position {
+            if let Some(first_line) = seen.insert(position, row.line) {
+                errors.push(RowError {
+                    line: row.line,
+                    message: format!("position {} is also used on line {}", position, first_line),
+                });
+            }
+        }
+    }
+    // order by position, rows without one keep file order
+    rows.sort_by_key(|row| row.position.unwrap_or(0));
+    return Ok((rows, errors));
+}
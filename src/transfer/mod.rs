@@ -0,0 +1,56 @@
+// This module converts decks to and from formats used by other tools.
+
+pub mod anki;
+pub mod bundle;
+pub mod delimited;
+
+#[derive(Debug)]
+pub enum TransferError {
+    IoError(std::io::Error),
+    JsonError(serde_json::Error),
+    ZipError(zip::result::ZipError),
+    SqliteError(rusqlite::Error),
+    CsvError(csv::Error),
+    BadOptions, // e.g. a delimiter that isn't a single byte
+    UnsupportedVersion(u32), // bundle written by a newer version of this library
+    BadBundle,               // bundle refers to cards it doesn't contain, or has bad card options
+    MissingCollection,     // archive has no collection database
+    UnsupportedCollection, // collection is in a format we can't read
+    TooLarge,              // import over its size limit, e.g. anki::MAX_PACKAGE_BYTES
+}
+
+impl From<TransferError> for crate::FlashError {
+    fn from(err: TransferError) -> crate::FlashError {
+        crate::FlashError::TransferError(err)
+    }
+}
+
+impl From<std::io::Error> for TransferError {
+    fn from(err: std::io::Error) -> TransferError {
+        TransferError::IoError(err)
+    }
+}
+
+impl From<serde_json::Error> for TransferError {
+    fn from(err: serde_json::Error) -> TransferError {
+        TransferError::JsonError(err)
+    }
+}
+
+impl From<zip::result::ZipError> for TransferError {
+    fn from(err: zip::result::ZipError) -> TransferError {
+        TransferError::ZipError(err)
+    }
+}
+
+impl From<rusqlite::Error> for TransferError {
+    fn from(err: rusqlite::Error) -> TransferError {
+        TransferError::SqliteError(err)
+    }
+}
+
+impl From<csv::Error> for TransferError {
+    fn from(err: csv::Error) -> TransferError {
+        TransferError::CsvError(err)
+    }
+}
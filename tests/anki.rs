@@ -0,0 +1,94 @@
+// Test reading Anki packages
+use std::io::{Cursor, Write};
+
+use lib_flashcard::transfer::TransferError;
+use lib_flashcard::transfer::anki::{MAX_COLLECTION_BYTES, SkipReason, read_package};
+
+// build a minimal schema 11 collection and zip it up like Anki does
+fn build_apkg(collection_name: &str) -> Vec<u8> {
+  let path = std::env::temp_dir().join(format!("lib_flashcard_test_{}.anki2", std::process::id()));
+  let _ = std::fs::remove_file(&path);
+  {
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    conn
+      .execute_batch(
+        "CREATE TABLE col (id integer primary key, decks text not null, models text not null);
+         CREATE TABLE notes (id integer primary key, mid integer not null, flds text not null);
+         CREATE TABLE cards (id integer primary key, nid integer not null, did integer not null, ord integer not null);
+         INSERT INTO col VALUES (1,
+           '{\"1\": {\"id\": 1, \"name\": \"Default\"}, \"20\": {\"id\": 20, \"name\": \"Spanish::Verbs\"}}',
+           '{\"100\": {\"name\": \"Basic\", \"type\": 0}, \"200\": {\"name\": \"Cloze\", \"type\": 1}}');
+         INSERT INTO notes VALUES (1, 100, 'hablar' || char(31) || 'to <b>speak</b>[sound:hablar.mp3]');
+         INSERT INTO notes VALUES (2, 100, 'comer&nbsp;' || char(31) || 'to eat<br>to dine');
+         INSERT INTO notes VALUES (3, 200, '{{c1::Madrid}} is the capital' || char(31) || '');
+         INSERT INTO notes VALUES (4, 100, 'lonely field');
+         INSERT INTO notes VALUES (5, 100, 'reverse' || char(31) || 'esrever');
+         INSERT INTO notes VALUES (6, 100, 'orphan' || char(31) || 'only a second card');
+         INSERT INTO cards VALUES (1, 1, 20, 0);
+         INSERT INTO cards VALUES (2, 2, 20, 0);
+         INSERT INTO cards VALUES (3, 3, 1, 0);
+         INSERT INTO cards VALUES (4, 4, 1, 0);
+         INSERT INTO cards VALUES (5, 5, 1, 0);
+         INSERT INTO cards VALUES (6, 5, 20, 1);
+         INSERT INTO cards VALUES (7, 6, 20, 1);",
+      )
+      .unwrap();
+  }
+  let collection = std::fs::read(&path).unwrap();
+  std::fs::remove_file(&path).unwrap();
+
+  let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+  writer
+    .start_file(collection_name, zip::write::SimpleFileOptions::default())
+    .unwrap();
+  writer.write_all(&collection).unwrap();
+  writer.start_file("media", zip::write::SimpleFileOptions::default()).unwrap();
+  writer.write_all(b"{}").unwrap();
+  return writer.finish().unwrap().into_inner();
+}
+
+#[test]
+fn anki_tests() {
+  let package = read_package(&build_apkg("collection.anki2"), MAX_COLLECTION_BYTES).unwrap();
+
+  // notes land in the deck of their first card, in creation order
+  assert_eq!(package.decks.len(), 2);
+  assert_eq!(package.decks[0].name, "Spanish::Verbs");
+  assert_eq!(package.decks[0].cards.len(), 2);
+  assert_eq!(package.decks[0].cards[0].question, "hablar");
+  assert_eq!(package.decks[0].cards[0].answer, "to speak");
+  assert_eq!(package.decks[0].cards[1].question, "comer");
+  assert_eq!(package.decks[0].cards[1].answer, "to eat\nto dine");
+  assert_eq!(package.decks[1].name, "Default");
+  assert_eq!(package.decks[1].cards.len(), 1);
+  assert_eq!(package.decks[1].cards[0].note_id, 5);
+
+  // cloze, single field and first-card-less notes are reported, not imported
+  assert_eq!(package.skipped.len(), 3);
+  assert_eq!(package.skipped[0].note_id, 3);
+  assert_eq!(package.skipped[0].reason, SkipReason::UnsupportedNoteType);
+  assert_eq!(package.skipped[1].note_id, 4);
+  assert_eq!(package.skipped[1].reason, SkipReason::MissingFields);
+  assert_eq!(package.skipped[2].note_id, 6);
+  assert_eq!(package.skipped[2].reason, SkipReason::MissingCard);
+
+  // newer collections are preferred and read the same way
+  let package = read_package(&build_apkg("collection.anki21"), MAX_COLLECTION_BYTES).unwrap();
+  assert_eq!(package.decks.len(), 2);
+
+  // archives without a collection are rejected
+  match read_package(&build_apkg("something_else"), MAX_COLLECTION_BYTES) {
+    Err(TransferError::MissingCollection) => {}
+    _ => panic!("expected a missing collection error"),
+  }
+  match read_package(b"not a zip file", MAX_COLLECTION_BYTES) {
+    Err(TransferError::ZipError(_)) => {}
+    _ => panic!("expected a zip error"),
+  }
+
+  // collections that unpack past the limit are rejected before they're read in full
+  match read_package(&build_apkg("collection.anki2"), 1024) {
+    Err(TransferError::TooLarge) => {}
+    _ => panic!("read a collection over the size limit"),
+  }
+}
@@ -0,0 +1,178 @@
+// Test portable deck bundles
+use lib_flashcard::FlashError;
+use lib_flashcard::card::Card;
+use lib_flashcard::card::choice::Distractor;
+use lib_flashcard::card::typed::AcceptedAnswer;
+use lib_flashcard::deck::{Deck, DeckDetails};
+use lib_flashcard::game::{CardScore, Review, SchedulerKind};
+use lib_flashcard::transfer::TransferError;
+use lib_flashcard::transfer::bundle::{BUNDLE_FORMAT_VERSION, DeckBundle};
+
+const NOW: u32 = 1_600_000_000;
+
+fn details() -> DeckDetails {
+  let mut deck = Deck::new(1, 1, "Spanish".to_owned()).unwrap();
+  deck.set_scheduler(SchedulerKind::Fsrs);
+  DeckDetails {
+    info: deck,
+    cards: vec![
+      Card::new(10, 1, 1, "hablar".to_owned(), "to speak".to_owned()).unwrap(),
+      Card::new(11, 1, 2, "comer".to_owned(), "to eat".to_owned()).unwrap(),
+    ],
+    distractors: vec![Distractor::new(11, 0, "to drink".to_owned())],
+    accepted_answers: vec![AcceptedAnswer::new(10, 0, "speak".to_owned())],
+    media: Vec::new(),
+  }
+}
+
+#[test]
+fn bundle_round_trip_tests() {
+  // score the second card once
+  let mut card_score = CardScore::new(1, 11);
+  let before = card_score.state();
+  card_score.review(SchedulerKind::Fsrs.scheduler(), 4, NOW);
+  let review = Review::new(20, &card_score, SchedulerKind::Fsrs, &before, Some(900)).unwrap();
+
+  let scores = vec![card_score];
+  let reviews = vec![review];
+  let bundle =
+    DeckBundle::new(&details(), Some((scores.as_slice(), reviews.as_slice()))).unwrap();
+  let json = serde_json::to_string(&bundle).unwrap();
+  let read = DeckBundle::from_json(&json).unwrap();
+  assert_eq!(read.format_version, BUNDLE_FORMAT_VERSION);
+  assert_eq!(read.deck.name, "Spanish");
+  assert_eq!(read.deck.scheduler, SchedulerKind::Fsrs);
+  assert_eq!(read.cards.len(), 2);
+  assert_eq!(read.cards[1].question, "comer");
+  assert!(read.cards[0].distractors.is_empty());
+  assert_eq!(read.cards[1].distractors, vec!["to drink".to_owned()]);
+  assert_eq!(read.cards[0].accepted_answers, vec!["speak".to_owned()]);
+  assert!(read.cards[1].accepted_answers.is_empty());
+  let scores = read.scores.as_ref().unwrap();
+  assert_eq!(scores[0].card, 1);
+  assert_eq!(scores[0].score, 4);
+  let reviews = read.reviews.as_ref().unwrap();
+  assert_eq!(reviews[0].response_ms, Some(900));
+  assert_eq!(reviews[0].state_after, scores[0].state);
+
+  // records are rebuilt with fresh ids under the importing account
+  let mut next_id = 100u64;
+  let records = read
+    .records(7, || {
+      next_id += 1;
+      Ok::<u64, FlashError>(next_id)
+    })
+    .unwrap();
+  assert_eq!(records.deck.id, 101);
+  assert_eq!(records.deck.owner_id, 7);
+  assert_eq!(records.deck.scheduler(), SchedulerKind::Fsrs);
+  assert_eq!(records.cards[0].deck_pos, 1);
+  assert_eq!(records.cards[1].deck_id, 101);
+  assert_eq!(records.scores[0].card_id, records.cards[1].id);
+  let distractor = Distractor::new(records.cards[1].id, 0, "to drink".to_owned());
+  assert_eq!(records.distractors, vec![distractor]);
+  let accepted_answer = AcceptedAnswer::new(records.cards[0].id, 0, "speak".to_owned());
+  assert_eq!(records.accepted_answers, vec![accepted_answer]);
+  assert_eq!(records.reviews[0].card_id, records.cards[1].id);
+  assert_eq!(records.reviews[0].state_after().unwrap(), scores[0].state);
+
+  // history is optional
+  let bundle = DeckBundle::new(&details(), None).unwrap();
+  let json = serde_json::to_string(&bundle).unwrap();
+  assert!(!json.contains("scores"));
+  assert!(DeckBundle::from_json(&json).unwrap().reviews.is_none());
+}
+
+#[test]
+fn bundle_rejection_tests() {
+  let newer = format!(
+    "{{\"format_version\": {}, \"deck\": {{\"name\": \"x\", \"scheduler\": \"sm2\"}}, \"cards\": []}}",
+    BUNDLE_FORMAT_VERSION + 1
+  );
+  match DeckBundle::from_json(&newer) {
+    Err(TransferError::UnsupportedVersion(version)) => {
+      assert_eq!(version, BUNDLE_FORMAT_VERSION + 1)
+    }
+    _ => panic!("expected an unsupported version"),
+  }
+
+  let dangling = "{\"format_version\": 1, \"deck\": {\"name\": \"x\", \"scheduler\": \"sm2\"}, \
+                  \"cards\": [], \"scores\": [{\"card\": 0, \"score\": 1, \"state\": \
+                  {\"ease_factor\": 2.5, \"interval\": 0, \"repetitions\": 0, \"stability\": 0.0, \
+                  \"difficulty\": 0.0, \"due\": 0, \"last_reviewed\": 0}}]}";
+  match DeckBundle::from_json(dangling) {
+    Err(TransferError::BadBundle) => {}
+    _ => panic!("expected a bad bundle"),
+  }
+
+  // only one score per card item
+  let state = "{\"ease_factor\": 2.5, \"interval\": 0, \"repetitions\": 0, \"stability\": 0.0, \
+               \"difficulty\": 0.0, \"due\": 0, \"last_reviewed\": 0}";
+  let duplicate_scores = format!(
+    "{{\"format_version\": 5, \"deck\": {{\"name\": \"x\", \"scheduler\": \"sm2\"}}, \
+     \"cards\": [{{\"question\": \"q\", \"answer\": \"a\"}}], \"scores\": [\
+     {{\"card\": 0, \"score\": 1, \"state\": {}}}, {{\"card\": 0, \"score\": 4, \"state\": {}}}]}}",
+    state, state
+  );
+  match DeckBundle::from_json(&duplicate_scores) {
+    Err(TransferError::BadBundle) => {}
+    _ => panic!("expected a bad bundle"),
+  }
+
+  // a distractor can't also be the answer
+  let same_as_answer = "{\"format_version\": 4, \"deck\": {\"name\": \"x\", \"scheduler\": \"sm2\"}, \
+                        \"cards\": [{\"question\": \"q\", \"answer\": \"a\", \"distractors\": [\"a\"]}]}";
+  match DeckBundle::from_json(same_as_answer) {
+    Err(TransferError::BadBundle) => {}
+    _ => panic!("expected a bad bundle"),
+  }
+
+  // the reverse side would give a multiple choice answer away
+  let reversible_choice = "{\"format_version\": 5, \
+                           \"deck\": {\"name\": \"x\", \"scheduler\": \"sm2\"}, \
+                           \"cards\": [{\"question\": \"q\", \"answer\": \"a\", \
+                           \"reversible\": true, \"distractors\": [\"b\"]}]}";
+  match DeckBundle::from_json(reversible_choice) {
+    Err(TransferError::BadBundle) => {}
+    _ => panic!("expected a bad bundle"),
+  }
+
+  // only basic cards have accepted answers
+  let cloze_answers = "{\"format_version\": 5, \"deck\": {\"name\": \"x\", \"scheduler\": \"sm2\"}, \
+                       \"cards\": [{\"question\": \"{{c1::q}}\", \"answer\": \"\", \"kind\": \"cloze\", \
+                       \"accepted_answers\": [\"a\"]}]}";
+  match DeckBundle::from_json(cloze_answers) {
+    Err(TransferError::BadBundle) => {}
+    _ => panic!("expected a bad bundle"),
+  }
+
+  // a cloze card needs a deletion to be studied
+  let no_deletions = "{\"format_version\": 5, \"deck\": {\"name\": \"x\", \"scheduler\": \"sm2\"}, \
+                      \"cards\": [{\"question\": \"q\", \"answer\": \"\", \"kind\": \"cloze\"}]}";
+  match DeckBundle::from_json(no_deletions) {
+    Err(TransferError::BadBundle) => {}
+    _ => panic!("expected a bad bundle"),
+  }
+
+  // only basic cards have a reverse side
+  let reversible_cloze = "{\"format_version\": 5, \
+                          \"deck\": {\"name\": \"x\", \"scheduler\": \"sm2\"}, \
+                          \"cards\": [{\"question\": \"{{c1::q}}\", \"answer\": \"\", \
+                          \"kind\": \"cloze\", \"reversible\": true}]}";
+  match DeckBundle::from_json(reversible_cloze) {
+    Err(TransferError::BadBundle) => {}
+    _ => panic!("expected a bad bundle"),
+  }
+
+  // history has to be for an item the card has
+  let missing_item = format!(
+    "{{\"format_version\": 5, \"deck\": {{\"name\": \"x\", \"scheduler\": \"sm2\"}}, \
+     \"cards\": [{{\"question\": \"{{{{c1::q}}}}\", \"answer\": \"\", \"kind\": \"cloze\"}}], \
+     \"scores\": [{{\"card\": 0, \"item\": 2, \"score\": 1, \"state\": {}}}]}}",
+    state
+  );
+  match DeckBundle::from_json(&missing_item) {
+    Err(TransferError::BadBundle) => {}
+    _ => panic!("expected a bad bundle"),
+  }
+}
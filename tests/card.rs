@@ -30,7 +30,7 @@ fn card_tests() {
   let auth_manager = prepare_auth_manager(&id_factory);
 
   // create the flashcard manager
-  let flash_manager = prepare_flash_manager(&id_factory);
+  let flash_manager = prepare_flash_manager(epoch, node_id);
 
   // prepare the three test accounts - valid, fake, expired
   let (valid, fake, expired) = prepare_sessions(&auth_manager);
@@ -80,7 +80,7 @@ fn card_tests() {
   // currently only fetched using DeckDetails which needs to be tested in Deck tests.
 
   // verify you can't update a card using a fake account
-  match flash_manager.update_card(&fake, card.id, None, Some("B".to_owned())) {
+  match flash_manager.update_card(&fake, card.id, None, Some("B".to_owned()), None) {
     Ok(_) => panic!("should not be able to update a card using fake account"),
     Err(error) => match error {
       FlashError::PermissionError => {}
@@ -92,7 +92,7 @@ fn card_tests() {
   }
 
   // verify you can't update a card using an expired session
-  match flash_manager.update_card(&expired, card.id, None, Some("B".to_owned())) {
+  match flash_manager.update_card(&expired, card.id, None, Some("B".to_owned()), None) {
     Ok(_) => panic!("should not be able to update a card using expired session"),
     Err(error) => match error {
       FlashError::SessionTimeout => {}
@@ -105,7 +105,7 @@ fn card_tests() {
 
   // update a card using the valid account
   flash_manager
-    .update_card(&valid, card.id, None, Some("B".to_owned()))
+    .update_card(&valid, card.id, None, Some("B".to_owned()), None)
     .unwrap();
 
   // verify you can't update position using a fake account
@@ -178,6 +178,64 @@ fn card_tests() {
   let updated_card = flash_manager.get_card(&valid, card.id).unwrap();
   assert_eq!(updated_card.deck_pos, 1);
 
+  // delete a card from the middle of the deck, the cards after it shift down one at a time
+  // without ever sharing a position (unique key on deck_id, deck_pos)
+  let middle = flash_manager
+    .create_card(&valid, deck.id, 2, "Q3".to_owned(), "A3".to_owned())
+    .expect("failed to create middle card");
+  let last = flash_manager
+    .create_card(&valid, deck.id, 3, "Q4".to_owned(), "A4".to_owned())
+    .expect("failed to create last card");
+  let after_last = flash_manager
+    .create_card(&valid, deck.id, 4, "Q5".to_owned(), "A5".to_owned())
+    .expect("failed to create card after the last");
+  flash_manager.delete_card(&valid, middle.id).unwrap();
+  let positions: Vec<(u64, u16)> = flash_manager
+    .get_cards_for_deck(&valid, &deck.id)
+    .unwrap()
+    .iter()
+    .map(|card| (card.id, card.deck_pos))
+    .collect();
+  assert_eq!(positions, vec![(card.id, 1), (last.id, 2), (after_last.id, 3)]);
+
+  // a cloze card can't lose its last deletion, or be made reversible
+  let cloze = flash_manager
+    .create_cloze_card(&valid, deck.id, 4, "{{c1::Paris}}".to_owned(), String::new())
+    .expect("failed to create cloze card");
+  match flash_manager.update_card(&valid, cloze.id, Some("Paris".to_owned()), None, None) {
+    Err(FlashError::DBError(DBApiError::NotAllowed)) => {}
+    _ => panic!("removed the last deletion of a cloze card"),
+  }
+  match flash_manager.update_card(&valid, cloze.id, None, None, Some(true)) {
+    Err(FlashError::DBError(DBApiError::NotAllowed)) => {}
+    _ => panic!("made a cloze card reversible"),
+  }
+  flash_manager
+    .update_card(&valid, cloze.id, Some("{{c2::Paris}}".to_owned()), None, None)
+    .unwrap();
+  flash_manager.delete_card(&valid, cloze.id).unwrap();
+
+  // the reverse of a multiple choice card would give its answer away
+  let choice = flash_manager
+    .create_card(&valid, deck.id, 4, "hablar".to_owned(), "to speak".to_owned())
+    .expect("failed to create choice card");
+  flash_manager
+    .set_card_distractors(&valid, choice.id, vec!["to eat".to_owned()])
+    .unwrap();
+  match flash_manager.update_card(&valid, choice.id, None, None, Some(true)) {
+    Err(FlashError::DBError(DBApiError::NotAllowed)) => {}
+    _ => panic!("made a multiple choice card reversible"),
+  }
+  flash_manager.set_card_distractors(&valid, choice.id, Vec::new()).unwrap();
+  flash_manager
+    .update_card(&valid, choice.id, None, None, Some(true))
+    .unwrap();
+  match flash_manager.set_card_distractors(&valid, choice.id, vec!["to eat".to_owned()]) {
+    Err(FlashError::DBError(DBApiError::NotAllowed)) => {}
+    _ => panic!("gave a reversible card distractors"),
+  }
+  flash_manager.delete_card(&valid, choice.id).unwrap();
+
   // delete the card with the valid account
   flash_manager.delete_card(&valid, card.id).unwrap();
 
@@ -215,20 +273,23 @@ fn prepare_auth_manager(id_factory: &Mutex<WebeIDFactory>) -> WebeAuth {
   }
 }
 
-fn prepare_flash_manager(id_factory: &Mutex<WebeIDFactory>) -> FlashManager {
+fn prepare_flash_manager(epoch: SystemTime, node_id: u8) -> FlashManager {
   // create the Flash database pool
   print!("Building Flash Database Connection Pool......");
   let db_connect_string =
     env::var("FLASH_DATABASE_URL").expect("Failed to load Flash DB Connect string from .env");
   let flash_db_manager = webe_auth::db::new_manager(db_connect_string)
     .expect("Failed to create Flash Database connection pool");
+  lib_flashcard::db::run_migrations(&flash_db_manager)
+    .expect("Failed to run Flash database migrations");
   println!("Done");
 
   // create the flash manager
-  lib_flashcard::FlashManager {
-    db_manager: flash_db_manager,
-    id_factory: id_factory,
-  }
+  let id_generator =
+    lib_flashcard::id::IdGenerator::new(epoch, node_id).expect("Failed to create ID generator");
+  let mut flash_manager = FlashManager::new(flash_db_manager, id_generator);
+  flash_manager.set_media_store(Box::new(lib_flashcard::media::memory::MemoryMediaStore::new()));
+  flash_manager
 }
 
 fn prepare_sessions(auth_manager: &WebeAuth) -> (Session, Session, Session) {
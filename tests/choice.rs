@@ -0,0 +1,72 @@
+// Test multiple choice options and grading
+use lib_flashcard::card::choice::{self, Distractor, MAX_DISTRACTORS};
+use lib_flashcard::card::{Card, CardKind};
+use lib_flashcard::game::queue::{add_choice_options, build_study_queue};
+
+fn texts(texts: &[&str]) -> Vec<String> {
+  texts.iter().map(|text| text.to_string()).collect()
+}
+
+#[test]
+fn choice_tests() {
+  // distractors are trimmed and kept in the editor's order
+  let distractors = choice::from_texts(1, "to speak", texts(&[" to eat", "to drink "])).unwrap();
+  assert_eq!(
+    distractors,
+    vec![
+      Distractor::new(1, 0, "to eat".to_owned()),
+      Distractor::new(1, 1, "to drink".to_owned()),
+    ]
+  );
+  assert!(choice::from_texts(1, "to speak", Vec::new()).unwrap().is_empty());
+
+  // empty, repeated or correct distractors are refused, as are too many of them
+  assert!(choice::from_texts(1, "to speak", texts(&["to eat", " "])).is_none());
+  assert!(choice::from_texts(1, "to speak", texts(&["to eat", "to eat"])).is_none());
+  assert!(choice::from_texts(1, "to speak", texts(&["to speak "])).is_none());
+  let too_many: Vec<String> = (0..=MAX_DISTRACTORS).map(|n| n.to_string()).collect();
+  assert!(choice::from_texts(1, "to speak", too_many).is_none());
+
+  // the options are the answer and distractors, in an order picked by the seed
+  let mut options = choice::options("to speak", &distractors, 7);
+  assert_eq!(options, choice::options("to speak", &distractors, 7));
+  options.sort();
+  assert_eq!(options, texts(&["to drink", "to eat", "to speak"]));
+  let first_options: Vec<String> = (0..20)
+    .map(|seed| choice::options("to speak", &distractors, seed).remove(0))
+    .collect();
+  assert!(first_options.iter().any(|option| option != "to speak"));
+  assert!(first_options.iter().any(|option| option == "to speak"));
+
+  // only the card's own options can be picked
+  assert_eq!(choice::grade("to speak", &distractors, " to speak"), Some(true));
+  assert_eq!(choice::grade("to speak", &distractors, "to eat"), Some(false));
+  assert_eq!(choice::grade("to speak", &distractors, "to sing"), None);
+
+  // only basic cards get options in the study queue
+  let basic = Card::new(1, 1, 1, "hablar".to_owned(), "to speak".to_owned()).unwrap();
+  let mut cloze = Card::new(2, 1, 2, "{{c1::hablar}}".to_owned(), String::new()).unwrap();
+  cloze.set_kind(CardKind::Cloze);
+  let plain = Card::new(3, 1, 3, "comer".to_owned(), "to eat".to_owned()).unwrap();
+  let candidates = vec![
+    (basic, 0, None),
+    (cloze, 1, None),
+    (plain, 0, None),
+  ];
+  let mut queue = build_study_queue(candidates, 10, 10);
+  let deck_distractors = vec![
+    distractors[0].clone(),
+    Distractor::new(2, 0, "to eat".to_owned()),
+  ];
+  add_choice_options(&mut queue, &deck_distractors, 7);
+  let with_options: Vec<(u64, u16)> = queue
+    .iter()
+    .filter(|study_item| study_item.options.is_some())
+    .map(|study_item| (study_item.card.id, study_item.item))
+    .collect();
+  assert_eq!(with_options, vec![(1, 0)]);
+  let study_item = queue.iter().find(|study_item| study_item.options.is_some()).unwrap();
+  assert_eq!(study_item.options.as_ref().unwrap().len(), 2);
+  assert_eq!(study_item.answer, None);
+  assert_eq!(study_item.card.answer(), "");
+}
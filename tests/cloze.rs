@@ -0,0 +1,62 @@
+// Test parsing and rendering cloze deletions
+use lib_flashcard::card::cloze::{self, Segment};
+use lib_flashcard::card::{Card, CardKind, REVERSE_ITEM};
+
+#[test]
+fn cloze_tests() {
+  let text = "The capital of {{c1::France}} is {{c2::Paris::city}}";
+  assert_eq!(
+    cloze::parse(text),
+    vec![
+      Segment::Text("The capital of "),
+      Segment::Deletion {
+        number: 1,
+        text: "France",
+        hint: None
+      },
+      Segment::Text(" is "),
+      Segment::Deletion {
+        number: 2,
+        text: "Paris",
+        hint: Some("city")
+      },
+    ]
+  );
+  assert_eq!(cloze::numbers(text), vec![1, 2]);
+
+  // each number hides its own deletions and shows the rest
+  let (question, answer) = cloze::render(text, 1).unwrap();
+  assert_eq!(question, "The capital of [...] is Paris");
+  assert_eq!(answer, "The capital of France is Paris");
+  let (question, _answer) = cloze::render(text, 2).unwrap();
+  assert_eq!(question, "The capital of France is [city]");
+  assert!(cloze::render(text, 3).is_none());
+
+  // deletions sharing a number are one item
+  let text = "{{c2::Oslo}} and {{c2::Bergen}} are in {{c1::Norway}}";
+  assert_eq!(cloze::numbers(text), vec![1, 2]);
+  assert_eq!(cloze::render(text, 2).unwrap().0, "[...] and [...] are in Norway");
+
+  // anything malformed is plain text
+  for text in ["{{c0::zero}}", "{{c1:one}}", "{{c::none}}", "{{c1::}}", "{{c1::open", "{{x}}"] {
+    assert!(cloze::numbers(text).is_empty(), "{}", text);
+    assert_eq!(cloze::parse(text), vec![Segment::Text(text)]);
+  }
+
+  // cards study every deletion number as its own item
+  let mut card = Card::new(1, 1, 1, "{{c3::a}} {{c1::b}}".to_owned(), String::new()).unwrap();
+  assert_eq!(card.items(), vec![0]);
+  card.set_kind(CardKind::Cloze);
+  assert_eq!(card.items(), vec![1, 3]);
+  assert_eq!(card.item_text(3).unwrap().0, "[...] b");
+  assert!(card.item_text(0).is_none());
+
+  // reversible basic cards add an item studied from answer to question
+  let mut card = Card::new(1, 1, 1, "hablar".to_owned(), "to speak".to_owned()).unwrap();
+  card.set_reversible(true);
+  assert_eq!(card.items(), vec![0, REVERSE_ITEM]);
+  assert_eq!(card.item_text(REVERSE_ITEM).unwrap(), ("to speak".to_owned(), "hablar".to_owned()));
+  // only basic cards reverse
+  card.set_kind(CardKind::Cloze);
+  assert!(card.items().is_empty());
+}
@@ -0,0 +1,183 @@
+// Test loading FlashManager settings from TOML and the environment
+use lib_flashcard::FlashManager;
+use lib_flashcard::config::{
+  ConfigError, DEFAULT_ID_EPOCH_MS, DEFAULT_POOL_MAX_SIZE, FlashConfig, IdConfig, MediaConfig,
+  PoolConfig, TypingConfig,
+};
+use lib_flashcard::game::queue::DEFAULT_NEW_CARDS_PER_QUEUE;
+
+use std::env;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+#[test]
+fn config_tests() {
+  // only the database url is required
+  let config = FlashConfig::from_toml_str("database_url = \"mysql://localhost/flash\"").unwrap();
+  assert_eq!(config, FlashConfig::new("mysql://localhost/flash".to_owned()));
+  assert_eq!(config.pool.max_size, DEFAULT_POOL_MAX_SIZE);
+  assert_eq!(config.id.epoch_ms, DEFAULT_ID_EPOCH_MS);
+  assert_eq!(config.new_cards_per_queue, DEFAULT_NEW_CARDS_PER_QUEUE);
+  assert_eq!(config.typing, TypingConfig::default());
+  assert_eq!(config.media, MediaConfig::default());
+  match FlashConfig::from_toml_str("[pool]\nmax_size = 4") {
+    Err(ConfigError::TomlError(_err)) => {}
+    _ => panic!("loaded a config without a database url"),
+  }
+
+  // every setting can be given
+  let config = FlashConfig::from_toml_str(
+    r#"
+    database_url = "mysql://localhost/flash"
+    new_cards_per_queue = 5
+
+    [pool]
+    max_size = 20
+    min_idle = 2
+    connection_timeout_secs = 5
+    test_on_check_out = false
+
+    [id]
+    node_id = 3
+    epoch_ms = 1600000000000
+
+    [typing]
+    ignore_case = false
+    ignore_diacritics = false
+    ignore_punctuation = true
+    chars_per_typo = 4
+    max_typos = 3
+
+    [media]
+    dir = "/var/lib/flash/media"
+    max_bytes = 1048576
+    "#,
+  )
+  .unwrap();
+  let expected_pool = PoolConfig {
+    max_size: 20,
+    min_idle: Some(2),
+    connection_timeout_secs: 5,
+    test_on_check_out: false,
+  };
+  assert_eq!(config.pool, expected_pool);
+  let expected_id = IdConfig {
+    node_id: 3,
+    epoch_ms: 1600000000000,
+  };
+  assert_eq!(config.id, expected_id);
+  assert_eq!(config.new_cards_per_queue, 5);
+  let expected_typing = TypingConfig {
+    ignore_case: false,
+    ignore_diacritics: false,
+    ignore_punctuation: true,
+    chars_per_typo: 4,
+    max_typos: 3,
+  };
+  assert_eq!(config.typing, expected_typing);
+  assert_eq!(config.typing.allowed_typos(3), 0);
+  assert_eq!(config.typing.allowed_typos(9), 2);
+  assert_eq!(config.typing.allowed_typos(40), 3);
+  let expected_media = MediaConfig {
+    dir: PathBuf::from("/var/lib/flash/media"),
+    max_bytes: 1048576,
+  };
+  assert_eq!(config.media, expected_media);
+
+  // the same settings from the environment
+  unsafe {
+    env::remove_var("FLASH_DATABASE_URL");
+  }
+  match FlashConfig::from_env() {
+    Err(ConfigError::Missing("FLASH_DATABASE_URL")) => {}
+    _ => panic!("loaded a config without a database url"),
+  }
+  let vars = [
+    ("FLASH_DATABASE_URL", "mysql://localhost/flash"),
+    ("FLASH_NEW_CARDS_PER_QUEUE", "5"),
+    ("FLASH_POOL_MAX_SIZE", "20"),
+    ("FLASH_POOL_MIN_IDLE", "2"),
+    ("FLASH_POOL_CONNECTION_TIMEOUT_SECS", "5"),
+    ("FLASH_POOL_TEST_ON_CHECK_OUT", "false"),
+    ("FLASH_ID_NODE_ID", "3"),
+    ("FLASH_ID_EPOCH_MS", "1600000000000"),
+    ("FLASH_TYPING_IGNORE_CASE", "false"),
+    ("FLASH_TYPING_IGNORE_DIACRITICS", "false"),
+    ("FLASH_TYPING_IGNORE_PUNCTUATION", "true"),
+    ("FLASH_TYPING_CHARS_PER_TYPO", "4"),
+    ("FLASH_TYPING_MAX_TYPOS", "3"),
+    ("FLASH_MEDIA_DIR", "/var/lib/flash/media"),
+    ("FLASH_MEDIA_MAX_BYTES", "1048576"),
+  ];
+  for (name, value) in vars.iter() {
+    unsafe {
+      env::set_var(name, value);
+    }
+  }
+  assert_eq!(FlashConfig::from_env().unwrap(), config);
+  unsafe {
+    env::set_var("FLASH_ID_NODE_ID", "300");
+  }
+  match FlashConfig::from_env() {
+    Err(ConfigError::Invalid("FLASH_ID_NODE_ID")) => {}
+    _ => panic!("accepted a node id that doesn't fit in a byte"),
+  }
+  for (name, _value) in vars.iter() {
+    unsafe {
+      env::remove_var(name);
+    }
+  }
+
+  // the builder overrides loaded settings
+  let epoch = SystemTime::UNIX_EPOCH
+    .checked_add(Duration::from_millis(1546300800000)) // 01-01-2019 12:00:00 AM GMT
+    .expect("failed to create custom epoch");
+  let builder = FlashManager::builder("mysql://localhost/flash".to_owned())
+    .max_pool_size(4)
+    .min_idle(Some(1))
+    .connection_timeout(Duration::from_secs(2))
+    .test_on_check_out(false)
+    .node_id(9)
+    .epoch(epoch)
+    .media_dir("uploads")
+    .max_media_bytes(1024);
+  assert_eq!(builder.config().pool.max_size, 4);
+  assert_eq!(builder.config().pool.min_idle, Some(1));
+  assert_eq!(builder.config().pool.connection_timeout_secs, 2);
+  assert!(!builder.config().pool.test_on_check_out);
+  assert_eq!(builder.config().id.node_id, 9);
+  assert_eq!(builder.config().id.epoch_ms, 1546300800000);
+  assert_eq!(builder.config().media.dir, PathBuf::from("uploads"));
+  assert_eq!(builder.config().media.max_bytes, 1024);
+
+  // a sub-second timeout rounds up rather than becoming 0
+  let builder = FlashManager::builder("mysql://localhost/flash".to_owned())
+    .connection_timeout(Duration::from_millis(500));
+  assert_eq!(builder.config().pool.connection_timeout_secs, 1);
+
+  // pool settings r2d2 would panic on are rejected before building
+  let invalid_pools = [
+    ("[pool]\nmax_size = 0", "pool.max_size"),
+    ("[pool]\nmax_size = 4\nmin_idle = 5", "pool.min_idle"),
+    ("[pool]\nconnection_timeout_secs = 0", "pool.connection_timeout_secs"),
+  ];
+  for (pool, field) in invalid_pools.iter() {
+    let text = format!("database_url = \"mysql://localhost/flash\"\n{}", pool);
+    match FlashConfig::from_toml_str(text.as_str()) {
+      Err(ConfigError::Invalid(invalid)) => assert_eq!(invalid, *field),
+      _ => panic!("loaded an invalid pool config"),
+    }
+  }
+  let builders = [
+    FlashManager::builder("mysql://localhost/flash".to_owned()).max_pool_size(0),
+    FlashManager::builder("mysql://localhost/flash".to_owned())
+      .min_idle(Some(DEFAULT_POOL_MAX_SIZE + 1)),
+    FlashManager::builder("mysql://localhost/flash".to_owned()).connection_timeout(Duration::ZERO),
+  ];
+  for builder in builders {
+    match builder.build() {
+      Err(ConfigError::Invalid(_field)) => {}
+      _ => panic!("built a pool r2d2 would panic on"),
+    }
+  }
+}
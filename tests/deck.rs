@@ -6,6 +6,9 @@ extern crate webe_id;
 use std::env;
 use std::time::{Duration, SystemTime};
 
+use lib_flashcard::db::DBApiError;
+use lib_flashcard::deck::{DeckRole, DeckVisibility, DEFAULT_DECKS_PER_PAGE};
+use lib_flashcard::media::MediaSide;
 use lib_flashcard::{FlashError, FlashManager};
 use webe_auth::session::Session;
 use webe_auth::{AuthManager, WebeAuth};
@@ -30,7 +33,7 @@ fn deck_tests() {
     let auth_manager = prepare_auth_manager(&id_factory);
 
     // create the flashcard manager
-    let flash_manager = prepare_flash_manager(&id_factory);
+    let flash_manager = prepare_flash_manager(epoch, node_id);
 
     // prepare the three test accounts - valid, fake, expired
     let (valid, fake, expired) = prepare_sessions(&auth_manager);
@@ -95,6 +98,124 @@ fn deck_tests() {
         },
     }
 
+    // verify the fake account can't share a deck it doesn't own
+    match flash_manager.share_deck(&fake, deck.id, fake.account_id, DeckRole::Owner) {
+        Ok(_wut) => panic!("was able to share a deck with fake account"),
+        Err(error) => match error {
+            FlashError::PermissionError => {}
+            _ => {
+                dbg!(error);
+                panic!("recieved an unexpected error")
+            }
+        },
+    }
+
+    // share the deck read-only with the fake account
+    flash_manager
+        .share_deck(&valid, deck.id, fake.account_id, DeckRole::Viewer)
+        .unwrap();
+    let members = flash_manager.list_members(&valid, deck.id).unwrap();
+    assert_eq!(members.len(), 1);
+    assert_eq!(members[0].role(), Some(DeckRole::Viewer));
+    assert_eq!(flash_manager.get_decks_for_session(&fake).unwrap().len(), 1);
+    flash_manager.get_deck_details(&fake, &deck.id).unwrap();
+
+    // viewers can't edit
+    match flash_manager.create_card(&fake, deck.id, 1, "Q".to_owned(), "A".to_owned()) {
+        Ok(_wut) => panic!("was able to create a card as a viewer"),
+        Err(error) => match error {
+            FlashError::PermissionError => {}
+            _ => {
+                dbg!(error);
+                panic!("recieved an unexpected error")
+            }
+        },
+    }
+
+    // editors can edit cards, but not rename the deck
+    flash_manager
+        .share_deck(&valid, deck.id, fake.account_id, DeckRole::Editor)
+        .unwrap();
+    flash_manager
+        .create_card(&fake, deck.id, 1, "Q".to_owned(), "A".to_owned())
+        .unwrap();
+    match flash_manager.rename_deck(&fake, deck.id, "editor_test") {
+        Ok(_wut) => panic!("was able to rename a deck as an editor"),
+        Err(error) => match error {
+            FlashError::PermissionError => {}
+            _ => {
+                dbg!(error);
+                panic!("recieved an unexpected error")
+            }
+        },
+    }
+
+    // unsharing removes access again
+    flash_manager
+        .unshare_deck(&valid, deck.id, fake.account_id)
+        .unwrap();
+    assert_eq!(flash_manager.get_decks_for_session(&fake).unwrap().len(), 0);
+
+    // private decks can't be viewed or cloned by others
+    match flash_manager.clone_deck(&fake, deck.id) {
+        Ok(_wut) => panic!("was able to clone a private deck"),
+        Err(error) => match error {
+            FlashError::PermissionError => {}
+            _ => {
+                dbg!(error);
+                panic!("recieved an unexpected error")
+            }
+        },
+    }
+
+    // unlisted decks can be viewed by id, but aren't in the catalog
+    flash_manager
+        .set_deck_visibility(&valid, deck.id, DeckVisibility::Unlisted)
+        .unwrap();
+    flash_manager.get_deck_details(&fake, &deck.id).unwrap();
+    let catalog = flash_manager
+        .browse_public_decks(&fake, Some("valid_test"), 0, DEFAULT_DECKS_PER_PAGE)
+        .unwrap();
+    assert!(catalog.decks.iter().all(|listed| listed.id != deck.id));
+
+    // public decks are listed, and cloning copies every card
+    flash_manager
+        .set_deck_visibility(&valid, deck.id, DeckVisibility::Public)
+        .unwrap();
+    let catalog = flash_manager
+        .browse_public_decks(&fake, Some("valid_test"), 0, DEFAULT_DECKS_PER_PAGE)
+        .unwrap();
+    assert!(catalog.decks.iter().any(|listed| listed.id == deck.id));
+    let png: &[u8] = &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0, 0, 0, 0];
+    let first_card = flash_manager.get_cards_for_deck(&valid, &deck.id).unwrap().remove(0);
+    flash_manager
+        .add_card_media(&valid, first_card.id, MediaSide::Question, "image/png", png)
+        .unwrap();
+    let lost = flash_manager
+        .add_card_media(&valid, first_card.id, MediaSide::Answer, "image/png", png)
+        .unwrap();
+    flash_manager.media_store().remove(lost.id).unwrap();
+    let copy = flash_manager.clone_deck(&fake, deck.id).unwrap();
+    assert_eq!(copy.owner_id, fake.account_id);
+    assert_eq!(copy.visibility(), DeckVisibility::Private);
+    let copied_cards = flash_manager.get_cards_for_deck(&fake, &copy.id).unwrap();
+    let original_cards = flash_manager.get_cards_for_deck(&valid, &deck.id).unwrap();
+    assert_eq!(copied_cards.len(), original_cards.len());
+    for (copied, original) in copied_cards.iter().zip(original_cards.iter()) {
+        assert_eq!(copied.deck_pos, original.deck_pos);
+        assert_eq!(copied.question(), original.question());
+    }
+    // attachments are copied with their bytes, and ones whose bytes are gone are left off
+    let copied_media = flash_manager.get_deck_details(&fake, &copy.id).unwrap().media;
+    assert_eq!(copied_media.len(), 1);
+    assert_eq!(copied_media[0].side(), MediaSide::Question);
+    assert_eq!(flash_manager.get_media(&fake, copied_media[0].id).unwrap().1, png);
+    // the clone is independent of the original
+    flash_manager.delete_deck(&fake, copy.id).unwrap();
+    flash_manager
+        .set_deck_visibility(&valid, deck.id, DeckVisibility::Private)
+        .unwrap();
+
     // try to delete with the expired account
     match flash_manager.delete_deck(&expired, deck.id) {
         Ok(_wut) => panic!("was able to delete a deck with expired account"),
@@ -122,6 +243,23 @@ fn deck_tests() {
     // delete the deck with the valid account
     flash_manager.delete_deck(&valid, deck.id).unwrap();
 
+    // accounts the deck was shared with as an owner can delete it too
+    let shared = flash_manager
+        .create_deck(&valid, "shared_owner_test".to_owned())
+        .unwrap();
+    flash_manager
+        .share_deck(&valid, shared.id, fake.account_id, DeckRole::Owner)
+        .unwrap();
+    flash_manager.delete_deck(&fake, shared.id).unwrap();
+    match flash_manager.get_deck_details(&valid, &shared.id) {
+        Ok(_wut) => panic!("deck outlived its deletion by a shared owner"),
+        Err(FlashError::DBError(DBApiError::NotFound)) => {}
+        Err(error) => {
+            dbg!(error);
+            panic!("recieved an unexpected error")
+        }
+    }
+
     // clean up the accounts
     delete_account(&auth_manager, "valid");
     delete_account(&auth_manager, "fake");
@@ -153,20 +291,23 @@ fn prepare_auth_manager(id_factory: &Mutex<WebeIDFactory>) -> WebeAuth {
     }
 }
 
-fn prepare_flash_manager(id_factory: &Mutex<WebeIDFactory>) -> FlashManager {
+fn prepare_flash_manager(epoch: SystemTime, node_id: u8) -> FlashManager {
     // create the Flash database pool
     print!("Building Flash Database Connection Pool......");
     let db_connect_string =
         env::var("FLASH_DATABASE_URL").expect("Failed to load Flash DB Connect string from .env");
     let flash_db_manager = webe_auth::db::new_manager(db_connect_string)
         .expect("Failed to create Flash Database connection pool");
+    lib_flashcard::db::run_migrations(&flash_db_manager)
+        .expect("Failed to run Flash database migrations");
     println!("Done");
 
     // create the flash manager
-    lib_flashcard::FlashManager {
-        db_manager: flash_db_manager,
-        id_factory: id_factory,
-    }
+    let id_generator =
+        lib_flashcard::id::IdGenerator::new(epoch, node_id).expect("Failed to create ID generator");
+    let mut flash_manager = FlashManager::new(flash_db_manager, id_generator);
+    flash_manager.set_media_store(Box::new(lib_flashcard::media::memory::MemoryMediaStore::new()));
+    flash_manager
 }
 
 fn prepare_sessions(auth_manager: &WebeAuth) -> (Session, Session, Session) {
@@ -0,0 +1,111 @@
+// Test CSV/TSV deck conversion
+use lib_flashcard::card::Card;
+use lib_flashcard::deck::{Deck, DeckDetails};
+use lib_flashcard::transfer::TransferError;
+use lib_flashcard::transfer::delimited::{
+  ColumnMapping, DelimitedOptions, read_rows, write_deck,
+};
+
+fn details() -> DeckDetails {
+  DeckDetails {
+    info: Deck::new(1, 1, "test".to_owned()).unwrap(),
+    cards: vec![
+      Card::new(2, 1, 1, "Q1".to_owned(), "A1, with comma".to_owned()).unwrap(),
+      Card::new(3, 1, 2, "Q2".to_owned(), "A2\twith tab".to_owned()).unwrap(),
+    ],
+    distractors: Vec::new(),
+    accepted_answers: Vec::new(),
+    media: Vec::new(),
+  }
+}
+
+#[test]
+fn delimited_round_trip_tests() {
+  for options in [DelimitedOptions::csv(), DelimitedOptions::tsv()].iter() {
+    let text = write_deck(&details(), options).unwrap();
+    let (rows, errors) = read_rows(&text, options).unwrap();
+    assert!(errors.is_empty());
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].question, "Q1");
+    assert_eq!(rows[0].answer, "A1, with comma");
+    assert_eq!(rows[1].answer, "A2\twith tab");
+    assert_eq!(rows[1].position, None);
+  }
+
+  // positions survive the round trip when they get a column
+  let options = DelimitedOptions {
+    columns: ColumnMapping::parse("0,1,2").unwrap(),
+    ..DelimitedOptions::csv()
+  };
+  let text = write_deck(&details(), &options).unwrap();
+  assert!(text.starts_with("question,answer,position\n"));
+  let (rows, errors) = read_rows(&text, &options).unwrap();
+  assert!(errors.is_empty());
+  assert_eq!(rows[1].position, Some(2));
+
+  // a plain two column file keeps its row order
+  let (rows, errors) =
+    read_rows("question,answer\nQ1,A1\nQ2,A2\n", &DelimitedOptions::csv()).unwrap();
+  assert!(errors.is_empty());
+  assert_eq!(rows.len(), 2);
+  assert_eq!(rows[1].question, "Q2");
+  assert_eq!(rows[1].position, None);
+
+  // custom column order without headers
+  let options = DelimitedOptions {
+    delimiter: ';',
+    has_headers: false,
+    columns: ColumnMapping {
+      question: 2,
+      answer: 0,
+      position: None,
+    },
+  };
+  let text = write_deck(&details(), &options).unwrap();
+  assert!(text.starts_with("A1, with comma;;Q1\n"));
+  let (rows, errors) = read_rows(&text, &options).unwrap();
+  assert!(errors.is_empty());
+  assert_eq!(rows[0].question, "Q1");
+  assert_eq!(rows[0].position, None);
+}
+
+#[test]
+fn delimited_error_tests() {
+  // rows are ordered by position and every bad row is reported
+  let text = "question,answer,position\n\
+              second,2,2\n\
+              first,1,1\n\
+              ,missing question,3\n\
+              bad position,x,zero\n\
+              \n\
+              duplicate,d,2\n";
+  let options = DelimitedOptions {
+    columns: ColumnMapping::parse("0,1,2").unwrap(),
+    ..DelimitedOptions::csv()
+  };
+  let (rows, errors) = read_rows(text, &options).unwrap();
+  assert_eq!(rows.len(), 3);
+  assert_eq!(rows[0].question, "first");
+  assert_eq!(rows[1].question, "second");
+  let lines: Vec<u64> = errors.iter().map(|e| e.line).collect();
+  assert_eq!(lines, vec![4, 5, 7]);
+
+  // overlapping columns are rejected outright
+  let options = DelimitedOptions {
+    columns: ColumnMapping {
+      question: 0,
+      answer: 0,
+      position: None,
+    },
+    ..DelimitedOptions::csv()
+  };
+  match read_rows("a,b", &options) {
+    Err(TransferError::BadOptions) => {}
+    _ => panic!("expected bad options"),
+  }
+
+  // column mappings from text
+  assert!(ColumnMapping::parse("2, 0").unwrap().position.is_none());
+  assert!(ColumnMapping::parse("0").is_none());
+  assert!(ColumnMapping::parse("0,1,x").is_none());
+}
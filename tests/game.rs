@@ -6,6 +6,8 @@ extern crate webe_id;
 use std::env;
 use std::time::{Duration, SystemTime};
 
+use lib_flashcard::db::DBApiError;
+use lib_flashcard::game::StudyItemKind;
 use lib_flashcard::{FlashError, FlashManager};
 use webe_auth::session::Session;
 use webe_auth::{AuthManager, WebeAuth};
@@ -28,7 +30,7 @@ fn game_tests() {
   let auth_manager = prepare_auth_manager(&id_factory);
 
   // create the flashcard manager
-  let flash_manager = prepare_flash_manager(&id_factory);
+  let flash_manager = prepare_flash_manager(epoch, node_id);
 
   // prepare the three test accounts - valid, fake, expired
   let (valid, fake, expired) = prepare_sessions(&auth_manager);
@@ -43,8 +45,26 @@ fn game_tests() {
     .create_card(&valid, deck.id, 1, "Q".to_owned(), "A".to_owned())
     .expect("failed to create first card");
 
+  // an unscored card is new and first in the study queue
+  let queue = flash_manager.get_study_queue(&valid, deck.id, 10).unwrap();
+  assert_eq!(queue.len(), 1);
+  assert_eq!(queue[0].kind, StudyItemKind::New);
+  assert_eq!(queue[0].card.id, card.id);
+
+  // verify you can't get a study queue using a fake account
+  match flash_manager.get_study_queue(&fake, deck.id, 10) {
+    Ok(_) => panic!("should not be able to get a study queue using fake account"),
+    Err(error) => match error {
+      FlashError::PermissionError => {}
+      _ => {
+        dbg!(error);
+        panic!("recieved an unexpected error")
+      }
+    },
+  }
+
   // verify you can't update score using a fake account
-  match flash_manager.update_score(&fake, card.id, 1) {
+  match flash_manager.update_score(&fake, card.id, 1, None) {
     Ok(_) => panic!("should not be able to update a scure using fake account"),
     Err(error) => match error {
       FlashError::PermissionError => {}
@@ -56,7 +76,7 @@ fn game_tests() {
   }
 
   // verify you can't update score using an expired account
-  match flash_manager.update_score(&expired, card.id, 1) {
+  match flash_manager.update_score(&expired, card.id, 1, None) {
     Ok(_) => panic!("should not be able to update a scure using expired account"),
     Err(error) => match error {
       FlashError::SessionTimeout => {}
@@ -68,7 +88,11 @@ fn game_tests() {
   }
 
   // verify you can update score using a valid account
-  flash_manager.update_score(&valid, card.id, 1).unwrap();
+  // a failing grade schedules the card again tomorrow
+  let card_score = flash_manager.update_score(&valid, card.id, 1, Some(1500)).unwrap();
+  assert_eq!(card_score.repetitions, 0);
+  assert_eq!(card_score.interval, 1);
+  assert!(card_score.due > card_score.last_reviewed);
 
   // verify you can't get scores using a fake account
   match flash_manager.get_deck_scores(&fake, deck.id) {
@@ -99,6 +123,65 @@ fn game_tests() {
   assert_eq!(scores.len(), 1);
   assert_eq!(scores[0].score, 1);
 
+  // nothing is due until tomorrow
+  let due = flash_manager.get_due_scores(&valid, deck.id).unwrap();
+  assert_eq!(due.len(), 0);
+  let queue = flash_manager.get_study_queue(&valid, deck.id, 10).unwrap();
+  assert_eq!(queue.len(), 0);
+
+  // verify SM-2 intervals grow with successful reviews
+  let card_score = flash_manager.update_score(&valid, card.id, 5, None).unwrap();
+  assert_eq!(card_score.repetitions, 1);
+  assert_eq!(card_score.interval, 1);
+  let card_score = flash_manager.update_score(&valid, card.id, 4, None).unwrap();
+  assert_eq!(card_score.repetitions, 2);
+  assert_eq!(card_score.interval, 6);
+
+  // verify every answer was kept in the review history
+  let reviews = flash_manager.get_review_history(&valid, deck.id).unwrap();
+  assert_eq!(reviews.len(), 3);
+  assert_eq!(reviews[0].grade, 1);
+  assert_eq!(reviews[0].response_ms, Some(1500));
+  assert_eq!(reviews[2].grade, 4);
+  assert_eq!(
+    reviews[1].state_after().unwrap(),
+    reviews[2].state_before().unwrap()
+  );
+
+  // verify you can't read review history using a fake account
+  match flash_manager.get_review_history(&fake, deck.id) {
+    Ok(_) => panic!("should not be able to get review history using fake account"),
+    Err(error) => match error {
+      FlashError::PermissionError => {}
+      _ => {
+        dbg!(error);
+        panic!("recieved an unexpected error")
+      }
+    },
+  }
+
+  // a multiple choice card hides its answer in the queue and is only graded from a pick
+  let choice_card = flash_manager
+    .create_card(&valid, deck.id, 2, "hablar".to_owned(), "to speak".to_owned())
+    .expect("failed to create choice card");
+  flash_manager
+    .set_card_distractors(&valid, choice_card.id, vec!["to eat".to_owned()])
+    .unwrap();
+  let queue = flash_manager.get_study_queue(&valid, deck.id, 10).unwrap();
+  let study_item = queue.iter().find(|item| item.card.id == choice_card.id).unwrap();
+  assert_eq!(study_item.options.as_ref().unwrap().len(), 2);
+  assert_eq!(study_item.answer, None);
+  assert_eq!(study_item.card.answer(), "");
+  match flash_manager.update_score(&valid, choice_card.id, 5, None) {
+    Err(FlashError::DBError(DBApiError::NotAllowed)) => {}
+    _ => panic!("graded a multiple choice card without a pick"),
+  }
+  let result = flash_manager
+    .answer_choice(&valid, choice_card.id, "to eat", None)
+    .unwrap();
+  assert!(!result.correct);
+  assert_eq!(result.answer, "to speak");
+
   // clean up the accounts
   delete_account(&auth_manager, "valid");
   delete_account(&auth_manager, "fake");
@@ -130,20 +213,23 @@ fn prepare_auth_manager(id_factory: &Mutex<WebeIDFactory>) -> WebeAuth {
   }
 }
 
-fn prepare_flash_manager(id_factory: &Mutex<WebeIDFactory>) -> FlashManager {
+fn prepare_flash_manager(epoch: SystemTime, node_id: u8) -> FlashManager {
   // create the Flash database pool
   print!("Building Flash Database Connection Pool......");
   let db_connect_string =
     env::var("FLASH_DATABASE_URL").expect("Failed to load Flash DB Connect string from .env");
   let flash_db_manager = webe_auth::db::new_manager(db_connect_string)
     .expect("Failed to create Flash Database connection pool");
+  lib_flashcard::db::run_migrations(&flash_db_manager)
+    .expect("Failed to run Flash database migrations");
   println!("Done");
 
   // create the flash manager
-  lib_flashcard::FlashManager {
-    db_manager: flash_db_manager,
-    id_factory: id_factory,
-  }
+  let id_generator =
+    lib_flashcard::id::IdGenerator::new(epoch, node_id).expect("Failed to create ID generator");
+  let mut flash_manager = FlashManager::new(flash_db_manager, id_generator);
+  flash_manager.set_media_store(Box::new(lib_flashcard::media::memory::MemoryMediaStore::new()));
+  flash_manager
 }
 
 fn prepare_sessions(auth_manager: &WebeAuth) -> (Session, Session, Session) {
@@ -0,0 +1,43 @@
+// Test mapping FlashErrors to HTTP statuses and error bodies
+use lib_flashcard::FlashError;
+use lib_flashcard::db::DBApiError;
+use lib_flashcard::http::error::{ErrorBody, ErrorCode};
+use lib_flashcard::media::MediaError;
+use lib_flashcard::transfer::TransferError;
+
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+
+fn status(err: FlashError) -> u16 {
+  ErrorCode::from(&err).status()
+}
+
+#[test]
+fn http_error_tests() {
+  assert_eq!(status(FlashError::SessionTimeout), 401);
+  assert_eq!(status(FlashError::PermissionError), 403);
+  assert_eq!(status(FlashError::DBError(DBApiError::NotFound)), 404);
+  assert_eq!(status(FlashError::DBError(DBApiError::NotAllowed)), 422);
+  let duplicate = DieselError::DatabaseError(
+    DatabaseErrorKind::UniqueViolation,
+    Box::new("duplicate card position".to_owned()),
+  );
+  assert_eq!(status(FlashError::DBError(DBApiError::OtherError(duplicate))), 409);
+  assert_eq!(status(FlashError::TransferError(TransferError::BadOptions)), 422);
+  assert_eq!(status(FlashError::MediaError(MediaError::NotFound)), 404);
+  assert_eq!(status(FlashError::MediaError(MediaError::TooLarge)), 413);
+  assert_eq!(status(FlashError::MediaError(MediaError::UnsupportedType)), 415);
+  let disk_full = std::io::Error::new(std::io::ErrorKind::Other, "disk full");
+  assert_eq!(status(FlashError::MediaError(MediaError::IOError(disk_full))), 500);
+  assert_eq!(status(FlashError::OtherError), 500);
+  assert_eq!(
+    status(FlashError::DBError(DBApiError::OtherError(DieselError::RollbackTransaction))),
+    500
+  );
+
+  // the body carries a stable code along with the message
+  let body = serde_json::to_value(ErrorBody::from(&FlashError::SessionTimeout)).unwrap();
+  assert_eq!(body["code"], "session_expired");
+  assert_eq!(body["message"], ErrorCode::SessionExpired.message());
+  let body = serde_json::to_value(ErrorBody::from(&FlashError::PermissionError)).unwrap();
+  assert_eq!(body["code"], "permission_denied");
+}
@@ -0,0 +1,65 @@
+// Test the lock-free ID generator
+use lib_flashcard::id::{IdError, IdGenerator};
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+#[test]
+fn id_tests() {
+  let epoch = SystemTime::UNIX_EPOCH
+    .checked_add(Duration::from_millis(1546300800000)) // 01-01-2019 12:00:00 AM GMT
+    .expect("failed to create custom epoch");
+
+  // ids from one generator keep increasing, and carry its node id
+  let generator = IdGenerator::new(epoch, 7).unwrap();
+  let ids: Vec<u64> = (0..50_000).map(|_| generator.next().unwrap()).collect();
+  assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+  assert!(ids.iter().all(|id| (id >> 14) & 0xFF == 7));
+
+  // generators on different nodes never collide
+  let other = IdGenerator::new(epoch, 8).unwrap();
+  let id = generator.next().unwrap();
+  assert_ne!(id, other.next().unwrap());
+
+  // many threads sharing one generator all get unique ids
+  let generator = Arc::new(IdGenerator::new(epoch, 0).unwrap());
+  let handles: Vec<_> = (0..8)
+    .map(|_| {
+      let generator = generator.clone();
+      thread::spawn(move || {
+        (0..10_000)
+          .map(|_| generator.next().unwrap())
+          .collect::<Vec<u64>>()
+      })
+    })
+    .collect();
+  let mut unique = HashSet::new();
+  for handle in handles {
+    for id in handle.join().unwrap() {
+      assert!(unique.insert(id), "duplicate id {}", id);
+    }
+  }
+
+  // ids already issued by webe_id can't be handed out again once skipped past
+  let mut factory = webe_id::WebeIDFactory::new(epoch, 0).unwrap();
+  let issued: Vec<u64> = (0..1_000).map(|_| factory.next().unwrap()).collect();
+  let generator = IdGenerator::new(epoch, 0).unwrap();
+  generator.skip_past(*issued.iter().max().unwrap());
+  let fresh: Vec<u64> = (0..1_000).map(|_| generator.next().unwrap()).collect();
+  assert!(fresh.iter().all(|id| issued.iter().all(|old| id > old)));
+
+  // even when the skipped id is far ahead of the clock
+  let generator = IdGenerator::new(epoch, 3).unwrap();
+  let ahead = u64::MAX >> 8;
+  generator.skip_past(ahead);
+  assert!(generator.next().unwrap() > ahead);
+
+  // the epoch can't be in the future
+  let future = SystemTime::now() + Duration::from_secs(60);
+  match IdGenerator::new(future, 0) {
+    Err(IdError::EpochInFuture) => {}
+    _ => panic!("created a generator with an epoch in the future"),
+  }
+}
@@ -0,0 +1,77 @@
+// Test checking uploads and storing card media
+use lib_flashcard::media::local::LocalMediaStore;
+use lib_flashcard::media::memory::MemoryMediaStore;
+use lib_flashcard::media::{self, CardMedia, MediaError, MediaSide, MediaStore};
+
+use std::env;
+use std::fs;
+
+const PNG: &[u8] = &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0, 0, 0, 0];
+const WAV: &[u8] = b"RIFF\x24\x00\x00\x00WAVEfmt ";
+const WEBP: &[u8] = b"RIFF\x24\x00\x00\x00WEBPVP8 ";
+
+fn store_tests(store: &dyn MediaStore) {
+  assert!(store.ids().unwrap().is_empty());
+  store.put(1, PNG).unwrap();
+  store.put(2, WAV).unwrap();
+  assert_eq!(store.get(1).unwrap(), PNG);
+  let mut ids = store.ids().unwrap();
+  ids.sort();
+  assert_eq!(ids, vec![1, 2]);
+
+  // putting the same id again replaces the bytes
+  store.put(1, WEBP).unwrap();
+  assert_eq!(store.get(1).unwrap(), WEBP);
+
+  store.remove(1).unwrap();
+  store.remove(1).unwrap(); // already gone
+  match store.get(1) {
+    Err(MediaError::NotFound) => {}
+    _ => panic!("got removed media"),
+  }
+  assert_eq!(store.ids().unwrap(), vec![2]);
+}
+
+#[test]
+fn media_tests() {
+  // uploads have to be what they claim to be
+  assert_eq!(media::sniff_content_type(PNG), Some("image/png"));
+  assert_eq!(media::sniff_content_type(WAV), Some("audio/wav"));
+  assert_eq!(media::sniff_content_type(WEBP), Some("image/webp"));
+  assert_eq!(media::sniff_content_type(b"ID3\x04\x00"), Some("audio/mpeg"));
+  assert_eq!(media::sniff_content_type(b"OggS\x00\x02"), Some("audio/ogg"));
+  assert_eq!(media::sniff_content_type(b"<svg></svg>"), None);
+  assert_eq!(media::check_upload("image/png", PNG, 100).unwrap(), "image/png");
+  assert_eq!(media::check_upload("Audio/X-WAV; codecs=1", WAV, 100).unwrap(), "audio/wav");
+  match media::check_upload("image/jpeg", PNG, 100) {
+    Err(MediaError::UnsupportedType) => {}
+    _ => panic!("accepted bytes that don't match the declared type"),
+  }
+  match media::check_upload("text/html", b"<html></html>", 100) {
+    Err(MediaError::UnsupportedType) => {}
+    _ => panic!("accepted an unsupported type"),
+  }
+  match media::check_upload("image/png", PNG, 8) {
+    Err(MediaError::TooLarge) => {}
+    _ => panic!("accepted an upload over the limit"),
+  }
+
+  // attachments serialize their side by name
+  let card_media = CardMedia::new(1, 2, MediaSide::Answer, "image/png", 12).unwrap();
+  let json = serde_json::to_value(&card_media).unwrap();
+  assert_eq!(json["side"], "answer");
+  assert_eq!(json["card_id"], "2");
+  assert_eq!(MediaSide::from_name("question"), Some(MediaSide::Question));
+  assert_eq!(MediaSide::from_name("back"), None);
+
+  store_tests(&MemoryMediaStore::new());
+
+  // the directory is created on the first upload, and partial uploads aren't listed
+  let dir = env::temp_dir().join(format!("lib_flashcard_media_{}", std::process::id()));
+  let _ = fs::remove_dir_all(&dir);
+  let local = LocalMediaStore::new(&dir);
+  store_tests(&local);
+  fs::write(dir.join("3.partial"), PNG).unwrap();
+  assert_eq!(local.ids().unwrap(), vec![2]);
+  fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,327 @@
+// Test the in-memory storage backend
+use lib_flashcard::FlashManager;
+use lib_flashcard::card::choice::Distractor;
+use lib_flashcard::card::typed::AcceptedAnswer;
+use lib_flashcard::card::{Card, CardKind};
+use lib_flashcard::config::{DEFAULT_MAX_MEDIA_BYTES, TypingConfig};
+use lib_flashcard::db::memory::MemoryStore;
+use lib_flashcard::db::{
+  AnswerApi, CardApi, DBApiError, DeckApi, DeckRecords, DistractorApi, FlashStore, GameApi,
+  MediaApi, MemberApi,
+};
+use lib_flashcard::deck::{Deck, DeckMember, DeckRole};
+use lib_flashcard::game::queue::DEFAULT_NEW_CARDS_PER_QUEUE;
+use lib_flashcard::game::{CardScore, Review, SchedulerKind};
+use lib_flashcard::id::IdGenerator;
+use lib_flashcard::media::memory::MemoryMediaStore;
+use lib_flashcard::media::{CardMedia, MediaSide};
+
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+const NOW: u32 = 1_600_000_000;
+
+fn positions(store: &MemoryStore, deck_id: u64) -> Vec<(u64, u16)> {
+  CardApi::find_cards_for_deck(store, &deck_id)
+    .unwrap()
+    .iter()
+    .map(|card| (card.id, card.deck_pos))
+    .collect()
+}
+
+fn card(id: u64, deck_id: u64, deck_pos: u16) -> Card {
+  Card::new(id, deck_id, deck_pos, "Q".to_owned(), "A".to_owned()).unwrap()
+}
+
+#[test]
+fn memory_tests() {
+  let store = MemoryStore::new();
+
+  // a deck with 4 cards
+  let deck = Deck::new(1, 10, "memory_test".to_owned()).unwrap();
+  let cards: Vec<Card> = (1..=4).map(|pos| card(100 + pos, deck.id, pos as u16)).collect();
+  DeckApi::insert_with_cards(&store, &deck, &cards).unwrap();
+  assert_eq!(DeckApi::find(&store, &deck.id).unwrap().name(), "memory_test");
+
+  // position 0 is reserved, and positions are unique within a deck
+  match CardApi::insert(&store, &card(200, deck.id, 0)) {
+    Err(DBApiError::NotAllowed) => {}
+    _ => panic!("was able to insert a card at position 0"),
+  }
+  match CardApi::insert(&store, &card(200, deck.id, 2)) {
+    Err(DBApiError::OtherError(DieselError::DatabaseError(
+      DatabaseErrorKind::UniqueViolation,
+      _,
+    ))) => {}
+    _ => panic!("was able to insert two cards at the same position"),
+  }
+  match CardApi::update_position(&store, 101, deck.id, 1, 0) {
+    Err(DBApiError::NotAllowed) => {}
+    _ => panic!("was able to move a card to position 0"),
+  }
+
+  // move the last card to the front, then back to the end
+  CardApi::update_position(&store, 104, deck.id, 4, 1).unwrap();
+  assert_eq!(
+    positions(&store, deck.id),
+    vec![(104, 1), (101, 2), (102, 3), (103, 4)]
+  );
+  CardApi::update_position(&store, 104, deck.id, 1, 4).unwrap();
+  assert_eq!(
+    positions(&store, deck.id),
+    vec![(101, 1), (102, 2), (103, 3), (104, 4)]
+  );
+  match CardApi::update_position(&store, 101, deck.id, 3, 2) {
+    Err(DBApiError::NotFound) => {}
+    _ => panic!("moved a card from the wrong position"),
+  }
+
+  // scores and reviews
+  let mut score = CardScore::new(10, 102);
+  let state_before = score.state();
+  score.review(SchedulerKind::Sm2.scheduler(), 2, NOW);
+  let review = Review::new(300, &score, SchedulerKind::Sm2, &state_before, None).unwrap();
+  GameApi::update_score(&store, &score, &review).unwrap();
+  assert_eq!(GameApi::find_score(&store, 10, 102, 0).unwrap().score, 2);
+  let candidates = GameApi::get_study_candidates(&store, deck.id, 10, NOW).unwrap();
+  assert_eq!(candidates.len(), 3); // card 102 isn't due yet
+
+  // deleting a card closes the gap, and removes its scores and reviews
+  CardApi::delete(&store, &102).unwrap();
+  assert_eq!(positions(&store, deck.id), vec![(101, 1), (103, 2), (104, 3)]);
+  match GameApi::find_score(&store, 10, 102, 0) {
+    Err(DBApiError::NotFound) => {}
+    _ => panic!("score outlived its card"),
+  }
+  match CardApi::delete(&store, &102) {
+    Err(DBApiError::NotFound) => {}
+    _ => panic!("deleted a missing card"),
+  }
+
+  // every cloze deletion is scored on its own
+  let text = "{{c1::Paris}} is in {{c2::France}}".to_owned();
+  let mut cloze = Card::new(105, deck.id, 4, text, String::new()).unwrap();
+  cloze.set_kind(CardKind::Cloze);
+  CardApi::insert(&store, &cloze).unwrap();
+  let mut score = CardScore::for_item(10, 105, 2);
+  let state_before = score.state();
+  score.review(SchedulerKind::Sm2.scheduler(), 5, NOW);
+  let review = Review::new(310, &score, SchedulerKind::Sm2, &state_before, None).unwrap();
+  GameApi::update_score(&store, &score, &review).unwrap();
+  match GameApi::find_score(&store, 10, 105, 1) {
+    Err(DBApiError::NotFound) => {}
+    _ => panic!("scoring one deletion scored the other"),
+  }
+  let candidates = GameApi::get_study_candidates(&store, deck.id, 10, NOW).unwrap();
+  let cloze_items: Vec<u16> = candidates
+    .iter()
+    .filter(|(card, _item, _score)| card.id == 105)
+    .map(|(_card, item, _score)| *item)
+    .collect();
+  assert_eq!(cloze_items, vec![1]); // c2 isn't due yet
+
+  // multiple choice distractors are listed in order, and replaced all at once
+  let distractor =
+    |card_id, position, text: &str| Distractor::new(card_id, position, text.to_owned());
+  DistractorApi::insert(&store, &[distractor(101, 1, "C"), distractor(101, 0, "B")]).unwrap();
+  DistractorApi::insert(&store, &[distractor(103, 0, "D")]).unwrap();
+  let texts = |distractors: Vec<Distractor>| -> Vec<String> {
+    distractors.into_iter().map(|distractor| distractor.text).collect()
+  };
+  assert_eq!(texts(DistractorApi::find_for_card(&store, 101).unwrap()), vec!["B", "C"]);
+  let deck_distractors = DistractorApi::find_for_deck(&store, deck.id).unwrap();
+  assert_eq!(texts(deck_distractors), vec!["B", "C", "D"]);
+  DistractorApi::replace_for_card(&store, 101, &[distractor(101, 0, "E")]).unwrap();
+  assert_eq!(texts(DistractorApi::find_for_card(&store, 101).unwrap()), vec!["E"]);
+  DistractorApi::replace_for_card(&store, 103, &[]).unwrap();
+  assert!(DistractorApi::find_for_card(&store, 103).unwrap().is_empty());
+
+  // accepted answers work the same way
+  let answer =
+    |card_id, position, text: &str| AcceptedAnswer::new(card_id, position, text.to_owned());
+  AnswerApi::insert(&store, &[answer(101, 0, "F"), answer(103, 0, "G")]).unwrap();
+  let answers = [answer(101, 0, "H"), answer(101, 1, "I")];
+  AnswerApi::replace_for_card(&store, 101, &answers).unwrap();
+  let deck_answers = AnswerApi::find_for_deck(&store, deck.id).unwrap();
+  let answer_texts: Vec<String> = deck_answers.into_iter().map(|answer| answer.text).collect();
+  assert_eq!(answer_texts, vec!["H", "I", "G"]);
+  match DistractorApi::insert(&store, &[distractor(999, 0, "F")]) {
+    Err(DBApiError::OtherError(DieselError::DatabaseError(
+      DatabaseErrorKind::ForeignKeyViolation,
+      _,
+    ))) => {}
+    _ => panic!("added a distractor to a missing card"),
+  }
+
+  // media records are listed oldest first per card
+  let media = |id, card_id, side| CardMedia::new(id, card_id, side, "image/png", 8).unwrap();
+  MediaApi::insert(&store, &media(402, 101, MediaSide::Answer)).unwrap();
+  MediaApi::insert(&store, &media(401, 101, MediaSide::Question)).unwrap();
+  MediaApi::insert(&store, &media(403, 103, MediaSide::Question)).unwrap();
+  let media_ids = |media: Vec<CardMedia>| -> Vec<u64> { media.iter().map(|m| m.id).collect() };
+  assert_eq!(media_ids(MediaApi::find_for_card(&store, 101).unwrap()), vec![401, 402]);
+  assert_eq!(media_ids(MediaApi::find_for_deck(&store, deck.id).unwrap()), vec![401, 402, 403]);
+  assert_eq!(MediaApi::find(&store, 402).unwrap().side(), MediaSide::Answer);
+  let mut existing = MediaApi::find_existing(&store, &[400, 401, 403]).unwrap();
+  existing.sort();
+  assert_eq!(existing, vec![401, 403]);
+  MediaApi::delete(&store, 403).unwrap();
+  match MediaApi::delete(&store, 403) {
+    Err(DBApiError::NotFound) => {}
+    _ => panic!("deleted a missing media record"),
+  }
+  match MediaApi::insert(&store, &media(404, 999, MediaSide::Question)) {
+    Err(DBApiError::OtherError(DieselError::DatabaseError(
+      DatabaseErrorKind::ForeignKeyViolation,
+      _,
+    ))) => {}
+    _ => panic!("added media to a missing card"),
+  }
+
+  // the attachment limit is counted along with the insert
+  match MediaApi::insert_limited(&store, &media(404, 101, MediaSide::Question), 2) {
+    Err(DBApiError::NotAllowed) => {}
+    _ => panic!("went over the attachment limit"),
+  }
+  MediaApi::insert_limited(&store, &media(404, 103, MediaSide::Question), 2).unwrap();
+
+  // deleting the deck removes its cards and members
+  MemberApi::upsert(&store, &DeckMember::new(deck.id, 11, DeckRole::Viewer)).unwrap();
+  assert_eq!(DeckApi::find_decks_for_member(&store, &11).unwrap().len(), 1);
+  DeckApi::delete(&store, &deck.id).unwrap();
+  assert_eq!(DeckApi::find_decks_for_member(&store, &11).unwrap().len(), 0);
+  match CardApi::find(&store, &101) {
+    Err(DBApiError::NotFound) => {}
+    _ => panic!("card outlived its deck"),
+  }
+  assert!(DistractorApi::find_for_card(&store, 101).unwrap().is_empty());
+  assert!(AnswerApi::find_for_card(&store, 101).unwrap().is_empty());
+  assert!(MediaApi::find_existing(&store, &[401, 402]).unwrap().is_empty());
+  match DeckApi::delete(&store, &deck.id) {
+    Err(DBApiError::NotFound) => {}
+    _ => panic!("deleted a missing deck"),
+  }
+
+  // decks inserted together are stored together, or not at all
+  let first = Deck::new(50, 20, "first".to_owned()).unwrap();
+  let second = Deck::new(51, 20, "second".to_owned()).unwrap();
+  let records = [
+    DeckRecords::new(first.clone(), vec![card(501, first.id, 1)]),
+    DeckRecords::new(second.clone(), vec![card(501, second.id, 1)]),
+  ];
+  match DeckApi::insert_records(&store, &records) {
+    Err(DBApiError::OtherError(DieselError::DatabaseError(
+      DatabaseErrorKind::UniqueViolation,
+      _,
+    ))) => {}
+    _ => panic!("was able to insert two cards with the same id"),
+  }
+  assert!(DeckApi::find_decks_for_owner(&store, &20).unwrap().is_empty());
+  let mut with_media = DeckRecords::new(second.clone(), vec![card(502, second.id, 1)]);
+  with_media.media = vec![CardMedia::new(503, 502, MediaSide::Answer, "image/png", 8).unwrap()];
+  let records = [
+    DeckRecords::new(first.clone(), vec![card(501, first.id, 1)]),
+    with_media,
+  ];
+  DeckApi::insert_records(&store, &records).unwrap();
+  assert_eq!(DeckApi::find_decks_for_owner(&store, &20).unwrap().len(), 2);
+  assert_eq!(positions(&store, second.id), vec![(502, 1)]);
+  assert_eq!(MediaApi::find(&store, 503).unwrap().card_id, 502);
+
+  // the creator and accounts shared in as owners can delete a deck, editors can't
+  MemberApi::upsert(&store, &DeckMember::new(first.id, 21, DeckRole::Editor)).unwrap();
+  MemberApi::upsert(&store, &DeckMember::new(second.id, 21, DeckRole::Owner)).unwrap();
+  match DeckApi::delete_for_owner(&store, first.id, 21) {
+    Err(DBApiError::NotFound) => {}
+    _ => panic!("deleted a deck as an editor"),
+  }
+  DeckApi::delete_for_owner(&store, second.id, 21).unwrap();
+  DeckApi::delete_for_owner(&store, first.id, 20).unwrap();
+  assert!(DeckApi::find_decks_for_owner(&store, &20).unwrap().is_empty());
+
+  // account-scoped lookups and writes
+  let deck = Deck::new(2, 10, "scoped".to_owned()).unwrap();
+  DeckApi::insert_with_cards(&store, &deck, &[card(201, deck.id, 1), card(202, deck.id, 2)])
+    .unwrap();
+  MemberApi::upsert(&store, &DeckMember::new(deck.id, 11, DeckRole::Editor)).unwrap();
+  MemberApi::upsert(&store, &DeckMember::new(deck.id, 12, DeckRole::Viewer)).unwrap();
+  assert_eq!(DeckApi::find_for_account(&store, deck.id, 10).unwrap().1, Some(DeckRole::Owner));
+  assert_eq!(DeckApi::find_for_account(&store, deck.id, 13).unwrap().1, None);
+  let (found, found_deck, role) = CardApi::find_for_account(&store, 201, 12).unwrap();
+  assert_eq!((found.id, found_deck.id, role), (201, deck.id, Some(DeckRole::Viewer)));
+
+  // viewers and strangers can't change cards, editors can
+  for account_id in [12, 13].iter() {
+    match CardApi::update_for_editor(&store, 201, *account_id, Some("Q2"), None, None) {
+      Err(DBApiError::NotFound) => {}
+      _ => panic!("updated a card without being an editor"),
+    }
+    match CardApi::delete_for_editor(&store, 201, *account_id) {
+      Err(DBApiError::NotFound) => {}
+      _ => panic!("deleted a card without being an editor"),
+    }
+  }
+  CardApi::update_for_editor(&store, 201, 11, Some("Q2"), None, None).unwrap();
+  let updated = CardApi::find(&store, &201).unwrap();
+  assert_eq!((updated.question(), updated.answer()), ("Q2", "A"));
+  assert_eq!(updated.items(), vec![0]);
+  CardApi::update_for_editor(&store, 201, 11, None, None, Some(true)).unwrap();
+  let updated = CardApi::find(&store, &201).unwrap();
+  assert!(updated.is_reversible());
+  assert_eq!(updated.items(), vec![0, 1]);
+  CardApi::delete_for_editor(&store, 201, 11).unwrap();
+  assert_eq!(positions(&store, deck.id), vec![(202, 1)]);
+
+  // only the creator can delete the deck
+  match DeckApi::delete_for_owner(&store, deck.id, 11) {
+    Err(DBApiError::NotFound) => {}
+    _ => panic!("deleted a deck without owning it"),
+  }
+  DeckApi::delete_for_owner(&store, deck.id, 10).unwrap();
+
+  // the flash manager works on top of any store
+  let epoch = SystemTime::UNIX_EPOCH
+    .checked_add(Duration::from_millis(1546300800000)) // 01-01-2019 12:00:00 AM GMT
+    .expect("failed to create custom epoch");
+  let id_generator = IdGenerator::new(epoch, 0u8).expect("Failed to create ID generator");
+  let mut flash_manager = FlashManager::new(store, id_generator);
+  assert_ne!(flash_manager.new_id().unwrap(), flash_manager.new_id().unwrap());
+  assert_eq!(flash_manager.new_cards_per_queue(), DEFAULT_NEW_CARDS_PER_QUEUE);
+  assert_eq!(flash_manager.max_media_bytes(), DEFAULT_MAX_MEDIA_BYTES);
+  flash_manager.set_new_cards_per_queue(5);
+  assert_eq!(flash_manager.new_cards_per_queue(), 5);
+  assert_eq!(*flash_manager.typing(), TypingConfig::default());
+
+  // or on a store chosen at runtime
+  let boxed: Box<dyn FlashStore> = Box::new(flash_manager.db_manager);
+  let mut flash_manager = FlashManager::new(boxed, flash_manager.id_generator);
+  flash_manager.set_media_store(Box::new(MemoryMediaStore::new()));
+  let deck = Deck::new(flash_manager.new_id().unwrap(), 10, "boxed".to_owned()).unwrap();
+  DeckApi::insert(&flash_manager.db_manager, &deck).unwrap();
+  assert_eq!(DeckApi::find_decks_for_owner(&flash_manager.db_manager, &10).unwrap().len(), 1);
+
+  // stored bytes without a media record are swept away
+  let media_card = card(flash_manager.new_id().unwrap(), deck.id, 1);
+  CardApi::insert(&flash_manager.db_manager, &media_card).unwrap();
+  let kept = CardMedia::new(500, media_card.id, MediaSide::Question, "image/png", 1).unwrap();
+  MediaApi::insert(&flash_manager.db_manager, &kept).unwrap();
+  flash_manager.media_store().put(500, b"kept").unwrap();
+  flash_manager.media_store().put(501, b"orphan").unwrap();
+  assert_eq!(flash_manager.clean_orphaned_media().unwrap(), 1);
+  assert_eq!(flash_manager.media_store().ids().unwrap(), vec![500]);
+  assert_eq!(flash_manager.clean_orphaned_media().unwrap(), 0);
+
+  // and can be shared between threads without a lock
+  let flash_manager = Arc::new(flash_manager);
+  let handles: Vec<_> = (0..4)
+    .map(|_| {
+      let flash_manager = flash_manager.clone();
+      thread::spawn(move || flash_manager.new_id().unwrap())
+    })
+    .collect();
+  let ids: Vec<u64> = handles.into_iter().map(|handle| handle.join().unwrap()).collect();
+  assert_eq!(ids.iter().collect::<HashSet<_>>().len(), ids.len());
+}
@@ -0,0 +1,85 @@
+// Test study queue ordering
+use lib_flashcard::card::{Card, CardKind};
+use lib_flashcard::game::queue::{build_study_queue, expand_candidates};
+use lib_flashcard::game::{CardScore, SchedulerKind, StudyItemKind};
+
+const NOW: u32 = 1_600_000_000;
+
+fn card(id: u64) -> Card {
+  Card::new(id, 1, id as u16, "Q".to_owned(), "A".to_owned()).unwrap()
+}
+
+fn reviewed(card_id: u64, grade: u8, reviewed_at: u32) -> Option<CardScore> {
+  let mut score = CardScore::new(1, card_id);
+  score.review(SchedulerKind::Sm2.scheduler(), grade, reviewed_at);
+  Some(score)
+}
+
+#[test]
+fn queue_tests() {
+  let candidates = vec![
+    (card(1), 0, None),
+    (card(2), 0, reviewed(2, 5, NOW - 10 * 86_400)), // review, due 9 days ago
+    (card(3), 0, None),
+    (card(4), 0, reviewed(4, 0, NOW - 2 * 86_400)), // learning, due yesterday
+    (card(5), 0, reviewed(5, 5, NOW - 20 * 86_400)), // review, due 19 days ago
+    (card(6), 0, None),
+  ];
+
+  let queue = build_study_queue(candidates, 10, 2);
+  let ids: Vec<u64> = queue.iter().map(|item| item.card.id).collect();
+  // learning first, most overdue review next, new cards spread between, capped at 2
+  assert_eq!(ids, vec![1, 4, 5, 3, 2]);
+  assert_eq!(queue[0].kind, StudyItemKind::New);
+  assert_eq!(queue[1].kind, StudyItemKind::Learning);
+  assert_eq!(queue[2].kind, StudyItemKind::Review);
+
+  // the limit keeps room for new cards
+  let candidates = vec![
+    (card(1), 0, None),
+    (card(2), 0, reviewed(2, 5, NOW - 10 * 86_400)),
+    (card(3), 0, reviewed(3, 5, NOW - 10 * 86_400)),
+  ];
+  let queue = build_study_queue(candidates, 2, 1);
+  assert_eq!(queue.len(), 2);
+  assert_eq!(queue[0].kind, StudyItemKind::New);
+
+  // a failed card is learning whatever the scheduler, Leitner puts it back in box 1
+  let mut lapsed = CardScore::new(1, 2);
+  lapsed.review(SchedulerKind::Leitner.scheduler(), 5, NOW - 20 * 86_400);
+  lapsed.review(SchedulerKind::Leitner.scheduler(), 0, NOW - 2 * 86_400);
+  assert_eq!(lapsed.repetitions, 1);
+  let mut passed = CardScore::new(1, 3);
+  passed.review(SchedulerKind::Leitner.scheduler(), 5, NOW - 20 * 86_400);
+  let candidates = vec![(card(3), 0, Some(passed)), (card(2), 0, Some(lapsed))];
+  let queue = build_study_queue(candidates, 10, 0);
+  let ids: Vec<u64> = queue.iter().map(|item| item.card.id).collect();
+  assert_eq!(ids, vec![2, 3]);
+  assert_eq!(queue[0].kind, StudyItemKind::Learning);
+  assert_eq!(queue[1].kind, StudyItemKind::Review);
+
+  // cloze cards are expanded into one candidate per deletion that is new or due
+  let mut cloze = Card::new(
+    7,
+    1,
+    7,
+    "{{c1::Ottawa}} is the capital of {{c2::Canada::country}}".to_owned(),
+    String::new(),
+  )
+  .unwrap();
+  cloze.set_kind(CardKind::Cloze);
+  let mut due = CardScore::for_item(1, 7, 1);
+  due.review(SchedulerKind::Sm2.scheduler(), 5, NOW - 10 * 86_400); // due 9 days ago
+  let mut later = CardScore::for_item(1, 7, 2);
+  later.review(SchedulerKind::Sm2.scheduler(), 5, NOW); // not due again yet
+  let rows = vec![(card(1), None), (cloze.clone(), Some(due)), (cloze, Some(later))];
+  let candidates = expand_candidates(rows, NOW);
+  let items: Vec<(u64, u16)> = candidates
+    .iter()
+    .map(|(card, item, _score)| (card.id, *item))
+    .collect();
+  assert_eq!(items, vec![(1, 0), (7, 1)]);
+  let queue = build_study_queue(candidates, 10, 5);
+  assert_eq!(queue[1].question, "[...] is the capital of Canada");
+  assert_eq!(queue[1].answer.as_deref(), Some("Ottawa is the capital of Canada"));
+}
@@ -0,0 +1,82 @@
+// Test review schedulers
+use lib_flashcard::game::scheduler::{Fsrs, Leitner, Sm2};
+use lib_flashcard::game::{ReviewState, Scheduler, SchedulerKind};
+
+const DAY: u32 = 86_400;
+const NOW: u32 = 1_600_000_000;
+
+#[test]
+fn sm2_tests() {
+  let new = ReviewState::new();
+
+  // first two successful reviews use fixed intervals
+  let first = Sm2.schedule(&new, 5, NOW);
+  assert_eq!(first.repetitions, 1);
+  assert_eq!(first.interval, 1);
+  assert_eq!(first.due, NOW + DAY);
+  let second = Sm2.schedule(&first, 5, NOW + DAY);
+  assert_eq!(second.interval, 6);
+
+  // later intervals grow by the ease factor
+  let third = Sm2.schedule(&second, 5, NOW + 7 * DAY);
+  assert_eq!(third.interval, (6.0 * second.ease_factor).round() as u32);
+
+  // a failing grade resets repetitions but never drops ease below the minimum
+  let mut failed = third;
+  for _ in 0..10 {
+    failed = Sm2.schedule(&failed, 0, NOW);
+  }
+  assert_eq!(failed.repetitions, 0);
+  assert_eq!(failed.interval, 1);
+  assert_eq!(failed.ease_factor, Sm2::MIN_EASE_FACTOR);
+}
+
+#[test]
+fn leitner_tests() {
+  let mut state = ReviewState::new();
+  for expected in Leitner::BOX_INTERVALS.iter() {
+    state = Leitner.schedule(&state, 4, NOW);
+    assert_eq!(state.interval, *expected);
+  }
+  // the last box is sticky
+  state = Leitner.schedule(&state, 4, NOW);
+  assert_eq!(state.repetitions, Leitner::BOX_INTERVALS.len() as u16);
+
+  // failing sends the card back to the first box
+  state = Leitner.schedule(&state, 1, NOW);
+  assert_eq!(state.repetitions, 1);
+  assert_eq!(state.interval, 1);
+
+  // a card coming from SM-2 with a long streak lands in the last box
+  let streak = ReviewState { repetitions: u16::MAX, ..ReviewState::default() };
+  let state = Leitner.schedule(&streak, 4, NOW);
+  assert_eq!(state.repetitions, Leitner::BOX_INTERVALS.len() as u16);
+}
+
+#[test]
+fn fsrs_tests() {
+  let new = ReviewState::new();
+
+  // easier first ratings give longer first intervals
+  let again = Fsrs.schedule(&new, 0, NOW);
+  let good = Fsrs.schedule(&new, 4, NOW);
+  let easy = Fsrs.schedule(&new, 5, NOW);
+  assert!(again.interval <= good.interval);
+  assert!(good.interval < easy.interval);
+  assert!(easy.difficulty < again.difficulty);
+
+  // recalling on time increases stability, forgetting reduces it
+  let recalled = Fsrs.schedule(&good, 4, good.due);
+  assert!(recalled.stability > good.stability);
+  let forgot = Fsrs.schedule(&recalled, 0, recalled.due);
+  assert!(forgot.stability < recalled.stability);
+  assert_eq!(forgot.repetitions, 0);
+}
+
+#[test]
+fn scheduler_kind_tests() {
+  for kind in [SchedulerKind::Sm2, SchedulerKind::Leitner, SchedulerKind::Fsrs].iter() {
+    assert_eq!(SchedulerKind::from_id(kind.id()), Some(*kind));
+  }
+  assert_eq!(SchedulerKind::from_id(99), None);
+}
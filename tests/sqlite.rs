@@ -0,0 +1,187 @@
+// Test the SQLite storage backend against a temporary database file
+#![cfg(feature = "sqlite")]
+
+use lib_flashcard::card::choice::Distractor;
+use lib_flashcard::card::typed::AcceptedAnswer;
+use lib_flashcard::card::{BASIC_ITEM, Card, CardKind, REVERSE_ITEM};
+use lib_flashcard::db::sqlite::{SqliteManager, new_manager, run_migrations};
+use lib_flashcard::db::{AnswerApi, CardApi, DBApiError, DeckApi, DistractorApi, GameApi, MediaApi};
+use lib_flashcard::deck::Deck;
+use lib_flashcard::game::{CardScore, Review, SchedulerKind};
+use lib_flashcard::media::{CardMedia, MediaSide};
+
+use std::env;
+use std::fs;
+
+const NOW: u32 = 1_600_000_000;
+
+fn positions(db_manager: &SqliteManager, deck_id: u64) -> Vec<(u64, u16)> {
+  CardApi::find_cards_for_deck(db_manager, &deck_id)
+    .unwrap()
+    .iter()
+    .map(|card| (card.id, card.deck_pos))
+    .collect()
+}
+
+#[test]
+fn sqlite_tests() {
+  let path = env::temp_dir().join(format!("lib_flashcard_test_{}.db", std::process::id()));
+  let db_manager = new_manager(path.to_string_lossy().into_owned()).unwrap();
+  run_migrations(&db_manager).unwrap();
+
+  // a deck with 4 cards
+  let deck = Deck::new(1, 10, "sqlite_test".to_owned()).unwrap();
+  let cards: Vec<Card> = (1..=4)
+    .map(|pos| Card::new(100 + pos, deck.id, pos as u16, "Q".to_owned(), "A".to_owned()).unwrap())
+    .collect();
+  DeckApi::insert_with_cards(&db_manager, &deck, &cards).unwrap();
+  assert_eq!(DeckApi::find(&db_manager, &deck.id).unwrap().name(), "sqlite_test");
+  assert_eq!(DeckApi::find_decks_for_owner(&db_manager, &10).unwrap().len(), 1);
+
+  // position 0 is reserved
+  let reserved = Card::new(200, deck.id, 0, "Q".to_owned(), "A".to_owned()).unwrap();
+  match CardApi::insert(&db_manager, &reserved) {
+    Err(DBApiError::NotAllowed) => {}
+    _ => panic!("was able to insert a card at position 0"),
+  }
+
+  // move the last card to the front, then back to the end
+  CardApi::update_position(&db_manager, 104, deck.id, 4, 1).unwrap();
+  assert_eq!(
+    positions(&db_manager, deck.id),
+    vec![(104, 1), (101, 2), (102, 3), (103, 4)]
+  );
+  CardApi::update_position(&db_manager, 104, deck.id, 1, 4).unwrap();
+  assert_eq!(
+    positions(&db_manager, deck.id),
+    vec![(101, 1), (102, 2), (103, 3), (104, 4)]
+  );
+
+  // the card isn't where the caller expects it
+  match CardApi::update_position(&db_manager, 101, deck.id, 3, 2) {
+    Err(DBApiError::NotFound) => {}
+    _ => panic!("moved a card from the wrong position"),
+  }
+
+  // scores are upserted, and every update appends a review
+  let mut score = CardScore::new(10, 102);
+  for (i, grade) in [5u8, 2].iter().enumerate() {
+    let state_before = score.state();
+    score.review(SchedulerKind::Sm2.scheduler(), *grade, NOW + i as u32);
+    let review =
+      Review::new(300 + i as u64, &score, SchedulerKind::Sm2, &state_before, None).unwrap();
+    GameApi::update_score(&db_manager, &score, &review).unwrap();
+  }
+  assert_eq!(GameApi::find_score(&db_manager, 10, 102, 0).unwrap().score, 2);
+  assert_eq!(GameApi::get_deck_reviews(&db_manager, deck.id, 10).unwrap().len(), 2);
+  let candidates = GameApi::get_study_candidates(&db_manager, deck.id, 10, NOW).unwrap();
+  assert_eq!(candidates.len(), 3); // card 102 isn't due yet
+
+  // deleting a card closes the gap, and cascades to its scores and reviews
+  CardApi::delete(&db_manager, &102).unwrap();
+  assert_eq!(
+    positions(&db_manager, deck.id),
+    vec![(101, 1), (103, 2), (104, 3)]
+  );
+  assert_eq!(GameApi::get_deck_reviews(&db_manager, deck.id, 10).unwrap().len(), 0);
+
+  // every cloze deletion is scored on its own
+  let text = "{{c1::Paris}} is in {{c2::France}}".to_owned();
+  let mut cloze = Card::new(105, deck.id, 4, text, String::new()).unwrap();
+  cloze.set_kind(CardKind::Cloze);
+  CardApi::insert(&db_manager, &cloze).unwrap();
+  assert_eq!(CardApi::find(&db_manager, &105).unwrap().kind(), CardKind::Cloze);
+  let mut score = CardScore::for_item(10, 105, 2);
+  let state_before = score.state();
+  score.review(SchedulerKind::Sm2.scheduler(), 5, NOW);
+  let review = Review::new(310, &score, SchedulerKind::Sm2, &state_before, None).unwrap();
+  GameApi::update_score(&db_manager, &score, &review).unwrap();
+  assert_eq!(GameApi::find_score(&db_manager, 10, 105, 2).unwrap().score, 5);
+  match GameApi::find_score(&db_manager, 10, 105, 1) {
+    Err(DBApiError::NotFound) => {}
+    _ => panic!("scoring one deletion scored the other"),
+  }
+  let candidates = GameApi::get_study_candidates(&db_manager, deck.id, 10, NOW).unwrap();
+  let cloze_items: Vec<u16> = candidates
+    .iter()
+    .filter(|(card, _item, _score)| card.id == 105)
+    .map(|(_card, item, _score)| *item)
+    .collect();
+  assert_eq!(cloze_items, vec![1]); // c2 isn't due yet
+
+  // so is each side of a reversible card
+  let mut reversible = Card::new(106, deck.id, 5, "Q".to_owned(), "A".to_owned()).unwrap();
+  reversible.set_reversible(true);
+  CardApi::insert(&db_manager, &reversible).unwrap();
+  let mut score = CardScore::for_item(10, 106, REVERSE_ITEM);
+  let state_before = score.state();
+  score.review(SchedulerKind::Sm2.scheduler(), 5, NOW);
+  let review = Review::new(311, &score, SchedulerKind::Sm2, &state_before, None).unwrap();
+  GameApi::update_score(&db_manager, &score, &review).unwrap();
+  let candidates = GameApi::get_study_candidates(&db_manager, deck.id, 10, NOW).unwrap();
+  let reversible_items: Vec<u16> = candidates
+    .iter()
+    .filter(|(card, _item, _score)| card.id == 106)
+    .map(|(_card, item, _score)| *item)
+    .collect();
+  assert_eq!(reversible_items, vec![BASIC_ITEM]); // the reverse isn't due yet
+
+  // multiple choice distractors are listed in order, and replaced all at once
+  let distractor =
+    |card_id, position, text: &str| Distractor::new(card_id, position, text.to_owned());
+  DistractorApi::insert(&db_manager, &[distractor(101, 1, "C"), distractor(101, 0, "B")]).unwrap();
+  DistractorApi::insert(&db_manager, &[distractor(103, 0, "D")]).unwrap();
+  let texts = |distractors: Vec<Distractor>| -> Vec<String> {
+    distractors.into_iter().map(|distractor| distractor.text).collect()
+  };
+  assert_eq!(texts(DistractorApi::find_for_card(&db_manager, 101).unwrap()), vec!["B", "C"]);
+  let deck_distractors = DistractorApi::find_for_deck(&db_manager, deck.id).unwrap();
+  assert_eq!(texts(deck_distractors), vec!["B", "C", "D"]);
+  DistractorApi::replace_for_card(&db_manager, 101, &[distractor(101, 0, "E")]).unwrap();
+  assert_eq!(texts(DistractorApi::find_for_card(&db_manager, 101).unwrap()), vec!["E"]);
+  DistractorApi::replace_for_card(&db_manager, 103, &[]).unwrap();
+  assert!(DistractorApi::find_for_card(&db_manager, 103).unwrap().is_empty());
+
+  // accepted answers work the same way
+  let answer =
+    |card_id, position, text: &str| AcceptedAnswer::new(card_id, position, text.to_owned());
+  AnswerApi::insert(&db_manager, &[answer(101, 0, "F"), answer(103, 0, "G")]).unwrap();
+  let answers = [answer(101, 0, "H"), answer(101, 1, "I")];
+  AnswerApi::replace_for_card(&db_manager, 101, &answers).unwrap();
+  let deck_answers = AnswerApi::find_for_deck(&db_manager, deck.id).unwrap();
+  let answer_texts: Vec<String> = deck_answers.into_iter().map(|answer| answer.text).collect();
+  assert_eq!(answer_texts, vec!["H", "I", "G"]);
+
+  // media records are listed oldest first per card
+  let media = |id, card_id, side| CardMedia::new(id, card_id, side, "image/png", 8).unwrap();
+  MediaApi::insert(&db_manager, &media(402, 101, MediaSide::Answer)).unwrap();
+  MediaApi::insert(&db_manager, &media(401, 101, MediaSide::Question)).unwrap();
+  MediaApi::insert(&db_manager, &media(403, 103, MediaSide::Question)).unwrap();
+  let media_ids = |media: Vec<CardMedia>| -> Vec<u64> { media.iter().map(|m| m.id).collect() };
+  assert_eq!(media_ids(MediaApi::find_for_card(&db_manager, 101).unwrap()), vec![401, 402]);
+  let deck_media = MediaApi::find_for_deck(&db_manager, deck.id).unwrap();
+  assert_eq!(media_ids(deck_media), vec![401, 402, 403]);
+  let found = MediaApi::find(&db_manager, 402).unwrap();
+  assert_eq!((found.side(), found.content_type.as_str()), (MediaSide::Answer, "image/png"));
+  let mut existing = MediaApi::find_existing(&db_manager, &[400, 401, 403]).unwrap();
+  existing.sort();
+  assert_eq!(existing, vec![401, 403]);
+  MediaApi::delete(&db_manager, 403).unwrap();
+  match MediaApi::delete(&db_manager, 403) {
+    Err(DBApiError::NotFound) => {}
+    _ => panic!("deleted a missing media record"),
+  }
+
+  // deleting the deck removes its cards
+  DeckApi::delete(&db_manager, &deck.id).unwrap();
+  match CardApi::find(&db_manager, &101) {
+    Err(DBApiError::NotFound) => {}
+    _ => panic!("card outlived its deck"),
+  }
+  assert!(DistractorApi::find_for_card(&db_manager, 101).unwrap().is_empty());
+  assert!(AnswerApi::find_for_card(&db_manager, 101).unwrap().is_empty());
+  assert!(MediaApi::find_existing(&db_manager, &[401, 402]).unwrap().is_empty());
+
+  drop(db_manager);
+  fs::remove_file(path).unwrap();
+}
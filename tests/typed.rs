@@ -0,0 +1,77 @@
+// Test checking typed answers
+use lib_flashcard::card::typed::{self, AcceptedAnswer, DiffSegment, MAX_ACCEPTED_ANSWERS};
+use lib_flashcard::config::TypingConfig;
+
+fn same(text: &str) -> DiffSegment {
+  DiffSegment::Same(text.to_owned())
+}
+
+#[test]
+fn typed_tests() {
+  let config = TypingConfig::default();
+
+  // only whitespace differs
+  let check = typed::check("  to   speak ", &["to speak"], &config);
+  assert_eq!((check.grade, check.typos, check.correct), (5, 0, true));
+  assert_eq!(check.diff, vec![same("to speak")]);
+
+  // case, diacritics and punctuation are folded away, the diff shows the accepted answer
+  assert_eq!(typed::check("To Speak!", &["to speak"], &config).grade, 4);
+  let check = typed::check("Cafe", &["café"], &config);
+  assert_eq!(check.grade, 4);
+  assert_eq!(check.diff, vec![same("café")]);
+
+  // only the marks are folded away, never the letters they're written with
+  assert_eq!(typed::check("한국", &["한국"], &config).grade, 5);
+  assert_eq!(typed::check("한극", &["한국"], &config).grade, 1);
+  let check = typed::check("かっこう", &["がっこう"], &config);
+  assert_eq!(check.grade, 4);
+  assert_eq!(check.diff, vec![same("がっこう")]);
+  assert_eq!(typed::check("きっこう", &["がっこう"], &config).grade, 1);
+
+  // one typo for every 6 characters still passes
+  let check = typed::check("to speek", &["to speak"], &config);
+  assert_eq!((check.grade, check.typos, check.correct), (3, 1, true));
+  assert_eq!(
+    check.diff,
+    vec![
+      same("to spe"),
+      DiffSegment::Extra("e".to_owned()),
+      DiffSegment::Missing("a".to_owned()),
+      same("k"),
+    ]
+  );
+  let check = typed::check("to spak", &["to speak"], &config);
+  assert_eq!(check.diff, vec![same("to sp"), DiffSegment::Missing("e".to_owned()), same("ak")]);
+  let check = typed::check("to spook", &["to speak"], &config);
+  assert_eq!((check.grade, check.typos, check.correct), (1, 2, false));
+  assert_eq!(typed::check(" ", &["to speak"], &config).grade, 0);
+
+  // the closest accepted answer is graded
+  let check = typed::check("colour", &["color", "colour"], &config);
+  assert_eq!((check.grade, check.answer.as_str()), (5, "colour"));
+  let check = typed::check("colr", &["color", "colour"], &config);
+  assert_eq!(check.answer, "color");
+
+  // stricter settings
+  let strict = TypingConfig {
+    ignore_case: false,
+    ignore_diacritics: false,
+    ignore_punctuation: false,
+    chars_per_typo: 0,
+    max_typos: 0,
+  };
+  let check = typed::check("To speak", &["to speak"], &strict);
+  assert_eq!((check.grade, check.typos), (1, 1));
+  assert_eq!(typed::check("cafe", &["café"], &strict).grade, 1);
+  assert_eq!(typed::check("かっこう", &["がっこう"], &strict).grade, 1);
+  assert_eq!(typed::check("to speak!", &["to speak"], &strict).grade, 1);
+  assert_eq!(typed::check("to  speak", &["to speak"], &strict).grade, 5);
+
+  // accepted answers follow the same rules as distractors
+  let answers = typed::from_texts(1, "to speak", vec![" speak".to_owned()]).unwrap();
+  assert_eq!(answers, vec![AcceptedAnswer::new(1, 0, "speak".to_owned())]);
+  assert!(typed::from_texts(1, "to speak", vec!["to speak".to_owned()]).is_none());
+  let too_many: Vec<String> = (0..=MAX_ACCEPTED_ANSWERS).map(|n| n.to_string()).collect();
+  assert!(typed::from_texts(1, "to speak", too_many).is_none());
+}